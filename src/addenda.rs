@@ -0,0 +1,462 @@
+use crate::iat::{
+    IatBankAddendum, IatOriginatorAddendum, IatOriginatorAddressAddendum, IatPaymentAddendum,
+    IatReceiverAddendum, IatReceiverAddressAddendum,
+};
+use crate::layout::{notification_of_change_addendum, return_addendum, terminal_addendum};
+use crate::writer::{date, RecordWriter};
+use crate::{Currency, Line, ParseError, WriteError};
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Addenda type 02, describing the terminal behind a POS, SHR or MTE entry.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq)]
+pub struct TerminalAddendum {
+    pub reference_information_1: String,
+    pub reference_information_2: String,
+    pub terminal_identification_code: String,
+    pub transaction_serial_number: String,
+    /// `MMDD`
+    pub transaction_date: String,
+    pub authorization_code_or_card_expiration_date: String,
+    pub terminal_location: String,
+    pub terminal_city: String,
+    pub terminal_state: String,
+    pub trace_number: String,
+}
+
+impl TerminalAddendum {
+    pub(crate) fn from_line(line: &Line) -> Result<TerminalAddendum, ParseError> {
+        Ok(TerminalAddendum {
+            reference_information_1: line.alpha(terminal_addendum::REFERENCE_INFORMATION_1)?,
+            reference_information_2: line.alpha(terminal_addendum::REFERENCE_INFORMATION_2)?,
+            terminal_identification_code: line
+                .alpha(terminal_addendum::TERMINAL_IDENTIFICATION_CODE)?,
+            transaction_serial_number: line.alpha(terminal_addendum::TRANSACTION_SERIAL_NUMBER)?,
+            transaction_date: line.alpha(terminal_addendum::TRANSACTION_DATE)?,
+            authorization_code_or_card_expiration_date: line
+                .alpha(terminal_addendum::AUTHORIZATION_CODE_OR_CARD_EXPIRATION_DATE)?,
+            terminal_location: line.alpha(terminal_addendum::TERMINAL_LOCATION)?,
+            terminal_city: line.alpha(terminal_addendum::TERMINAL_CITY)?,
+            terminal_state: line.alpha(terminal_addendum::TERMINAL_STATE)?,
+            trace_number: line.alpha(terminal_addendum::TRACE_NUMBER)?,
+        })
+    }
+
+    fn write(&self, record: RecordWriter) -> Result<RecordWriter, WriteError> {
+        record
+            .put(
+                terminal_addendum::REFERENCE_INFORMATION_1,
+                &self.reference_information_1,
+            )?
+            .put(
+                terminal_addendum::REFERENCE_INFORMATION_2,
+                &self.reference_information_2,
+            )?
+            .put(
+                terminal_addendum::TERMINAL_IDENTIFICATION_CODE,
+                &self.terminal_identification_code,
+            )?
+            .put(
+                terminal_addendum::TRANSACTION_SERIAL_NUMBER,
+                &self.transaction_serial_number,
+            )?
+            .put(terminal_addendum::TRANSACTION_DATE, &self.transaction_date)?
+            .put(
+                terminal_addendum::AUTHORIZATION_CODE_OR_CARD_EXPIRATION_DATE,
+                &self.authorization_code_or_card_expiration_date,
+            )?
+            .put(
+                terminal_addendum::TERMINAL_LOCATION,
+                &self.terminal_location,
+            )?
+            .put(terminal_addendum::TERMINAL_CITY, &self.terminal_city)?
+            .put(terminal_addendum::TERMINAL_STATE, &self.terminal_state)?
+            .put(terminal_addendum::TRACE_NUMBER, &self.trace_number)
+    }
+}
+
+/// Addenda type 98, sent back by the RDFI to correct the account details
+/// of an earlier entry.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq)]
+pub struct NotificationOfChangeAddendum {
+    /// `C01` through `C14`
+    pub change_code: String,
+    pub original_entry_trace_number: String,
+    pub original_rdfi_id: String,
+    pub corrected_data: String,
+    pub trace_number: String,
+}
+
+impl NotificationOfChangeAddendum {
+    pub(crate) fn from_line(line: &Line) -> Result<NotificationOfChangeAddendum, ParseError> {
+        Ok(NotificationOfChangeAddendum {
+            change_code: line.alpha(notification_of_change_addendum::CHANGE_CODE)?,
+            original_entry_trace_number: line
+                .alpha(notification_of_change_addendum::ORIGINAL_ENTRY_TRACE_NUMBER)?,
+            original_rdfi_id: line.alpha(notification_of_change_addendum::ORIGINAL_RDFI_ID)?,
+            corrected_data: line.alpha(notification_of_change_addendum::CORRECTED_DATA)?,
+            trace_number: line.alpha(notification_of_change_addendum::TRACE_NUMBER)?,
+        })
+    }
+
+    fn write(&self, record: RecordWriter) -> Result<RecordWriter, WriteError> {
+        record
+            .put(
+                notification_of_change_addendum::CHANGE_CODE,
+                &self.change_code,
+            )?
+            .put(
+                notification_of_change_addendum::ORIGINAL_ENTRY_TRACE_NUMBER,
+                &self.original_entry_trace_number,
+            )?
+            .put(
+                notification_of_change_addendum::ORIGINAL_RDFI_ID,
+                &self.original_rdfi_id,
+            )?
+            .put(
+                notification_of_change_addendum::CORRECTED_DATA,
+                &self.corrected_data,
+            )?
+            .put(
+                notification_of_change_addendum::TRACE_NUMBER,
+                &self.trace_number,
+            )
+    }
+}
+
+/// Addenda type 99, sent back with a returned entry. Dishonored and
+/// contested returns pack their extra fields into `addenda_information`.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq)]
+pub struct ReturnAddendum {
+    /// `R01` through `R85`
+    pub return_reason_code: String,
+    pub original_entry_trace_number: String,
+    pub date_of_death: Option<NaiveDate>,
+    pub original_rdfi_id: String,
+    pub addenda_information: String,
+    pub trace_number: String,
+}
+
+impl ReturnAddendum {
+    pub(crate) fn from_line(line: &Line) -> Result<ReturnAddendum, ParseError> {
+        Ok(ReturnAddendum {
+            return_reason_code: line.alpha(return_addendum::RETURN_REASON_CODE)?,
+            original_entry_trace_number: line
+                .alpha(return_addendum::ORIGINAL_ENTRY_TRACE_NUMBER)?,
+            date_of_death: line.date(return_addendum::DATE_OF_DEATH)?,
+            original_rdfi_id: line.alpha(return_addendum::ORIGINAL_RDFI_ID)?,
+            addenda_information: line.alpha(return_addendum::ADDENDA_INFORMATION)?,
+            trace_number: line.alpha(return_addendum::TRACE_NUMBER)?,
+        })
+    }
+
+    fn write(&self, record: RecordWriter) -> Result<RecordWriter, WriteError> {
+        record
+            .put(
+                return_addendum::RETURN_REASON_CODE,
+                &self.return_reason_code,
+            )?
+            .put(
+                return_addendum::ORIGINAL_ENTRY_TRACE_NUMBER,
+                &self.original_entry_trace_number,
+            )?
+            .put(return_addendum::DATE_OF_DEATH, &date(&self.date_of_death))?
+            .put(return_addendum::ORIGINAL_RDFI_ID, &self.original_rdfi_id)?
+            .put(
+                return_addendum::ADDENDA_INFORMATION,
+                &self.addenda_information,
+            )?
+            .put(return_addendum::TRACE_NUMBER, &self.trace_number)
+    }
+}
+
+/// The structured contents of an addendum, picked by its addenda type code.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AddendaDetail {
+    /// 02
+    Terminal(TerminalAddendum),
+    /// 05, whose text is the addendum's `payment_related_info`
+    PaymentRelated,
+    /// 98
+    NotificationOfChange(NotificationOfChangeAddendum),
+    /// 99
+    Return(ReturnAddendum),
+    /// 10
+    IatPayment(IatPaymentAddendum),
+    /// 11
+    IatOriginator(IatOriginatorAddendum),
+    /// 12
+    IatOriginatorAddress(IatOriginatorAddressAddendum),
+    /// 13
+    IatOriginatingBank(IatBankAddendum),
+    /// 14
+    IatReceivingBank(IatBankAddendum),
+    /// 15
+    IatReceiver(IatReceiverAddendum),
+    /// 16
+    IatReceiverAddress(IatReceiverAddressAddendum),
+    /// 17, whose text is the addendum's `payment_related_info`
+    IatRemittance,
+    /// 18
+    IatForeignCorrespondentBank(IatBankAddendum),
+}
+
+impl AddendaDetail {
+    /// `None` for addenda types with no structure of their own
+    pub(crate) fn from_line(
+        addenda_type_code: &str,
+        line: &Line,
+    ) -> Result<Option<AddendaDetail>, ParseError> {
+        let detail = match addenda_type_code {
+            "02" => AddendaDetail::Terminal(TerminalAddendum::from_line(line)?),
+            "05" => AddendaDetail::PaymentRelated,
+            "98" => {
+                AddendaDetail::NotificationOfChange(NotificationOfChangeAddendum::from_line(line)?)
+            }
+            "99" => AddendaDetail::Return(ReturnAddendum::from_line(line)?),
+            "10" => AddendaDetail::IatPayment(IatPaymentAddendum::from_line(line)?),
+            "11" => AddendaDetail::IatOriginator(IatOriginatorAddendum::from_line(line)?),
+            "12" => {
+                AddendaDetail::IatOriginatorAddress(IatOriginatorAddressAddendum::from_line(line)?)
+            }
+            "13" => AddendaDetail::IatOriginatingBank(IatBankAddendum::from_line(line)?),
+            "14" => AddendaDetail::IatReceivingBank(IatBankAddendum::from_line(line)?),
+            "15" => AddendaDetail::IatReceiver(IatReceiverAddendum::from_line(line)?),
+            "16" => AddendaDetail::IatReceiverAddress(IatReceiverAddressAddendum::from_line(line)?),
+            "17" => AddendaDetail::IatRemittance,
+            "18" => AddendaDetail::IatForeignCorrespondentBank(IatBankAddendum::from_line(line)?),
+            _ => return Ok(None),
+        };
+        Ok(Some(detail))
+    }
+
+    /// the addenda type code this detail is written with
+    pub fn addenda_type_code(&self) -> &'static str {
+        match self {
+            AddendaDetail::Terminal(_) => "02",
+            AddendaDetail::PaymentRelated => "05",
+            AddendaDetail::NotificationOfChange(_) => "98",
+            AddendaDetail::Return(_) => "99",
+            AddendaDetail::IatPayment(_) => "10",
+            AddendaDetail::IatOriginator(_) => "11",
+            AddendaDetail::IatOriginatorAddress(_) => "12",
+            AddendaDetail::IatOriginatingBank(_) => "13",
+            AddendaDetail::IatReceivingBank(_) => "14",
+            AddendaDetail::IatReceiver(_) => "15",
+            AddendaDetail::IatReceiverAddress(_) => "16",
+            AddendaDetail::IatRemittance => "17",
+            AddendaDetail::IatForeignCorrespondentBank(_) => "18",
+        }
+    }
+
+    /// Types 05 and 17 are free text, kept in the addendum's
+    /// `payment_related_info` as for types with no detail at all.
+    pub fn is_free_text(&self) -> bool {
+        matches!(
+            self,
+            AddendaDetail::PaymentRelated | AddendaDetail::IatRemittance
+        )
+    }
+
+    /// Types 02, 98 and 99 end in a trace number where the others have
+    /// their sequence numbers.
+    pub fn trace_number(&self) -> Option<&str> {
+        match self {
+            AddendaDetail::Terminal(a) => Some(&a.trace_number),
+            AddendaDetail::NotificationOfChange(a) => Some(&a.trace_number),
+            AddendaDetail::Return(a) => Some(&a.trace_number),
+            _ => None,
+        }
+    }
+
+    /// Puts the detail's columns into `record`. Types 02, 98 and 99 include
+    /// their trace number; the sequence numbers of the others, and the text
+    /// of free text types, are left to the [`Addendum`](crate::Addendum).
+    pub(crate) fn write(&self, record: RecordWriter) -> Result<RecordWriter, WriteError> {
+        match self {
+            AddendaDetail::Terminal(a) => a.write(record),
+            AddendaDetail::PaymentRelated | AddendaDetail::IatRemittance => Ok(record),
+            AddendaDetail::NotificationOfChange(a) => a.write(record),
+            AddendaDetail::Return(a) => a.write(record),
+            AddendaDetail::IatPayment(a) => a.write(record),
+            AddendaDetail::IatOriginator(a) => a.write(record),
+            AddendaDetail::IatOriginatorAddress(a) => a.write(record),
+            AddendaDetail::IatOriginatingBank(a)
+            | AddendaDetail::IatReceivingBank(a)
+            | AddendaDetail::IatForeignCorrespondentBank(a) => a.write(record),
+            AddendaDetail::IatReceiver(a) => a.write(record),
+            AddendaDetail::IatReceiverAddress(a) => a.write(record),
+        }
+    }
+}
+
+/// a one line summary, as shown in the addenda popup
+impl fmt::Display for AddendaDetail {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AddendaDetail::Terminal(a) => write!(
+                f,
+                "terminal {} at {}, {} {}",
+                a.terminal_identification_code,
+                a.terminal_location,
+                a.terminal_city,
+                a.terminal_state
+            ),
+            AddendaDetail::PaymentRelated => write!(f, "payment related information"),
+            AddendaDetail::NotificationOfChange(a) => write!(
+                f,
+                "{} for {}: {}",
+                a.change_code, a.original_entry_trace_number, a.corrected_data
+            ),
+            AddendaDetail::Return(a) => write!(
+                f,
+                "{} for {} {}",
+                a.return_reason_code, a.original_entry_trace_number, a.addenda_information
+            ),
+            AddendaDetail::IatPayment(a) => write!(
+                f,
+                "{} {} to {} (foreign trace {})",
+                a.transaction_type_code,
+                a.foreign_payment_amount.pretty_dollars_cents(),
+                a.receiving_name,
+                a.foreign_trace_number
+            ),
+            AddendaDetail::IatOriginator(a) => write!(
+                f,
+                "originator: {}, {}",
+                a.originator_name, a.originator_street_address
+            ),
+            AddendaDetail::IatOriginatorAddress(a) => write!(
+                f,
+                "originator: {}, {}",
+                a.originator_city_state, a.originator_country_postal_code
+            ),
+            AddendaDetail::IatOriginatingBank(a) => write!(f, "ODFI: {}", a),
+            AddendaDetail::IatReceivingBank(a) => write!(f, "RDFI: {}", a),
+            AddendaDetail::IatReceiver(a) => write!(
+                f,
+                "receiver: {}, {}",
+                a.receiver_id_number, a.receiver_street_address
+            ),
+            AddendaDetail::IatReceiverAddress(a) => write!(
+                f,
+                "receiver: {}, {}",
+                a.receiver_city_state, a.receiver_country_postal_code
+            ),
+            AddendaDetail::IatRemittance => write!(f, "remittance information"),
+            AddendaDetail::IatForeignCorrespondentBank(a) => {
+                write!(f, "correspondent bank: {}", a)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Addendum;
+
+    const TERMINAL: &str =
+        "702REF0001A1 TERM010001230625AUTH01200 Main Street            Minneapolis    MN091012980000090";
+    const NOTIFICATION_OF_CHANGE: &str =
+        "798C01091000019999999      091012981918171614                                  091012980000088";
+    const RETURN: &str =
+        "799R1409100001999999919061109101298Deceased account holder                     091012980000089";
+
+    fn detail(line: &str) -> AddendaDetail {
+        let addendum = Addendum::parse(line, 1).unwrap();
+        // these types end in a trace number instead of sequence numbers
+        assert_eq!(addendum.addenda_sequence_number, "");
+        assert_eq!(addendum.entry_detail_sequence_number, "");
+        assert_eq!(addendum.payment_related_info, "");
+        assert_eq!(addendum.to_record().unwrap(), line);
+        addendum.detail.unwrap()
+    }
+
+    #[test]
+    fn reads_and_writes_terminal_addenda() {
+        assert_eq!(
+            detail(TERMINAL),
+            AddendaDetail::Terminal(TerminalAddendum {
+                reference_information_1: "REF0001".to_string(),
+                reference_information_2: "A1".to_string(),
+                terminal_identification_code: "TERM01".to_string(),
+                transaction_serial_number: "000123".to_string(),
+                transaction_date: "0625".to_string(),
+                authorization_code_or_card_expiration_date: "AUTH01".to_string(),
+                terminal_location: "200 Main Street".to_string(),
+                terminal_city: "Minneapolis".to_string(),
+                terminal_state: "MN".to_string(),
+                trace_number: "091012980000090".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn reads_and_writes_notifications_of_change() {
+        let parsed = detail(NOTIFICATION_OF_CHANGE);
+        assert_eq!(
+            parsed,
+            AddendaDetail::NotificationOfChange(NotificationOfChangeAddendum {
+                change_code: "C01".to_string(),
+                original_entry_trace_number: "091000019999999".to_string(),
+                original_rdfi_id: "09101298".to_string(),
+                corrected_data: "1918171614".to_string(),
+                trace_number: "091012980000088".to_string(),
+            })
+        );
+        assert_eq!(parsed.trace_number(), Some("091012980000088"));
+    }
+
+    #[test]
+    fn reads_and_writes_returns() {
+        let parsed = detail(RETURN);
+        assert_eq!(
+            parsed,
+            AddendaDetail::Return(ReturnAddendum {
+                return_reason_code: "R14".to_string(),
+                original_entry_trace_number: "091000019999999".to_string(),
+                date_of_death: NaiveDate::from_ymd_opt(2019, 6, 11),
+                original_rdfi_id: "09101298".to_string(),
+                addenda_information: "Deceased account holder".to_string(),
+                trace_number: "091012980000089".to_string(),
+            })
+        );
+        assert_eq!(parsed.trace_number(), Some("091012980000089"));
+
+        // most returns have no date of death
+        let line = RETURN.replace("R14091000019999999190611", "R01091000019999999      ");
+        match detail(&line) {
+            AddendaDetail::Return(r) => {
+                assert_eq!(r.return_reason_code, "R01");
+                assert_eq!(r.date_of_death, None);
+            }
+            other => panic!("expected a return, found {:?}", other),
+        }
+    }
+
+    #[test]
+    fn writes_edited_details_in_their_own_columns() {
+        let mut addendum = Addendum::parse(NOTIFICATION_OF_CHANGE, 1).unwrap();
+        addendum.detail = Some(AddendaDetail::NotificationOfChange(
+            NotificationOfChangeAddendum {
+                change_code: "C03".to_string(),
+                original_entry_trace_number: "091000010000001".to_string(),
+                original_rdfi_id: "12104288".to_string(),
+                corrected_data: "231380104   123456".to_string(),
+                trace_number: "091012980000091".to_string(),
+            },
+        ));
+        let written = addendum.to_record().unwrap();
+        assert_eq!(
+            written,
+            "798C03091000010000001      12104288231380104   123456                          091012980000091"
+        );
+        assert_eq!(
+            Addendum::parse(&written, 1).unwrap().detail,
+            addendum.detail
+        );
+    }
+}
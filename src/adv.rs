@@ -0,0 +1,34 @@
+//! Entries of automated accounting advice batches (SEC code `ADV`), which an
+//! ACH operator sends to tell an institution of the entries it settled. They
+//! have a shorter account number, a 12 digit amount, and routing details of
+//! the advice in place of the individual id and trace number.
+use crate::layout::adv_entry_detail;
+use crate::{Line, ParseError};
+use serde::{Deserialize, Serialize};
+
+/// The parts of an ADV entry detail that differ from other entries. The
+/// account number, amount and name are held in
+/// [`DetailEntry`](crate::DetailEntry) as for any entry.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq)]
+pub struct AdvEntryDetail {
+    pub advice_routing_number: String,
+    pub file_identification: String,
+    pub ach_operator_data: String,
+    pub ach_operator_routing_number: String,
+    pub julian_date_created: String,
+    pub sequence_number: String,
+}
+
+impl AdvEntryDetail {
+    pub(crate) fn from_line(line: &Line) -> Result<AdvEntryDetail, ParseError> {
+        Ok(AdvEntryDetail {
+            advice_routing_number: line.alpha(adv_entry_detail::ADVICE_ROUTING_NUMBER)?,
+            file_identification: line.alpha(adv_entry_detail::FILE_IDENTIFICATION)?,
+            ach_operator_data: line.alpha(adv_entry_detail::ACH_OPERATOR_DATA)?,
+            ach_operator_routing_number: line
+                .alpha(adv_entry_detail::ACH_OPERATOR_ROUTING_NUMBER)?,
+            julian_date_created: line.alpha(adv_entry_detail::JULIAN_DATE_CREATED)?,
+            sequence_number: line.alpha(adv_entry_detail::SEQUENCE_NUMBER)?,
+        })
+    }
+}
@@ -0,0 +1,191 @@
+use serde::de::{self, Visitor};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use std::str::FromStr;
+use thousands::Separable;
+
+/// An amount of money in cents. Serializes as an integer number of cents and
+/// deserializes from either that or a `"1234.56"` dollar string.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct Amount(u64);
+
+impl Amount {
+    pub const ZERO: Amount = Amount(0);
+
+    pub fn from_cents(cents: u64) -> Amount {
+        Amount(cents)
+    }
+
+    pub fn cents(&self) -> u64 {
+        self.0
+    }
+
+    pub fn checked_add(self, other: Amount) -> Option<Amount> {
+        self.0.checked_add(other.0).map(Amount)
+    }
+
+    pub fn checked_sub(self, other: Amount) -> Option<Amount> {
+        self.0.checked_sub(other.0).map(Amount)
+    }
+
+    pub fn saturating_add(self, other: Amount) -> Amount {
+        Amount(self.0.saturating_add(other.0))
+    }
+}
+
+/// `1234.56`, with no thousands separators
+impl fmt::Display for Amount {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{:02}", self.0 / 100, self.0 % 100)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvalidAmount(pub String);
+
+impl fmt::Display for InvalidAmount {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid amount {:?}", self.0)
+    }
+}
+
+impl std::error::Error for InvalidAmount {}
+
+/// Parses a dollar amount such as `1234.56`, `1,234.5` or `1234`.
+impl FromStr for Amount {
+    type Err = InvalidAmount;
+
+    fn from_str(s: &str) -> Result<Amount, InvalidAmount> {
+        let invalid = || InvalidAmount(s.to_string());
+        let cleaned: String = s.trim().chars().filter(|c| *c != ',').collect();
+        let (dollars, cents) = match cleaned.split_once('.') {
+            Some((d, c)) => (d, c),
+            None => (cleaned.as_str(), ""),
+        };
+        let all_digits = |s: &str| s.bytes().all(|b| b.is_ascii_digit());
+        if dollars.is_empty() || !all_digits(dollars) || cents.len() > 2 || !all_digits(cents) {
+            return Err(invalid());
+        }
+        let dollars: u64 = dollars.parse().map_err(|_| invalid())?;
+        let cents: u64 = format!("{:0<2}", cents).parse().map_err(|_| invalid())?;
+        dollars
+            .checked_mul(100)
+            .and_then(|d| d.checked_add(cents))
+            .map(Amount)
+            .ok_or_else(invalid)
+    }
+}
+
+impl Serialize for Amount {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u64(self.0)
+    }
+}
+
+struct AmountVisitor;
+
+impl<'de> Visitor<'de> for AmountVisitor {
+    type Value = Amount;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "an integer number of cents or a dollar string like \"1234.56\""
+        )
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> Result<Amount, E> {
+        Ok(Amount(v))
+    }
+
+    fn visit_i64<E: de::Error>(self, v: i64) -> Result<Amount, E> {
+        u64::try_from(v)
+            .map(Amount)
+            .map_err(|_| E::custom("amounts can't be negative"))
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<Amount, E> {
+        v.parse().map_err(E::custom)
+    }
+}
+
+impl<'de> Deserialize<'de> for Amount {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Amount, D::Error> {
+        deserializer.deserialize_any(AmountVisitor)
+    }
+}
+
+pub trait Currency {
+    fn pretty_dollars_cents(&self) -> String;
+}
+
+impl Currency for Amount {
+    fn pretty_dollars_cents(&self) -> String {
+        format!(
+            "{}.{:02}",
+            (self.0 / 100).separate_with_commas(),
+            self.0 % 100
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(s: &str) -> Result<u64, InvalidAmount> {
+        s.parse::<Amount>().map(|a| a.cents())
+    }
+
+    #[test]
+    fn parses_dollar_strings() {
+        assert_eq!(parse("1234.56"), Ok(123456));
+        assert_eq!(parse("1,234.5"), Ok(123450));
+        assert_eq!(parse("1234"), Ok(123400));
+        assert_eq!(parse("1."), Ok(100));
+        assert_eq!(parse("0.01"), Ok(1));
+        assert_eq!(parse(" 12.30 "), Ok(1230));
+        assert_eq!(parse("0"), Ok(0));
+    }
+
+    #[test]
+    fn rejects_malformed_amounts() {
+        for s in ["", ".5", "-1", "+1", "1.234", "1.2.3", "12a", "1e3", "１２"] {
+            assert_eq!(parse(s), Err(InvalidAmount(s.to_string())), "{:?}", s);
+        }
+        // more cents than fit in a u64
+        assert!(parse("184467440737095516.16").is_err());
+        assert_eq!(parse("184467440737095516.15"), Ok(u64::MAX));
+    }
+
+    #[test]
+    fn formats_cents() {
+        assert_eq!(Amount::from_cents(5).to_string(), "0.05");
+        assert_eq!(Amount::from_cents(123456).to_string(), "1234.56");
+        assert_eq!(Amount::ZERO.pretty_dollars_cents(), "0.00");
+        assert_eq!(
+            Amount::from_cents(123456789).pretty_dollars_cents(),
+            "1,234,567.89"
+        );
+        let amount = Amount::from_cents(100000070);
+        assert_eq!(amount.to_string().parse(), Ok(amount));
+    }
+
+    #[test]
+    fn arithmetic_doesnt_wrap() {
+        let max = Amount::from_cents(u64::MAX);
+        assert_eq!(max.checked_add(Amount::from_cents(1)), None);
+        assert_eq!(max.saturating_add(Amount::from_cents(1)), max);
+        assert_eq!(Amount::ZERO.checked_sub(Amount::from_cents(1)), None);
+    }
+
+    #[test]
+    fn deserializes_cents_or_dollars() {
+        let cents: Amount = serde_json::from_str("123456").unwrap();
+        let dollars: Amount = serde_json::from_str("\"1,234.56\"").unwrap();
+        assert_eq!(cents, dollars);
+        assert_eq!(serde_json::to_string(&cents).unwrap(), "123456");
+        assert!(serde_json::from_str::<Amount>("-1").is_err());
+        assert!(serde_json::from_str::<Amount>("\"12.345\"").is_err());
+    }
+}
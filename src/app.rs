@@ -1,28 +1,393 @@
-use crate::lib::{Addendum, NachaFile};
+use crate::filter::{search_matches, Filter};
 use crate::term::DetailEntryWithCounter;
-use tui::widgets::TableState;
+use nacha::{layout, Addendum, EntryLayout, Field, NachaFile};
+use std::cmp::Ordering;
+use tui::widgets::{ListState, TableState};
+
+/// which pane the movement keys go to
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Focus {
+    Batches,
+    Entries,
+}
+
+/// the column the entry table is sorted by
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum SortKey {
+    /// the order of the file
+    Original,
+    Amount,
+    Name,
+    Trace,
+    TransactionCode,
+}
+
+impl SortKey {
+    /// the next key `s` moves to
+    fn next(self) -> SortKey {
+        match self {
+            SortKey::Original => SortKey::Amount,
+            SortKey::Amount => SortKey::Name,
+            SortKey::Name => SortKey::Trace,
+            SortKey::Trace => SortKey::TransactionCode,
+            SortKey::TransactionCode => SortKey::Original,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            SortKey::Original => "file order",
+            SortKey::Amount => "amount",
+            SortKey::Name => "name",
+            SortKey::Trace => "trace #",
+            SortKey::TransactionCode => "txn code",
+        }
+    }
+
+    fn compare(self, a: &DetailEntryWithCounter, b: &DetailEntryWithCounter) -> Ordering {
+        match self {
+            SortKey::Original => a.counter.cmp(&b.counter),
+            SortKey::Amount => a.entry.amount.cmp(&b.entry.amount),
+            SortKey::Name => a.entry.receiver_name().cmp(b.entry.receiver_name()),
+            SortKey::Trace => a.entry.trace_number.cmp(&b.entry.trace_number),
+            SortKey::TransactionCode => a
+                .entry
+                .transaction_code
+                .as_str()
+                .cmp(b.entry.transaction_code.as_str()),
+        }
+    }
+}
+
+/// what typed characters go to
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum InputMode {
+    /// shortcuts
+    Normal,
+    /// the search query, after `/`
+    Search,
+    /// the filter, after `f`
+    Filter,
+}
+
 pub struct App<'a> {
     pub should_quit: bool,
+    /// every entry in the file, in file order
+    pub all_entries: Vec<DetailEntryWithCounter>,
+    /// the entries shown in the table, i.e. those of the selected batch
     pub entries: StatefulTable<DetailEntryWithCounter>,
-    pub entry_count: usize,
     pub nacha_file: &'a mut NachaFile,
     pub show_popup: bool,
     pub addenda_popup: StatefulTable<Addendum>,
+    /// the full screen view of the selected entry, opened with enter
+    pub show_detail: bool,
+    /// lines scrolled down in the detail view
+    pub detail_scroll: u16,
+    /// the lines of the file holding each entry and its addenda, by counter
+    raw_records: Vec<Vec<(usize, String)>>,
+    /// the original lines of the selected entry, opened with `x`
+    pub show_raw: bool,
+    /// which of the entry's lines the cursor is on
+    pub raw_record: usize,
+    /// which field of that line the cursor is on
+    pub raw_field: usize,
+    pub focus: Focus,
+    /// the batch tree, where 0 is every batch and `i + 1` is batch `i`
+    pub batches: ListState,
+    /// which batches of the tree show their header and control summary
+    pub expanded: Vec<bool>,
+    pub input_mode: InputMode,
+    /// the search or filter being typed
+    pub input: String,
+    /// the last search, which `n` and `N` jump between matches of
+    pub search: String,
+    /// the selected entry when the search started, to go back to on `esc`
+    search_origin: Option<usize>,
+    pub filter: Option<Filter>,
+    pub sort: SortKey,
+    pub descending: bool,
+    /// a filter that didn't parse, shown until the next key
+    pub message: Option<String>,
 }
 
 impl<'a> App<'a> {
-    pub fn new(nacha_file: &'a mut NachaFile, entries: Vec<DetailEntryWithCounter>) -> App<'a> {
-        let count = entries.len();
+    pub fn new(
+        nacha_file: &'a mut NachaFile,
+        entries: Vec<DetailEntryWithCounter>,
+        raw_records: Vec<Vec<(usize, String)>>,
+    ) -> App<'a> {
+        let mut batches = ListState::default();
+        batches.select(Some(0));
+        let expanded = vec![false; nacha_file.batches.len()];
         App {
             should_quit: false,
-            nacha_file: nacha_file,
-            entries: StatefulTable::with_items(entries),
-            entry_count: count,
+            nacha_file,
+            entries: StatefulTable::with_items(entries.clone()),
+            all_entries: entries,
             show_popup: false,
             addenda_popup: StatefulTable::new(),
+            show_detail: false,
+            detail_scroll: 0,
+            raw_records,
+            show_raw: false,
+            raw_record: 0,
+            raw_field: 0,
+            focus: Focus::Entries,
+            batches,
+            expanded,
+            input_mode: InputMode::Normal,
+            input: String::new(),
+            search: String::new(),
+            search_origin: None,
+            filter: None,
+            sort: SortKey::Original,
+            descending: false,
+            message: None,
+        }
+    }
+
+    /// the batch the entry table is narrowed to, `None` for all of them
+    pub fn selected_batch(&self) -> Option<usize> {
+        self.batches.selected().and_then(|i| i.checked_sub(1))
+    }
+
+    /// Rebuilds the entry table from the selected batch and the filter, in
+    /// the order of the sort.
+    fn refresh_entries(&mut self) {
+        let batch = self.selected_batch();
+        let batches = &self.nacha_file.batches;
+        let filter = &self.filter;
+        let items = self
+            .all_entries
+            .iter()
+            .filter(|e| batch.map_or(true, |b| e.batch == b))
+            .filter(|e| {
+                filter.as_ref().map_or(true, |f| {
+                    f.matches(&e.entry, &batches[e.batch].batch_header)
+                })
+            })
+            .cloned();
+        let mut items: Vec<_> = items.collect();
+        let (sort, descending) = (self.sort, self.descending);
+        // ties stay in file order either way
+        items.sort_by(|a, b| {
+            let ordering = sort.compare(a, b);
+            let ordering = if descending {
+                ordering.reverse()
+            } else {
+                ordering
+            };
+            ordering.then(a.counter.cmp(&b.counter))
+        });
+        self.entries = StatefulTable::with_items(items);
+    }
+
+    pub fn selected_entry(&self) -> Option<&DetailEntryWithCounter> {
+        self.entries
+            .state
+            .selected()
+            .and_then(|i| self.entries.items.get(i))
+    }
+
+    /// The line numbers and text of the selected entry and its addenda, as
+    /// they are in the file. Entries that can't be found there are written
+    /// out afresh, with no line number.
+    pub fn selected_records(&self) -> Vec<(Option<usize>, String)> {
+        let Some(e) = self.selected_entry() else {
+            return Vec::new();
+        };
+        match self.raw_records.get(e.counter as usize - 1) {
+            Some(lines) => lines.iter().map(|(n, l)| (Some(*n), l.clone())).collect(),
+            None => std::iter::once(e.entry.to_record())
+                .chain(e.entry.addenda.iter().map(|a| a.to_record()))
+                .map(|l| (None, l.unwrap_or_else(|e| e.to_string())))
+                .collect(),
+        }
+    }
+
+    /// the layout of a line of the selected entry
+    pub fn record_fields(&self, record: &str) -> &'static [Field] {
+        let entries = self.selected_entry().map_or(EntryLayout::Standard, |e| {
+            self.nacha_file.batches[e.batch].batch_header.entry_layout()
+        });
+        layout::for_record(record, entries).unwrap_or_default()
+    }
+
+    /// Moves the raw view's cursor by `records` lines and `fields` fields,
+    /// staying within the entry's lines and the fields of the line.
+    fn move_raw_cursor(&mut self, records: isize, fields: isize) {
+        let lines = self.selected_records();
+        if lines.is_empty() {
+            return;
+        }
+        self.raw_record = self
+            .raw_record
+            .saturating_add_signed(records)
+            .min(lines.len() - 1);
+        let count = self.record_fields(&lines[self.raw_record].1).len();
+        self.raw_field = self
+            .raw_field
+            .saturating_add_signed(fields)
+            .min(count.saturating_sub(1));
+    }
+
+    /// whether the entry matches the current search
+    pub fn is_match(&self, e: &DetailEntryWithCounter) -> bool {
+        !self.search.is_empty() && search_matches(&e.entry, &self.search)
+    }
+
+    /// Selects the next entry matching the search, starting at `from` and
+    /// wrapping around, going backwards if `forward` isn't set.
+    fn find(&mut self, from: usize, forward: bool) {
+        let len = self.entries.items.len();
+        let found = (0..len)
+            .map(|i| {
+                if forward {
+                    (from + i) % len
+                } else {
+                    (from + len - i % len) % len
+                }
+            })
+            .find(|&i| self.is_match(&self.entries.items[i]));
+        if let Some(i) = found {
+            self.entries.state.select(Some(i));
+        }
+    }
+
+    fn next_match(&mut self, forward: bool) {
+        let len = self.entries.items.len();
+        if len == 0 {
+            return;
+        }
+        let current = self.entries.state.selected().unwrap_or(0);
+        let from = if forward {
+            (current + 1) % len
+        } else {
+            (current + len - 1) % len
+        };
+        self.find(from, forward);
+    }
+
+    fn open_raw(&mut self) {
+        if self.selected_entry().is_some() {
+            self.show_detail = false;
+            self.show_raw = true;
+            self.raw_record = 0;
+            self.raw_field = 0;
+        }
+    }
+
+    /// Starts typing a search, or a filter prefilled with the current one.
+    fn start_input(&mut self, mode: InputMode) {
+        self.input_mode = mode;
+        self.input = match mode {
+            InputMode::Filter => self.filter.as_ref().map(|f| f.to_string()),
+            _ => None,
+        }
+        .unwrap_or_default();
+        if mode == InputMode::Search {
+            self.search_origin = self.entries.state.selected();
+        }
+    }
+
+    /// Handles a character typed into the search or filter. Searches are
+    /// incremental, jumping to the first match as the query is typed.
+    fn on_input(&mut self, c: char) {
+        self.input.push(c);
+        if self.input_mode == InputMode::Search {
+            self.search = self.input.clone();
+            self.find(self.search_origin.unwrap_or(0), true);
+        }
+    }
+
+    pub fn on_backspace(&mut self) {
+        if self.input_mode == InputMode::Normal {
+            return;
+        }
+        self.input.pop();
+        if self.input_mode == InputMode::Search {
+            self.search = self.input.clone();
+            self.find(self.search_origin.unwrap_or(0), true);
+        }
+    }
+
+    /// Closes the detail or raw view, or leaves the search, going back to where it
+    /// started, or the filter, leaving the old one in place.
+    pub fn on_esc(&mut self) {
+        self.show_detail = false;
+        self.show_raw = false;
+        if self.input_mode == InputMode::Search {
+            self.search.clear();
+            self.entries.state.select(self.search_origin);
+        }
+        self.input_mode = InputMode::Normal;
+        self.input.clear();
+    }
+
+    /// Applies the filter being typed, clearing it when the input is empty.
+    fn apply_filter(&mut self) {
+        match Filter::parse(&self.input) {
+            Ok(filter) if filter.to_string().is_empty() => self.filter = None,
+            Ok(filter) => self.filter = Some(filter),
+            Err(e) => {
+                self.message = Some(e);
+                return;
+            }
         }
+        self.refresh_entries();
     }
+
+    fn select_batch(&mut self, i: usize) {
+        self.batches.select(Some(i));
+        self.refresh_entries();
+    }
+
+    fn next_batch(&mut self) {
+        let len = self.nacha_file.batches.len() + 1;
+        let i = self.batches.selected().map_or(0, |i| (i + 1) % len);
+        self.select_batch(i);
+    }
+
+    fn previous_batch(&mut self) {
+        let len = self.nacha_file.batches.len() + 1;
+        let i = self.batches.selected().map_or(0, |i| (i + len - 1) % len);
+        self.select_batch(i);
+    }
+
+    fn toggle_batch(&mut self) {
+        if let Some(b) = self.selected_batch() {
+            self.expanded[b] = !self.expanded[b];
+        }
+    }
+
     pub fn on_key(&mut self, c: char) {
+        self.message = None;
+        if self.input_mode != InputMode::Normal {
+            self.on_input(c);
+            return;
+        }
+        if self.show_raw {
+            match c {
+                'q' => self.should_quit = true,
+                'x' => self.show_raw = false,
+                'j' => self.move_raw_cursor(1, 0),
+                'k' => self.move_raw_cursor(-1, 0),
+                'l' => self.move_raw_cursor(0, 1),
+                'h' => self.move_raw_cursor(0, -1),
+                _ => {}
+            }
+            return;
+        }
+        if self.show_detail {
+            match c {
+                'x' => self.open_raw(),
+                'q' => self.should_quit = true,
+                'j' => self.detail_scroll = self.detail_scroll.saturating_add(1),
+                'k' => self.detail_scroll = self.detail_scroll.saturating_sub(1),
+                _ => {}
+            }
+            return;
+        }
         match c {
             'q' => {
                 self.should_quit = true;
@@ -30,6 +395,8 @@ impl<'a> App<'a> {
             'j' => {
                 if self.show_popup {
                     self.addenda_popup.next();
+                } else if self.focus == Focus::Batches {
+                    self.next_batch();
                 } else {
                     self.entries.next();
                 }
@@ -37,6 +404,8 @@ impl<'a> App<'a> {
             'k' => {
                 if self.show_popup {
                     self.addenda_popup.previous();
+                } else if self.focus == Focus::Batches {
+                    self.previous_batch();
                 } else {
                     self.entries.previous();
                 }
@@ -44,51 +413,111 @@ impl<'a> App<'a> {
             'h' => {
                 if self.show_popup {
                     self.addenda_popup.jump_previous();
-                } else {
+                } else if self.focus == Focus::Entries {
                     self.entries.jump_previous();
                 }
             }
             'l' => {
                 if self.show_popup {
                     self.addenda_popup.jump_next();
-                } else {
+                } else if self.focus == Focus::Entries {
                     self.entries.jump_next();
                 }
             }
+            '/' if !self.show_popup => self.start_input(InputMode::Search),
+            'f' if !self.show_popup => self.start_input(InputMode::Filter),
+            'n' if !self.show_popup => self.next_match(true),
+            'N' if !self.show_popup => self.next_match(false),
+            'x' if !self.show_popup && self.focus == Focus::Entries => self.open_raw(),
+            's' if !self.show_popup => {
+                self.sort = self.sort.next();
+                self.refresh_entries();
+            }
+            'r' if !self.show_popup => {
+                self.descending = !self.descending;
+                self.refresh_entries();
+            }
+            'o' if self.focus == Focus::Batches => self.toggle_batch(),
             'o' => {
-                match self.entries.state.selected() {
-                    Some(i) => {
-                        if self.entries.items[i].entry.has_addenda() {
-                            self.show_popup = !self.show_popup;
-                        }
-                        if self.addenda_popup.has_items() && !self.show_popup {
-                            self.addenda_popup.clear_items();
-                        } else if !self.addenda_popup.has_items() && self.show_popup {
-                            self.addenda_popup
-                                .add_items(self.entries.items[i].entry.addenda.clone())
-                        }
+                if let Some(i) = self.entries.state.selected() {
+                    let Some(item) = self.entries.items.get(i) else {
+                        return;
+                    };
+                    if item.entry.has_addenda() {
+                        self.show_popup = !self.show_popup;
+                    }
+                    if self.addenda_popup.has_items() && !self.show_popup {
+                        self.addenda_popup.clear_items();
+                    } else if !self.addenda_popup.has_items() && self.show_popup {
+                        self.addenda_popup.add_items(item.entry.addenda.clone())
                     }
-                    None => {}
                 };
             }
             _ => {}
         }
     }
 
+    /// switches between the batch tree and the entry table
+    pub fn on_tab(&mut self) {
+        if !self.show_popup
+            && !self.show_detail
+            && !self.show_raw
+            && self.input_mode == InputMode::Normal
+        {
+            self.focus = match self.focus {
+                Focus::Batches => Focus::Entries,
+                Focus::Entries => Focus::Batches,
+            };
+        }
+    }
+
+    pub fn on_enter(&mut self) {
+        self.message = None;
+        match self.input_mode {
+            InputMode::Search => {
+                self.input_mode = InputMode::Normal;
+                self.input.clear();
+            }
+            InputMode::Filter => {
+                self.apply_filter();
+                self.input_mode = InputMode::Normal;
+                self.input.clear();
+            }
+            InputMode::Normal if self.show_detail => self.show_detail = false,
+            InputMode::Normal if self.show_raw => self.show_raw = false,
+            InputMode::Normal if self.show_popup => {}
+            InputMode::Normal if self.focus == Focus::Batches => self.toggle_batch(),
+            InputMode::Normal => {
+                if self.selected_entry().is_some() {
+                    self.show_detail = true;
+                    self.detail_scroll = 0;
+                }
+            }
+        }
+    }
+
     pub fn on_up(&mut self) {
-        self.entries.previous();
+        if self.input_mode == InputMode::Normal {
+            self.on_key('k');
+        }
     }
 
     pub fn on_down(&mut self) {
-        self.entries.next();
+        if self.input_mode == InputMode::Normal {
+            self.on_key('j');
+        }
     }
 
     pub fn on_right(&mut self) {
-        self.entries.jump_next();
+        if self.input_mode == InputMode::Normal {
+            self.on_key('l');
+        }
     }
 
     pub fn on_left(&mut self) {
-        self.entries.jump_previous();
+        if self.input_mode == InputMode::Normal {
+            self.on_key('h');
+        }
     }
 }
 
@@ -106,7 +535,7 @@ impl<T> StatefulTable<T> {
             jump_size: 1,
         };
         s.state.select(Some(0));
-        return s;
+        s
     }
 
     /// used with popup
@@ -115,7 +544,7 @@ impl<T> StatefulTable<T> {
     }
 
     pub fn has_items(&self) -> bool {
-        self.items.len() > 0
+        !self.items.is_empty()
     }
 
     pub fn add_items(&mut self, items: Vec<T>) {
@@ -138,10 +567,13 @@ impl<T> StatefulTable<T> {
             jump_size,
         };
         s.state.select(Some(0));
-        return s;
+        s
     }
 
     pub fn next(&mut self) {
+        if self.items.is_empty() {
+            return;
+        }
         let i = match self.state.selected() {
             Some(i) => {
                 if i >= self.items.len() - 1 {
@@ -156,6 +588,9 @@ impl<T> StatefulTable<T> {
     }
 
     pub fn previous(&mut self) {
+        if self.items.is_empty() {
+            return;
+        }
         let i = match self.state.selected() {
             Some(i) => {
                 if i == 0 {
@@ -170,9 +605,12 @@ impl<T> StatefulTable<T> {
     }
 
     pub fn jump_next(&mut self) {
+        if self.items.is_empty() {
+            return;
+        }
         let i = match self.state.selected() {
             Some(i) => {
-                if i > self.items.len() - 1 - self.jump_size {
+                if i + self.jump_size > self.items.len() - 1 {
                     // self.items.len() - 1 - i
                     self.jump_size % (self.items.len() - i)
                 } else {
@@ -185,6 +623,9 @@ impl<T> StatefulTable<T> {
     }
 
     pub fn jump_previous(&mut self) {
+        if self.items.is_empty() {
+            return;
+        }
         let i = match self.state.selected() {
             Some(i) => {
                 if i < self.jump_size {
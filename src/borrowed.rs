@@ -0,0 +1,718 @@
+//! Records whose text fields borrow from the input instead of being copied
+//! into `String`s. The owned records are parsed through these, with the
+//! columns of each field taken from [`layout`](crate::layout).
+//!
+//! IAT fields and typed addenda details are still owned, as they only turn
+//! up in a small share of records.
+use crate::layout::{
+    addendum, adv_entry_detail, batch_control, batch_header, entry_detail, file_control,
+    file_header, iat_entry_detail, EntryLayout, RECORD_TYPE_CODE,
+};
+use crate::writer::{Position, Sources};
+use crate::{
+    hh_mm_format, AddendaDetail, Addendum, AdvEntryDetail, Amount, Batch, BatchControl,
+    BatchHeader, DetailEntry, Diagnostic, FileControl, FileHeader, IatBatchHeader, IatEntryDetail,
+    Line, NachaFile, ParseError, ParseErrorKind, Record, ServiceClassCode, StandardEntryClass,
+    TransactionCode,
+};
+use chrono::{NaiveDate, NaiveTime};
+use log::{debug, info};
+use serde::Serialize;
+use std::cell::RefCell;
+
+/// A [`NachaFile`] borrowing its text from the input.
+#[derive(Debug, Serialize, Clone)]
+pub struct NachaFileRef<'a> {
+    pub file_header: FileHeaderRef<'a>,
+    pub batches: Vec<BatchRef<'a>>,
+    pub file_control: FileControlRef<'a>,
+    #[serde(skip_serializing)]
+    raw: &'a str,
+    #[serde(skip_serializing)]
+    sources: Sources,
+}
+
+impl<'a> NachaFileRef<'a> {
+    /// Parses the content of a NACHA file, stopping at the first malformed
+    /// record or field.
+    pub fn parse(content: &'a str) -> Result<NachaFileRef<'a>, ParseError> {
+        NachaFileRef::read(content, None)
+    }
+
+    /// Parses the content of a NACHA file, carrying on past malformed records
+    /// and fields, as [`NachaFile::parse_lenient`] does.
+    pub fn parse_lenient(content: &'a str) -> (NachaFileRef<'a>, Vec<Diagnostic>) {
+        let sink = RefCell::new(Vec::new());
+        let file = NachaFileRef::read(content, Some(&sink))
+            .expect("lenient parsing reports errors as diagnostics");
+        (file, sink.into_inner())
+    }
+
+    pub(crate) fn read(
+        content: &'a str,
+        sink: Option<&RefCell<Vec<Diagnostic>>>,
+    ) -> Result<NachaFileRef<'a>, ParseError> {
+        let mut file = NachaFileRef {
+            file_header: FileHeaderRef::default(),
+            batches: Vec::new(),
+            file_control: FileControlRef::default(),
+            raw: content,
+            sources: Sources::new(),
+        };
+        let mut seen_header = false;
+        let mut seen_control = false;
+        let mut last_line = 0;
+
+        for (i, text) in content.lines().enumerate() {
+            last_line = i + 1;
+            if text.is_empty() {
+                continue;
+            }
+            let line = Line::lenient(text, i + 1, sink);
+            let entries = file
+                .batches
+                .last()
+                .map_or(EntryLayout::Standard, |b| b.batch_header.entry_layout());
+            let parsed = RecordRef::from_line(&line, entries).and_then(|record| {
+                match record {
+                    RecordRef::FileHeader(_) => seen_header = true,
+                    RecordRef::FileControl(_) => seen_control = true,
+                    _ => {}
+                }
+                file.push(record, &line)
+            });
+            match (parsed, sink) {
+                (Ok(position), _) => {
+                    // `text` is a slice of `content`
+                    let start = text.as_ptr() as usize - content.as_ptr() as usize;
+                    file.sources.insert(position, start..start + text.len());
+                }
+                (Err(e), Some(sink)) => {
+                    file.sources.skip();
+                    sink.borrow_mut().push(e.into());
+                }
+                (Err(e), None) => return Err(e),
+            }
+            if seen_control {
+                break;
+            }
+        }
+
+        if let Some(sink) = sink {
+            if !seen_header {
+                sink.borrow_mut()
+                    .push(Diagnostic::warning(None, "no file header record found"));
+            }
+            if !seen_control {
+                sink.borrow_mut().push(Diagnostic::warning(
+                    Some(last_line),
+                    "no file control record found",
+                ));
+            }
+        }
+        info!("Done parsing file");
+        Ok(file)
+    }
+
+    /// attaches a record to the file, or to the batch or entry before it,
+    /// and says where it went
+    fn push(&mut self, record: RecordRef<'a>, line: &Line) -> Result<Position, ParseError> {
+        let orphan = || line.error(ParseErrorKind::OrphanRecord, RECORD_TYPE_CODE);
+        let last_batch = self.batches.len().checked_sub(1).ok_or_else(orphan);
+        let position = match record {
+            RecordRef::FileHeader(header) => {
+                debug!("file header found");
+                self.file_header = header;
+                Position::FileHeader
+            }
+            RecordRef::BatchHeader(batch_header) => {
+                debug!("batch header found");
+                self.batches.push(BatchRef {
+                    batch_header,
+                    detail_entries: Vec::new(),
+                    batch_control: BatchControlRef::default(),
+                });
+                Position::BatchHeader(self.batches.len() - 1)
+            }
+            RecordRef::Entry(entry) => {
+                debug!("detail entry found");
+                let b = last_batch?;
+                let entries = &mut self.batches[b].detail_entries;
+                entries.push(entry);
+                Position::Entry(b, entries.len() - 1)
+            }
+            RecordRef::Addendum(addendum) => {
+                debug!("addendum entry found");
+                let b = last_batch?;
+                let entries = &mut self.batches[b].detail_entries;
+                let e = entries.len().checked_sub(1).ok_or_else(orphan)?;
+                let addenda = &mut entries[e].addenda;
+                addenda.push(addendum);
+                Position::Addendum(b, e, addenda.len() - 1)
+            }
+            RecordRef::BatchControl(control) => {
+                debug!("batch control found");
+                let b = last_batch?;
+                self.batches[b].batch_control = control;
+                Position::BatchControl(b)
+            }
+            RecordRef::FileControl(control) => {
+                debug!("file control found");
+                self.file_control = control;
+                Position::FileControl
+            }
+        };
+        Ok(position)
+    }
+
+    /// Copies the text into a [`NachaFile`], which can outlive the input.
+    pub fn into_owned(self) -> NachaFile {
+        NachaFile {
+            file_header: self.file_header.into_owned(),
+            batches: self.batches.into_iter().map(BatchRef::into_owned).collect(),
+            file_control: self.file_control.into_owned(),
+            raw: self.raw.to_string(),
+            sources: self.sources,
+        }
+    }
+}
+
+/// a record of any type, as read from one line
+pub(crate) enum RecordRef<'a> {
+    FileHeader(FileHeaderRef<'a>),
+    BatchHeader(BatchHeaderRef<'a>),
+    Entry(DetailEntryRef<'a>),
+    Addendum(AddendumRef<'a>),
+    BatchControl(BatchControlRef<'a>),
+    FileControl(FileControlRef<'a>),
+}
+
+impl<'a> RecordRef<'a> {
+    /// Parses one line by its record type code. Entries in IAT and ADV
+    /// batches are laid out differently, so the caller says which batch
+    /// it's in.
+    pub(crate) fn from_line(
+        line: &Line<'a, '_>,
+        entries: EntryLayout,
+    ) -> Result<RecordRef<'a>, ParseError> {
+        match line.slice(RECORD_TYPE_CODE)? {
+            "1" => FileHeaderRef::from_line(line).map(RecordRef::FileHeader),
+            "5" => BatchHeaderRef::from_line(line).map(RecordRef::BatchHeader),
+            "6" => match entries {
+                EntryLayout::Standard => DetailEntryRef::from_line(line),
+                EntryLayout::Iat => DetailEntryRef::from_iat_line(line),
+                EntryLayout::Adv => DetailEntryRef::from_adv_line(line),
+            }
+            .map(RecordRef::Entry),
+            "7" => AddendumRef::from_line(line).map(RecordRef::Addendum),
+            "8" => BatchControlRef::from_line(line).map(RecordRef::BatchControl),
+            "9" => FileControlRef::from_line(line).map(RecordRef::FileControl),
+            _ => Err(line.error(ParseErrorKind::UnknownRecordType, RECORD_TYPE_CODE)),
+        }
+    }
+
+    pub(crate) fn into_owned(self) -> Record {
+        match self {
+            RecordRef::FileHeader(r) => Record::FileHeader(r.into_owned()),
+            RecordRef::BatchHeader(r) => Record::BatchHeader(r.into_owned()),
+            RecordRef::Entry(r) => Record::Entry(r.into_owned()),
+            RecordRef::Addendum(r) => Record::Addendum(r.into_owned()),
+            RecordRef::BatchControl(r) => Record::BatchControl(r.into_owned()),
+            RecordRef::FileControl(r) => Record::FileControl(r.into_owned()),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Clone, Default)]
+pub struct FileHeaderRef<'a> {
+    pub record_type_code: &'a str,
+    pub priority_code: &'a str,
+    pub immediate_destination: &'a str,
+    pub immediate_origin: &'a str,
+    pub file_creation_date: Option<NaiveDate>,
+    #[serde(serialize_with = "hh_mm_format")]
+    pub file_creation_time: Option<NaiveTime>,
+    pub file_id_modifier: &'a str,
+    pub record_size: &'a str,
+    pub blocking_factor: &'a str,
+    pub format_code: &'a str,
+    pub immediate_destination_name: &'a str,
+    pub immediate_origin_name: &'a str,
+    pub reference_code: &'a str,
+}
+
+impl<'a> FileHeaderRef<'a> {
+    pub fn parse(text: &'a str, line_number: usize) -> Result<FileHeaderRef<'a>, ParseError> {
+        FileHeaderRef::from_line(&Line::new(text, line_number))
+    }
+
+    pub(crate) fn from_line(line: &Line<'a, '_>) -> Result<FileHeaderRef<'a>, ParseError> {
+        Ok(FileHeaderRef {
+            record_type_code: line.trimmed(RECORD_TYPE_CODE)?,
+            priority_code: line.trimmed(file_header::PRIORITY_CODE)?,
+            immediate_destination: line.trimmed(file_header::IMMEDIATE_DESTINATION)?,
+            immediate_origin: line.trimmed(file_header::IMMEDIATE_ORIGIN)?,
+            file_creation_date: line.date(file_header::FILE_CREATION_DATE)?,
+            file_creation_time: line.time(file_header::FILE_CREATION_TIME)?,
+            file_id_modifier: line.trimmed(file_header::FILE_ID_MODIFIER)?,
+            record_size: line.trimmed(file_header::RECORD_SIZE)?,
+            blocking_factor: line.trimmed(file_header::BLOCKING_FACTOR)?,
+            format_code: line.trimmed(file_header::FORMAT_CODE)?,
+            immediate_destination_name: line.trimmed(file_header::IMMEDIATE_DESTINATION_NAME)?,
+            immediate_origin_name: line.trimmed(file_header::IMMEDIATE_ORIGIN_NAME)?,
+            reference_code: line.trimmed(file_header::REFERENCE_CODE)?,
+        })
+    }
+
+    pub fn into_owned(self) -> FileHeader {
+        FileHeader {
+            record_type_code: self.record_type_code.to_string(),
+            priority_code: self.priority_code.to_string(),
+            immediate_destination: self.immediate_destination.to_string(),
+            immediate_origin: self.immediate_origin.to_string(),
+            file_creation_date: self.file_creation_date,
+            file_creation_time: self.file_creation_time,
+            file_id_modifier: self.file_id_modifier.to_string(),
+            record_size: self.record_size.to_string(),
+            blocking_factor: self.blocking_factor.to_string(),
+            format_code: self.format_code.to_string(),
+            immediate_destination_name: self.immediate_destination_name.to_string(),
+            immediate_origin_name: self.immediate_origin_name.to_string(),
+            reference_code: self.reference_code.to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct BatchRef<'a> {
+    pub batch_header: BatchHeaderRef<'a>,
+    pub detail_entries: Vec<DetailEntryRef<'a>>,
+    pub batch_control: BatchControlRef<'a>,
+}
+
+impl<'a> BatchRef<'a> {
+    pub fn into_owned(self) -> Batch {
+        Batch {
+            batch_header: self.batch_header.into_owned(),
+            detail_entries: self
+                .detail_entries
+                .into_iter()
+                .map(DetailEntryRef::into_owned)
+                .collect(),
+            batch_control: self.batch_control.into_owned(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct BatchHeaderRef<'a> {
+    pub record_type_code: &'a str,
+    pub service_class_code: ServiceClassCode,
+    pub company_name: &'a str,
+    pub company_discretionary_data: &'a str,
+    pub company_id: &'a str,
+    pub standard_entry_class_code: StandardEntryClass,
+    pub company_entry_description: &'a str,
+    pub company_descriptive_date: &'a str,
+    pub effective_entry_date: Option<NaiveDate>,
+    pub settlement_date: Option<NaiveDate>,
+    pub originator_status_code: &'a str,
+    pub originating_dfi_id: &'a str,
+    pub batch_number: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub iat: Option<IatBatchHeader>,
+}
+
+impl<'a> BatchHeaderRef<'a> {
+    pub fn parse(text: &'a str, line_number: usize) -> Result<BatchHeaderRef<'a>, ParseError> {
+        BatchHeaderRef::from_line(&Line::new(text, line_number))
+    }
+
+    pub(crate) fn from_line(line: &Line<'a, '_>) -> Result<BatchHeaderRef<'a>, ParseError> {
+        let standard_entry_class_code = line.code(batch_header::STANDARD_ENTRY_CLASS_CODE)?;
+        if standard_entry_class_code == StandardEntryClass::International {
+            return Ok(BatchHeaderRef {
+                iat: Some(IatBatchHeader::from_line(line)?),
+                company_name: "",
+                company_discretionary_data: "",
+                company_descriptive_date: "",
+                ..BatchHeaderRef::from_ppd_line(line, standard_entry_class_code)?
+            });
+        }
+        BatchHeaderRef::from_ppd_line(line, standard_entry_class_code)
+    }
+
+    fn from_ppd_line(
+        line: &Line<'a, '_>,
+        standard_entry_class_code: StandardEntryClass,
+    ) -> Result<BatchHeaderRef<'a>, ParseError> {
+        let effective_entry_date = line.date(batch_header::EFFECTIVE_ENTRY_DATE)?;
+        Ok(BatchHeaderRef {
+            record_type_code: line.trimmed(RECORD_TYPE_CODE)?,
+            service_class_code: line.code(batch_header::SERVICE_CLASS_CODE)?,
+            company_name: line.trimmed(batch_header::COMPANY_NAME)?,
+            company_discretionary_data: line.trimmed(batch_header::COMPANY_DISCRETIONARY_DATA)?,
+            company_id: line.trimmed(batch_header::COMPANY_ID)?,
+            standard_entry_class_code,
+            company_entry_description: line.trimmed(batch_header::COMPANY_ENTRY_DESCRIPTION)?,
+            company_descriptive_date: line.trimmed(batch_header::COMPANY_DESCRIPTIVE_DATE)?,
+            effective_entry_date,
+            settlement_date: line.julian(batch_header::SETTLEMENT_DATE, effective_entry_date)?,
+            originator_status_code: line.trimmed(batch_header::ORIGINATOR_STATUS_CODE)?,
+            originating_dfi_id: line.trimmed(batch_header::ORIGINATING_DFI_ID)?,
+            batch_number: line.trimmed(batch_header::BATCH_NUMBER)?,
+            iat: None,
+        })
+    }
+
+    pub fn is_iat(&self) -> bool {
+        self.standard_entry_class_code == StandardEntryClass::International
+    }
+
+    /// how the batch's entries are laid out
+    pub fn entry_layout(&self) -> EntryLayout {
+        EntryLayout::for_class(&self.standard_entry_class_code)
+    }
+
+    pub fn into_owned(self) -> BatchHeader {
+        BatchHeader {
+            record_type_code: self.record_type_code.to_string(),
+            service_class_code: self.service_class_code,
+            company_name: self.company_name.to_string(),
+            company_discretionary_data: self.company_discretionary_data.to_string(),
+            company_id: self.company_id.to_string(),
+            standard_entry_class_code: self.standard_entry_class_code,
+            company_entry_description: self.company_entry_description.to_string(),
+            company_descriptive_date: self.company_descriptive_date.to_string(),
+            effective_entry_date: self.effective_entry_date,
+            settlement_date: self.settlement_date,
+            originator_status_code: self.originator_status_code.to_string(),
+            originating_dfi_id: self.originating_dfi_id.to_string(),
+            batch_number: self.batch_number.to_string(),
+            iat: self.iat,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Clone, Default)]
+pub struct BatchControlRef<'a> {
+    pub record_type_code: &'a str,
+    pub service_class_code: ServiceClassCode,
+    pub entry_addenda_count: &'a str,
+    pub entry_hash: &'a str,
+    pub total_debit: Amount,
+    pub total_credit: Amount,
+    pub company_id: &'a str,
+    pub message_authentication_code: &'a str,
+    pub reserved: &'a str,
+    pub originating_dfi_id: &'a str,
+    pub batch_number: &'a str,
+}
+
+impl<'a> BatchControlRef<'a> {
+    pub fn parse(text: &'a str, line_number: usize) -> Result<BatchControlRef<'a>, ParseError> {
+        BatchControlRef::from_line(&Line::new(text, line_number))
+    }
+
+    pub(crate) fn from_line(line: &Line<'a, '_>) -> Result<BatchControlRef<'a>, ParseError> {
+        Ok(BatchControlRef {
+            record_type_code: line.trimmed(RECORD_TYPE_CODE)?,
+            service_class_code: line.code(batch_control::SERVICE_CLASS_CODE)?,
+            entry_addenda_count: line.trimmed(batch_control::ENTRY_ADDENDA_COUNT)?,
+            entry_hash: line.trimmed(batch_control::ENTRY_HASH)?,
+            total_debit: line.amount(batch_control::TOTAL_DEBIT)?,
+            total_credit: line.amount(batch_control::TOTAL_CREDIT)?,
+            company_id: line.trimmed(batch_control::COMPANY_ID)?,
+            message_authentication_code: line
+                .trimmed(batch_control::MESSAGE_AUTHENTICATION_CODE)?,
+            reserved: line.trimmed(batch_control::RESERVED)?,
+            originating_dfi_id: line.trimmed(batch_control::ORIGINATING_DFI_ID)?,
+            batch_number: line.trimmed(batch_control::BATCH_NUMBER)?,
+        })
+    }
+
+    pub fn into_owned(self) -> BatchControl {
+        BatchControl {
+            record_type_code: self.record_type_code.to_string(),
+            service_class_code: self.service_class_code,
+            entry_addenda_count: self.entry_addenda_count.to_string(),
+            entry_hash: self.entry_hash.to_string(),
+            total_debit: self.total_debit,
+            total_credit: self.total_credit,
+            company_id: self.company_id.to_string(),
+            message_authentication_code: self.message_authentication_code.to_string(),
+            reserved: self.reserved.to_string(),
+            originating_dfi_id: self.originating_dfi_id.to_string(),
+            batch_number: self.batch_number.to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct DetailEntryRef<'a> {
+    pub record_type_code: &'a str,
+    pub transaction_code: TransactionCode,
+    pub receiving_dfi_id: &'a str,
+    pub check_digit: &'a str,
+    pub dfi_account_number: &'a str,
+    pub amount: Amount,
+    pub individual_id_number: &'a str,
+    pub individual_name: &'a str,
+    pub discretionary_data: &'a str,
+    pub addenda_record_indicator: &'a str,
+    pub trace_number: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub iat: Option<IatEntryDetail>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub adv: Option<AdvEntryDetail>,
+    pub addenda: Vec<AddendumRef<'a>>,
+}
+
+impl<'a> DetailEntryRef<'a> {
+    pub fn parse(text: &'a str, line_number: usize) -> Result<DetailEntryRef<'a>, ParseError> {
+        DetailEntryRef::from_line(&Line::new(text, line_number))
+    }
+
+    pub(crate) fn from_line(line: &Line<'a, '_>) -> Result<DetailEntryRef<'a>, ParseError> {
+        Ok(DetailEntryRef {
+            record_type_code: line.trimmed(RECORD_TYPE_CODE)?,
+            transaction_code: line.code(entry_detail::TRANSACTION_CODE)?,
+            receiving_dfi_id: line.trimmed(entry_detail::RECEIVING_DFI_ID)?,
+            check_digit: line.trimmed(entry_detail::CHECK_DIGIT)?,
+            dfi_account_number: line.trimmed(entry_detail::DFI_ACCOUNT_NUMBER)?,
+            amount: line.amount(entry_detail::AMOUNT)?,
+            individual_id_number: line.trimmed(entry_detail::INDIVIDUAL_ID_NUMBER)?,
+            individual_name: line.trimmed(entry_detail::INDIVIDUAL_NAME)?,
+            discretionary_data: line.trimmed(entry_detail::DISCRETIONARY_DATA)?,
+            addenda_record_indicator: line.trimmed(entry_detail::ADDENDA_RECORD_INDICATOR)?,
+            trace_number: line.trimmed(entry_detail::TRACE_NUMBER)?,
+            iat: None,
+            adv: None,
+            addenda: Vec::new(),
+        })
+    }
+
+    /// parses an entry in an IAT batch, where the foreign receiver's account
+    /// number is held in `dfi_account_number`
+    pub fn parse_iat(text: &'a str, line_number: usize) -> Result<DetailEntryRef<'a>, ParseError> {
+        DetailEntryRef::from_iat_line(&Line::new(text, line_number))
+    }
+
+    pub(crate) fn from_iat_line(line: &Line<'a, '_>) -> Result<DetailEntryRef<'a>, ParseError> {
+        Ok(DetailEntryRef {
+            record_type_code: line.trimmed(RECORD_TYPE_CODE)?,
+            transaction_code: line.code(entry_detail::TRANSACTION_CODE)?,
+            receiving_dfi_id: line.trimmed(entry_detail::RECEIVING_DFI_ID)?,
+            check_digit: line.trimmed(entry_detail::CHECK_DIGIT)?,
+            dfi_account_number: line.trimmed(iat_entry_detail::FOREIGN_RECEIVER_ACCOUNT_NUMBER)?,
+            amount: line.amount(entry_detail::AMOUNT)?,
+            individual_id_number: "",
+            individual_name: "",
+            discretionary_data: "",
+            addenda_record_indicator: line.trimmed(entry_detail::ADDENDA_RECORD_INDICATOR)?,
+            trace_number: line.trimmed(entry_detail::TRACE_NUMBER)?,
+            iat: Some(IatEntryDetail::from_line(line)?),
+            adv: None,
+            addenda: Vec::new(),
+        })
+    }
+
+    /// parses an entry in an ADV batch
+    pub fn parse_adv(text: &'a str, line_number: usize) -> Result<DetailEntryRef<'a>, ParseError> {
+        DetailEntryRef::from_adv_line(&Line::new(text, line_number))
+    }
+
+    pub(crate) fn from_adv_line(line: &Line<'a, '_>) -> Result<DetailEntryRef<'a>, ParseError> {
+        Ok(DetailEntryRef {
+            record_type_code: line.trimmed(RECORD_TYPE_CODE)?,
+            transaction_code: line.code(adv_entry_detail::TRANSACTION_CODE)?,
+            receiving_dfi_id: line.trimmed(adv_entry_detail::RECEIVING_DFI_ID)?,
+            check_digit: line.trimmed(adv_entry_detail::CHECK_DIGIT)?,
+            dfi_account_number: line.trimmed(adv_entry_detail::DFI_ACCOUNT_NUMBER)?,
+            amount: line.amount(adv_entry_detail::AMOUNT)?,
+            individual_id_number: "",
+            individual_name: line.trimmed(adv_entry_detail::INDIVIDUAL_NAME)?,
+            discretionary_data: line.trimmed(adv_entry_detail::DISCRETIONARY_DATA)?,
+            addenda_record_indicator: line.trimmed(adv_entry_detail::ADDENDA_RECORD_INDICATOR)?,
+            trace_number: "",
+            iat: None,
+            adv: Some(AdvEntryDetail::from_line(line)?),
+            addenda: Vec::new(),
+        })
+    }
+
+    pub fn has_addenda(&self) -> bool {
+        !self.addenda.is_empty()
+    }
+
+    pub fn into_owned(self) -> DetailEntry {
+        DetailEntry {
+            record_type_code: self.record_type_code.to_string(),
+            transaction_code: self.transaction_code,
+            receiving_dfi_id: self.receiving_dfi_id.to_string(),
+            check_digit: self.check_digit.to_string(),
+            dfi_account_number: self.dfi_account_number.to_string(),
+            amount: self.amount,
+            individual_id_number: self.individual_id_number.to_string(),
+            individual_name: self.individual_name.to_string(),
+            discretionary_data: self.discretionary_data.to_string(),
+            addenda_record_indicator: self.addenda_record_indicator.to_string(),
+            trace_number: self.trace_number.to_string(),
+            iat: self.iat,
+            adv: self.adv,
+            addenda: self
+                .addenda
+                .into_iter()
+                .map(AddendumRef::into_owned)
+                .collect(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct AddendumRef<'a> {
+    pub record_type_code: &'a str,
+    pub addenda_type_code: &'a str,
+    /// the free-form text of types 05 and 17, and of addenda types without
+    /// a [`AddendaDetail`]
+    pub payment_related_info: &'a str,
+    pub addenda_sequence_number: &'a str,
+    pub entry_detail_sequence_number: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<AddendaDetail>,
+}
+
+impl<'a> AddendumRef<'a> {
+    pub fn parse(text: &'a str, line_number: usize) -> Result<AddendumRef<'a>, ParseError> {
+        AddendumRef::from_line(&Line::new(text, line_number))
+    }
+
+    pub(crate) fn from_line(line: &Line<'a, '_>) -> Result<AddendumRef<'a>, ParseError> {
+        let addenda_type_code = line.trimmed(addendum::ADDENDA_TYPE_CODE)?;
+        let detail = AddendaDetail::from_line(addenda_type_code, line)?;
+        let payment_related_info = match &detail {
+            Some(detail) if !detail.is_free_text() => "",
+            _ => line.trimmed(addendum::PAYMENT_RELATED_INFO)?,
+        };
+        // types that end in a trace number have no sequence numbers
+        let (addenda_sequence_number, entry_detail_sequence_number) =
+            match detail.as_ref().and_then(|d| d.trace_number()) {
+                Some(_) => ("", ""),
+                None => (
+                    line.trimmed(addendum::ADDENDA_SEQUENCE_NUMBER)?,
+                    line.trimmed(addendum::ENTRY_DETAIL_SEQUENCE_NUMBER)?,
+                ),
+            };
+        Ok(AddendumRef {
+            record_type_code: line.trimmed(RECORD_TYPE_CODE)?,
+            addenda_type_code,
+            payment_related_info,
+            addenda_sequence_number,
+            entry_detail_sequence_number,
+            detail,
+        })
+    }
+
+    pub fn into_owned(self) -> Addendum {
+        Addendum {
+            record_type_code: self.record_type_code.to_string(),
+            addenda_type_code: self.addenda_type_code.to_string(),
+            payment_related_info: self.payment_related_info.to_string(),
+            addenda_sequence_number: self.addenda_sequence_number.to_string(),
+            entry_detail_sequence_number: self.entry_detail_sequence_number.to_string(),
+            detail: self.detail,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Clone, Default)]
+pub struct FileControlRef<'a> {
+    pub record_type_code: &'a str,
+    pub batch_count: u32,
+    pub block_count: u32,
+    pub entry_and_addenda_count: u32,
+    pub entry_hash: &'a str,
+    pub total_debit: Amount,
+    pub total_credit: Amount,
+    pub reserved: &'a str,
+}
+
+impl<'a> FileControlRef<'a> {
+    pub fn parse(text: &'a str, line_number: usize) -> Result<FileControlRef<'a>, ParseError> {
+        FileControlRef::from_line(&Line::new(text, line_number))
+    }
+
+    pub(crate) fn from_line(line: &Line<'a, '_>) -> Result<FileControlRef<'a>, ParseError> {
+        Ok(FileControlRef {
+            record_type_code: line.trimmed(RECORD_TYPE_CODE)?,
+            batch_count: line.numeric(file_control::BATCH_COUNT)?,
+            block_count: line.numeric(file_control::BLOCK_COUNT)?,
+            entry_and_addenda_count: line.numeric(file_control::ENTRY_AND_ADDENDA_COUNT)?,
+            entry_hash: line.trimmed(file_control::ENTRY_HASH)?,
+            total_debit: line.amount(file_control::TOTAL_DEBIT)?,
+            total_credit: line.amount(file_control::TOTAL_CREDIT)?,
+            reserved: line.trimmed(file_control::RESERVED)?,
+        })
+    }
+
+    pub fn into_owned(self) -> FileControl {
+        FileControl {
+            record_type_code: self.record_type_code.to_string(),
+            batch_count: self.batch_count,
+            block_count: self.block_count,
+            entry_and_addenda_count: self.entry_and_addenda_count,
+            entry_hash: self.entry_hash.to_string(),
+            total_debit: self.total_debit,
+            total_credit: self.total_credit,
+            reserved: self.reserved.to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::EXAMPLES;
+
+    fn json<T: Serialize>(value: &T) -> serde_json::Value {
+        serde_json::to_value(value).unwrap()
+    }
+
+    #[test]
+    fn into_owned_matches_the_owned_parser() {
+        for content in EXAMPLES {
+            let owned = NachaFileRef::parse(content).unwrap().into_owned();
+            let parsed = NachaFile::parse(content).unwrap();
+            assert_eq!(json(&owned), json(&parsed));
+            assert_eq!(owned.to_ach_string().unwrap(), content);
+        }
+    }
+
+    #[test]
+    fn fields_borrow_from_the_input() {
+        for content in EXAMPLES {
+            let input = content.as_bytes().as_ptr_range();
+            // empty fields may be `""` literals rather than slices of the input
+            let borrows = |field: &str| {
+                let field = field.as_bytes().as_ptr_range();
+                field.is_empty() || input.start <= field.start && field.end <= input.end
+            };
+            let file = NachaFileRef::parse(content).unwrap();
+            assert!(borrows(file.file_header.immediate_origin_name));
+            assert!(borrows(file.file_control.entry_hash));
+            for batch in &file.batches {
+                assert!(borrows(batch.batch_header.company_id));
+                assert!(borrows(batch.batch_control.entry_hash));
+                for entry in &batch.detail_entries {
+                    assert!(borrows(entry.receiving_dfi_id));
+                    assert!(borrows(entry.dfi_account_number));
+                    assert!(borrows(entry.individual_name));
+                    for addendum in &entry.addenda {
+                        assert!(borrows(addendum.addenda_type_code));
+                        assert!(borrows(addendum.payment_related_info));
+                    }
+                }
+            }
+        }
+    }
+}
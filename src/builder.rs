@@ -0,0 +1,551 @@
+use crate::{
+    AddendaDetail, Addendum, Amount, Batch, BatchControl, BatchHeader, DetailEntry, FileHeader,
+    NachaFile, ServiceClassCode, StandardEntryClass, TransactionCode, WriteError,
+};
+use chrono::{Local, NaiveDate, NaiveTime};
+
+/// Builds a [`NachaFile`] for origination. Trace numbers, batch numbers,
+/// addenda sequence numbers and the batch and file controls are all filled
+/// in by [`NachaFileBuilder::build`].
+#[derive(Debug, Clone)]
+pub struct NachaFileBuilder {
+    immediate_destination: String,
+    immediate_origin: String,
+    immediate_destination_name: String,
+    immediate_origin_name: String,
+    file_id_modifier: String,
+    reference_code: String,
+    file_creation_date: NaiveDate,
+    file_creation_time: NaiveTime,
+    batches: Vec<BatchBuilder>,
+}
+
+impl NachaFileBuilder {
+    pub fn new(immediate_destination: &str, immediate_origin: &str) -> NachaFileBuilder {
+        let now = Local::now().naive_local();
+        NachaFileBuilder {
+            immediate_destination: immediate_destination.to_string(),
+            immediate_origin: immediate_origin.to_string(),
+            immediate_destination_name: "".to_string(),
+            immediate_origin_name: "".to_string(),
+            file_id_modifier: "A".to_string(),
+            reference_code: "".to_string(),
+            file_creation_date: now.date(),
+            file_creation_time: now.time(),
+            batches: Vec::new(),
+        }
+    }
+
+    pub fn destination_name(mut self, name: &str) -> NachaFileBuilder {
+        self.immediate_destination_name = name.to_string();
+        self
+    }
+
+    pub fn origin_name(mut self, name: &str) -> NachaFileBuilder {
+        self.immediate_origin_name = name.to_string();
+        self
+    }
+
+    pub fn file_id_modifier(mut self, modifier: &str) -> NachaFileBuilder {
+        self.file_id_modifier = modifier.to_string();
+        self
+    }
+
+    pub fn reference_code(mut self, code: &str) -> NachaFileBuilder {
+        self.reference_code = code.to_string();
+        self
+    }
+
+    /// defaults to the current local date and time
+    pub fn created_at(mut self, date: NaiveDate, time: NaiveTime) -> NachaFileBuilder {
+        self.file_creation_date = date;
+        self.file_creation_time = time;
+        self
+    }
+
+    pub fn batch(mut self, batch: BatchBuilder) -> NachaFileBuilder {
+        self.batches.push(batch);
+        self
+    }
+
+    /// Fails if a value is too wide for its field, e.g. a company name over
+    /// 16 characters.
+    pub fn build(self) -> Result<NachaFile, WriteError> {
+        let file_header = FileHeader {
+            record_type_code: "1".to_string(),
+            priority_code: "01".to_string(),
+            immediate_destination: self.immediate_destination,
+            immediate_origin: self.immediate_origin,
+            file_creation_date: Some(self.file_creation_date),
+            file_creation_time: Some(self.file_creation_time),
+            file_id_modifier: self.file_id_modifier,
+            record_size: "094".to_string(),
+            blocking_factor: "10".to_string(),
+            format_code: "1".to_string(),
+            immediate_destination_name: self.immediate_destination_name,
+            immediate_origin_name: self.immediate_origin_name,
+            reference_code: self.reference_code,
+        };
+        // trace numbers keep counting across batches so they stay unique
+        // within the file
+        let mut trace_sequence = 0;
+        let batches = self
+            .batches
+            .into_iter()
+            .enumerate()
+            .map(|(i, b)| b.build(i as u32 + 1, &mut trace_sequence))
+            .collect();
+        let mut file = NachaFile {
+            file_header,
+            batches,
+            file_control: Default::default(),
+            raw: String::new(),
+            sources: Default::default(),
+        };
+        file.recompute_controls();
+        file.raw = file.to_ach_string()?;
+        Ok(file)
+    }
+}
+
+/// One batch of entries for a [`NachaFileBuilder`].
+#[derive(Debug, Clone)]
+pub struct BatchBuilder {
+    service_class_code: Option<ServiceClassCode>,
+    company_name: String,
+    company_discretionary_data: String,
+    company_id: String,
+    standard_entry_class_code: StandardEntryClass,
+    company_entry_description: String,
+    company_descriptive_date: String,
+    effective_entry_date: NaiveDate,
+    originating_dfi_id: String,
+    entries: Vec<DetailEntry>,
+}
+
+impl BatchBuilder {
+    pub fn new(
+        company_name: &str,
+        company_id: &str,
+        standard_entry_class_code: StandardEntryClass,
+        company_entry_description: &str,
+        effective_entry_date: NaiveDate,
+        originating_dfi_id: &str,
+    ) -> BatchBuilder {
+        BatchBuilder {
+            service_class_code: None,
+            company_name: company_name.to_string(),
+            company_discretionary_data: "".to_string(),
+            company_id: company_id.to_string(),
+            standard_entry_class_code,
+            company_entry_description: company_entry_description.to_string(),
+            company_descriptive_date: "".to_string(),
+            effective_entry_date,
+            originating_dfi_id: originating_dfi_id.to_string(),
+            entries: Vec::new(),
+        }
+    }
+
+    /// By default the service class code is worked out from the entries:
+    /// 280 for advices, 220 for credits only, 225 for debits only and 200 for
+    /// a mix.
+    pub fn service_class_code(mut self, code: ServiceClassCode) -> BatchBuilder {
+        self.service_class_code = Some(code);
+        self
+    }
+
+    pub fn company_discretionary_data(mut self, data: &str) -> BatchBuilder {
+        self.company_discretionary_data = data.to_string();
+        self
+    }
+
+    pub fn company_descriptive_date(mut self, date: &str) -> BatchBuilder {
+        self.company_descriptive_date = date.to_string();
+        self
+    }
+
+    pub fn entry(mut self, entry: DetailEntry) -> BatchBuilder {
+        self.entries.push(entry);
+        self
+    }
+
+    pub fn entries(mut self, entries: impl IntoIterator<Item = DetailEntry>) -> BatchBuilder {
+        self.entries.extend(entries);
+        self
+    }
+
+    fn build(self, batch_number: u32, trace_sequence: &mut u32) -> Batch {
+        let service_class_code = self.service_class_code.unwrap_or_else(|| {
+            if !self.entries.is_empty()
+                && self.entries.iter().all(|e| e.transaction_code.is_advice())
+            {
+                return ServiceClassCode::AutomatedAccountingAdvices;
+            }
+            let debits = self.entries.iter().any(|e| e.transaction_code.is_debit());
+            let credits = self.entries.iter().any(|e| e.transaction_code.is_credit());
+            match (debits, credits) {
+                (true, false) => ServiceClassCode::DebitsOnly,
+                (false, true) => ServiceClassCode::CreditsOnly,
+                _ => ServiceClassCode::Mixed,
+            }
+        });
+        let batch_header = BatchHeader {
+            record_type_code: "5".to_string(),
+            service_class_code,
+            company_name: self.company_name,
+            company_discretionary_data: self.company_discretionary_data,
+            company_id: self.company_id,
+            standard_entry_class_code: self.standard_entry_class_code,
+            company_entry_description: self.company_entry_description,
+            company_descriptive_date: self.company_descriptive_date,
+            effective_entry_date: Some(self.effective_entry_date),
+            settlement_date: None,
+            originator_status_code: "1".to_string(),
+            originating_dfi_id: self.originating_dfi_id,
+            batch_number: format!("{:07}", batch_number),
+            iat: None,
+        };
+
+        let detail_entries = self
+            .entries
+            .into_iter()
+            .map(|mut entry| {
+                *trace_sequence += 1;
+                let sequence = format!("{:07}", trace_sequence);
+                entry.trace_number = format!("{:0>8}{}", batch_header.originating_dfi_id, sequence);
+                entry.addenda_record_indicator =
+                    if entry.has_addenda() { "1" } else { "0" }.to_string();
+                for (j, addendum) in entry.addenda.iter_mut().enumerate() {
+                    addendum.addenda_sequence_number = format!("{:04}", j + 1);
+                    addendum.entry_detail_sequence_number = sequence.clone();
+                }
+                entry
+            })
+            .collect();
+
+        Batch {
+            batch_header,
+            detail_entries,
+            batch_control: BatchControl::new(),
+        }
+    }
+}
+
+impl DetailEntry {
+    /// A new entry for use with [`BatchBuilder::entry`]. `routing_number` is
+    /// the full 9 digit RDFI routing number, check digit included.
+    pub fn new(
+        transaction_code: TransactionCode,
+        routing_number: &str,
+        dfi_account_number: &str,
+        amount: Amount,
+        individual_name: &str,
+    ) -> DetailEntry {
+        let split = routing_number
+            .char_indices()
+            .nth(8)
+            .map_or(routing_number.len(), |(i, _)| i);
+        let (receiving_dfi_id, check_digit) = routing_number.split_at(split);
+        DetailEntry {
+            record_type_code: "6".to_string(),
+            transaction_code,
+            receiving_dfi_id: receiving_dfi_id.to_string(),
+            check_digit: check_digit.to_string(),
+            dfi_account_number: dfi_account_number.to_string(),
+            amount,
+            individual_id_number: "".to_string(),
+            individual_name: individual_name.to_string(),
+            discretionary_data: "".to_string(),
+            addenda_record_indicator: "0".to_string(),
+            trace_number: "".to_string(),
+            iat: None,
+            adv: None,
+            addenda: Vec::new(),
+        }
+    }
+
+    pub fn with_individual_id(mut self, id: &str) -> DetailEntry {
+        self.individual_id_number = id.to_string();
+        self
+    }
+
+    pub fn with_discretionary_data(mut self, data: &str) -> DetailEntry {
+        self.discretionary_data = data.to_string();
+        self
+    }
+
+    /// Adds a payment related information (type 05) addendum.
+    pub fn with_addendum(mut self, payment_related_info: &str) -> DetailEntry {
+        self.addenda.push(Addendum {
+            record_type_code: "7".to_string(),
+            addenda_type_code: "05".to_string(),
+            payment_related_info: payment_related_info.to_string(),
+            addenda_sequence_number: "".to_string(),
+            entry_detail_sequence_number: "".to_string(),
+            detail: Some(AddendaDetail::PaymentRelated),
+        });
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    fn file() -> NachaFileBuilder {
+        NachaFileBuilder::new("231380104", "0121042882")
+            .destination_name("Federal Reserve Bank")
+            .origin_name("My Bank Name")
+            .created_at(
+                date(2026, 10, 18),
+                NaiveTime::from_hms_opt(6, 55, 0).unwrap(),
+            )
+    }
+
+    fn batch(description: &str) -> BatchBuilder {
+        BatchBuilder::new(
+            "Name on Account",
+            "121042882",
+            StandardEntryClass::PrearrangedPayment,
+            description,
+            date(2019, 6, 25),
+            "12104288",
+        )
+    }
+
+    fn entry(transaction_code: TransactionCode, cents: u64, name: &str) -> DetailEntry {
+        DetailEntry::new(
+            transaction_code,
+            "021200025",
+            "998412345",
+            Amount::from_cents(cents),
+            name,
+        )
+    }
+
+    #[test]
+    fn builds_the_payroll_example() {
+        // what `nacha build examples/payments.csv --config examples/origin.yaml` writes
+        let file = file()
+            .batch(
+                batch("PAYROLL")
+                    .entry(
+                        entry(TransactionCode::CheckingCredit, 215000, "Julian Price")
+                            .with_individual_id("A271"),
+                    )
+                    .entry(
+                        DetailEntry::new(
+                            TransactionCode::SavingsCredit,
+                            "231380104",
+                            "12345678",
+                            Amount::from_cents(187550),
+                            "Sydney Butler",
+                        )
+                        .with_individual_id("A272")
+                        .with_addendum("June salary"),
+                    )
+                    .entry(
+                        DetailEntry::new(
+                            TransactionCode::CheckingCredit,
+                            "121042882",
+                            "55501234",
+                            Amount::from_cents(98025),
+                            "Avery Stone",
+                        )
+                        .with_individual_id("A273"),
+                    ),
+            )
+            .build()
+            .unwrap();
+
+        let control = &file.batches[0].batch_control;
+        assert_eq!(control.service_class_code, ServiceClassCode::CreditsOnly);
+        assert_eq!(control.entry_addenda_count, "000004");
+        assert_eq!(control.entry_hash, "0037362300");
+        assert_eq!(control.total_debit, Amount::ZERO);
+        assert_eq!(control.total_credit, Amount::from_cents(500575));
+        let control = &file.file_control;
+        assert_eq!(
+            (control.batch_count, control.block_count),
+            (1, 1),
+            "8 records fit in one block"
+        );
+        assert_eq!(control.entry_and_addenda_count, 4);
+        assert_eq!(control.entry_hash, "0037362300");
+        assert_eq!(control.total_credit, Amount::from_cents(500575));
+
+        let written = file.to_ach_string().unwrap();
+        let lines: Vec<_> = written.lines().collect();
+        assert_eq!(
+            lines[..8],
+            [
+                "101 23138010401210428822610180655A094101Federal Reserve Bank   My Bank Name                   ",
+                "5220Name on Account                     121042882 PPDPAYROLL         190625   1121042880000001",
+                "622021200025998412345        0000215000A271           Julian Price            0121042880000001",
+                "63223138010412345678         0000187550A272           Sydney Butler           1121042880000002",
+                "705June salary                                                                     00010000002",
+                "62212104288255501234         0000098025A273           Avery Stone             0121042880000003",
+                "82200000040037362300000000000000000000500575121042882                          121042880000001",
+                "9000001000001000000040037362300000000000000000000500575                                       ",
+            ]
+        );
+        assert_eq!(lines.len(), 10);
+        assert_eq!(file.raw(), written);
+    }
+
+    #[test]
+    fn numbers_batches_entries_and_addenda_across_the_file() {
+        let file = file()
+            .batch(
+                batch("FIRST")
+                    .entry(entry(TransactionCode::CheckingCredit, 100, "A"))
+                    .entry(
+                        entry(TransactionCode::CheckingCredit, 200, "B")
+                            .with_addendum("one")
+                            .with_addendum("two"),
+                    ),
+            )
+            .batch(batch("SECOND").entry(entry(TransactionCode::CheckingCredit, 300, "C")))
+            .batch(
+                batch("THIRD")
+                    .entry(entry(TransactionCode::CheckingCredit, 400, "D").with_addendum("three")),
+            )
+            .build()
+            .unwrap();
+
+        let batch_numbers: Vec<_> = file
+            .batches
+            .iter()
+            .map(|b| {
+                assert_eq!(b.batch_header.batch_number, b.batch_control.batch_number);
+                b.batch_header.batch_number.as_str()
+            })
+            .collect();
+        assert_eq!(batch_numbers, ["0000001", "0000002", "0000003"]);
+
+        let entries: Vec<_> = file
+            .batches
+            .iter()
+            .flat_map(|b| &b.detail_entries)
+            .collect();
+        let traces: Vec<_> = entries.iter().map(|e| e.trace_number.as_str()).collect();
+        assert_eq!(
+            traces,
+            [
+                "121042880000001",
+                "121042880000002",
+                "121042880000003",
+                "121042880000004"
+            ]
+        );
+        let indicators: Vec<_> = entries
+            .iter()
+            .map(|e| e.addenda_record_indicator.as_str())
+            .collect();
+        assert_eq!(indicators, ["0", "1", "0", "1"]);
+
+        let addenda: Vec<_> = entries
+            .iter()
+            .flat_map(|e| &e.addenda)
+            .map(|a| {
+                (
+                    a.addenda_sequence_number.as_str(),
+                    a.entry_detail_sequence_number.as_str(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            addenda,
+            [
+                ("0001", "0000002"),
+                ("0002", "0000002"),
+                ("0001", "0000004")
+            ]
+        );
+    }
+
+    #[test]
+    fn picks_the_service_class_from_the_entries() {
+        let service_class = |batch: BatchBuilder| {
+            let file = file().batch(batch).build().unwrap();
+            let batch = &file.batches[0];
+            assert_eq!(
+                batch.batch_header.service_class_code,
+                batch.batch_control.service_class_code
+            );
+            batch.batch_header.service_class_code.clone()
+        };
+        let credit = || entry(TransactionCode::CheckingCredit, 100, "A");
+        let debit = || entry(TransactionCode::SavingsDebit, 100, "B");
+
+        assert_eq!(
+            service_class(batch("CREDITS").entry(credit()).entry(credit())),
+            ServiceClassCode::CreditsOnly
+        );
+        assert_eq!(
+            service_class(batch("DEBITS").entry(debit())),
+            ServiceClassCode::DebitsOnly
+        );
+        assert_eq!(
+            service_class(batch("MIXED").entry(credit()).entry(debit())),
+            ServiceClassCode::Mixed
+        );
+        assert_eq!(service_class(batch("EMPTY")), ServiceClassCode::Mixed);
+        // unless it's chosen
+        assert_eq!(
+            service_class(
+                batch("CHOSEN")
+                    .service_class_code(ServiceClassCode::Mixed)
+                    .entry(credit())
+            ),
+            ServiceClassCode::Mixed
+        );
+    }
+
+    #[test]
+    fn totals_every_batch_into_the_file_control() {
+        let file = file()
+            .batch(
+                batch("CREDITS")
+                    .entries((1..=6).map(|i| entry(TransactionCode::CheckingCredit, i * 100, "A"))),
+            )
+            .batch(batch("DEBITS").entry(entry(TransactionCode::SavingsDebit, 5000, "B")))
+            .build()
+            .unwrap();
+
+        let controls: Vec<_> = file
+            .batches
+            .iter()
+            .map(|b| {
+                let c = &b.batch_control;
+                (
+                    c.entry_addenda_count.as_str(),
+                    c.entry_hash.as_str(),
+                    c.total_debit.cents(),
+                    c.total_credit.cents(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            controls,
+            [
+                ("000006", "0012720012", 0, 2100),
+                ("000001", "0002120002", 5000, 0)
+            ]
+        );
+        let control = &file.file_control;
+        assert_eq!(control.batch_count, 2);
+        assert_eq!(control.entry_and_addenda_count, 7);
+        assert_eq!(control.entry_hash, "0014840014");
+        assert_eq!(control.total_debit, Amount::from_cents(5000));
+        assert_eq!(control.total_credit, Amount::from_cents(2100));
+        // 2 file records, 4 batch records and 7 entries take two blocks
+        assert_eq!(control.block_count, 2);
+        assert_eq!(file.to_ach_string().unwrap().lines().count(), 20);
+        assert!(file.validate().is_empty());
+    }
+}
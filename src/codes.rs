@@ -0,0 +1,385 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+/// A code that isn't one of the values defined by the NACHA rules.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnknownCode(pub String);
+
+impl fmt::Display for UnknownCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "unknown code {:?}", self.0)
+    }
+}
+
+impl std::error::Error for UnknownCode {}
+
+/// The code enums, as read by a lenient parse, which keeps a code it doesn't
+/// know as `Unknown` rather than making one up.
+pub(crate) trait Code: Sized {
+    fn from_code(code: &str) -> Option<Self>;
+    fn unknown(code: &str) -> Self;
+}
+
+/// Implements `Code`, `Display`, `FromStr` and string (de)serialization in terms of
+/// the code's `as_str` and `from_code`.
+macro_rules! string_code {
+    ($t:ty) => {
+        impl $t {
+            /// whether a lenient parse kept this as a code it didn't know
+            pub fn is_unknown(&self) -> bool {
+                matches!(self, Self::Unknown(_))
+            }
+        }
+
+        impl Code for $t {
+            fn from_code(code: &str) -> Option<$t> {
+                <$t>::from_code(code)
+            }
+
+            fn unknown(code: &str) -> $t {
+                Self::Unknown(code.to_string())
+            }
+        }
+
+        impl fmt::Display for $t {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                write!(f, "{}", self.as_str())
+            }
+        }
+
+        impl FromStr for $t {
+            type Err = UnknownCode;
+
+            fn from_str(s: &str) -> Result<$t, UnknownCode> {
+                <$t>::from_code(s.trim()).ok_or_else(|| UnknownCode(s.to_string()))
+            }
+        }
+
+        impl TryFrom<String> for $t {
+            type Error = UnknownCode;
+
+            fn try_from(s: String) -> Result<$t, UnknownCode> {
+                s.parse()
+            }
+        }
+
+        impl From<$t> for String {
+            fn from(c: $t) -> String {
+                c.as_str().to_string()
+            }
+        }
+    };
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AccountType {
+    Checking,
+    Savings,
+    GeneralLedger,
+    Loan,
+}
+
+/// Transaction code of an entry detail record. The first digit gives the
+/// account type and the second whether it's a credit (1-4) or debit (5-9)
+/// and whether it's a return, live entry, prenote or zero dollar entry.
+/// Automated accounting advices (ADV, 81-88) are the exception: odd codes
+/// are credits and even ones debits.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum TransactionCode {
+    CheckingReturnCredit,
+    #[default]
+    CheckingCredit,
+    CheckingPrenoteCredit,
+    CheckingZeroDollarCredit,
+    CheckingReturnDebit,
+    CheckingDebit,
+    CheckingPrenoteDebit,
+    CheckingZeroDollarDebit,
+    SavingsReturnCredit,
+    SavingsCredit,
+    SavingsPrenoteCredit,
+    SavingsZeroDollarCredit,
+    SavingsReturnDebit,
+    SavingsDebit,
+    SavingsPrenoteDebit,
+    SavingsZeroDollarDebit,
+    GeneralLedgerReturnCredit,
+    GeneralLedgerCredit,
+    GeneralLedgerPrenoteCredit,
+    GeneralLedgerZeroDollarCredit,
+    GeneralLedgerReturnDebit,
+    GeneralLedgerDebit,
+    GeneralLedgerPrenoteDebit,
+    GeneralLedgerZeroDollarDebit,
+    LoanReturnCredit,
+    LoanCredit,
+    LoanPrenoteCredit,
+    LoanZeroDollarCredit,
+    LoanReversalDebit,
+    LoanReturnDebit,
+    AdviceCreditForDebitsOriginated,
+    AdviceDebitForCreditsOriginated,
+    AdviceCreditForCreditsReceived,
+    AdviceDebitForDebitsReceived,
+    AdviceCreditForCreditsInRejectedBatches,
+    AdviceDebitForDebitsInRejectedBatches,
+    AdviceSummaryCredit,
+    AdviceSummaryDebit,
+    /// a code that isn't in the rules, kept as found by a lenient parse
+    Unknown(String),
+}
+
+const TRANSACTION_CODES: [(TransactionCode, &str); 38] = [
+    (TransactionCode::CheckingReturnCredit, "21"),
+    (TransactionCode::CheckingCredit, "22"),
+    (TransactionCode::CheckingPrenoteCredit, "23"),
+    (TransactionCode::CheckingZeroDollarCredit, "24"),
+    (TransactionCode::CheckingReturnDebit, "26"),
+    (TransactionCode::CheckingDebit, "27"),
+    (TransactionCode::CheckingPrenoteDebit, "28"),
+    (TransactionCode::CheckingZeroDollarDebit, "29"),
+    (TransactionCode::SavingsReturnCredit, "31"),
+    (TransactionCode::SavingsCredit, "32"),
+    (TransactionCode::SavingsPrenoteCredit, "33"),
+    (TransactionCode::SavingsZeroDollarCredit, "34"),
+    (TransactionCode::SavingsReturnDebit, "36"),
+    (TransactionCode::SavingsDebit, "37"),
+    (TransactionCode::SavingsPrenoteDebit, "38"),
+    (TransactionCode::SavingsZeroDollarDebit, "39"),
+    (TransactionCode::GeneralLedgerReturnCredit, "41"),
+    (TransactionCode::GeneralLedgerCredit, "42"),
+    (TransactionCode::GeneralLedgerPrenoteCredit, "43"),
+    (TransactionCode::GeneralLedgerZeroDollarCredit, "44"),
+    (TransactionCode::GeneralLedgerReturnDebit, "46"),
+    (TransactionCode::GeneralLedgerDebit, "47"),
+    (TransactionCode::GeneralLedgerPrenoteDebit, "48"),
+    (TransactionCode::GeneralLedgerZeroDollarDebit, "49"),
+    (TransactionCode::LoanReturnCredit, "51"),
+    (TransactionCode::LoanCredit, "52"),
+    (TransactionCode::LoanPrenoteCredit, "53"),
+    (TransactionCode::LoanZeroDollarCredit, "54"),
+    (TransactionCode::LoanReversalDebit, "55"),
+    (TransactionCode::LoanReturnDebit, "56"),
+    (TransactionCode::AdviceCreditForDebitsOriginated, "81"),
+    (TransactionCode::AdviceDebitForCreditsOriginated, "82"),
+    (TransactionCode::AdviceCreditForCreditsReceived, "83"),
+    (TransactionCode::AdviceDebitForDebitsReceived, "84"),
+    (
+        TransactionCode::AdviceCreditForCreditsInRejectedBatches,
+        "85",
+    ),
+    (TransactionCode::AdviceDebitForDebitsInRejectedBatches, "86"),
+    (TransactionCode::AdviceSummaryCredit, "87"),
+    (TransactionCode::AdviceSummaryDebit, "88"),
+];
+
+impl TransactionCode {
+    pub fn from_code(code: &str) -> Option<TransactionCode> {
+        TRANSACTION_CODES
+            .iter()
+            .find(|(_, c)| *c == code)
+            .map(|(t, _)| t.clone())
+    }
+
+    pub fn as_str(&self) -> &str {
+        if let TransactionCode::Unknown(code) = self {
+            return code;
+        }
+        TRANSACTION_CODES
+            .iter()
+            .find(|(t, _)| t == self)
+            .map(|(_, c)| *c)
+            .expect("every transaction code has an entry in the table")
+    }
+
+    /// the second digit, `None` for an unknown code
+    fn last_digit(&self) -> Option<u8> {
+        match self {
+            TransactionCode::Unknown(_) => None,
+            known => Some(known.as_str().as_bytes()[1] - b'0'),
+        }
+    }
+
+    /// Advices post to the institutions' own settlement accounts, so they
+    /// count as general ledger. `None` for an unknown code.
+    pub fn account_type(&self) -> Option<AccountType> {
+        match self {
+            TransactionCode::Unknown(_) => None,
+            known => Some(match known.as_str().as_bytes()[0] {
+                b'2' => AccountType::Checking,
+                b'3' => AccountType::Savings,
+                b'4' | b'8' => AccountType::GeneralLedger,
+                _ => AccountType::Loan,
+            }),
+        }
+    }
+
+    /// automated accounting advices, only found in ADV batches
+    pub fn is_advice(&self) -> bool {
+        self.last_digit().is_some() && self.as_str().starts_with('8')
+    }
+
+    /// An unknown code is neither a debit nor a credit.
+    pub fn is_debit(&self) -> bool {
+        match self.last_digit() {
+            Some(digit) if self.is_advice() => digit % 2 == 0,
+            Some(digit) => digit >= 5,
+            None => false,
+        }
+    }
+
+    pub fn is_credit(&self) -> bool {
+        self.last_digit().is_some() && !self.is_debit()
+    }
+
+    /// returns, notifications of change and refused entries
+    pub fn is_return(&self) -> bool {
+        !self.is_advice() && matches!(self.last_digit(), Some(1 | 6))
+    }
+
+    pub fn is_prenote(&self) -> bool {
+        !self.is_advice() && matches!(self.last_digit(), Some(3 | 8))
+    }
+
+    /// zero dollar entries carrying remittance data
+    pub fn is_zero_dollar(&self) -> bool {
+        !self.is_advice() && matches!(self.last_digit(), Some(4 | 9))
+    }
+}
+
+string_code!(TransactionCode);
+
+/// Service class code of a batch, saying which kinds of entries it holds.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum ServiceClassCode {
+    #[default]
+    Mixed,
+    CreditsOnly,
+    DebitsOnly,
+    AutomatedAccountingAdvices,
+    /// a code that isn't in the rules, kept as found by a lenient parse
+    Unknown(String),
+}
+
+impl ServiceClassCode {
+    pub fn from_code(code: &str) -> Option<ServiceClassCode> {
+        match code {
+            "200" => Some(ServiceClassCode::Mixed),
+            "220" => Some(ServiceClassCode::CreditsOnly),
+            "225" => Some(ServiceClassCode::DebitsOnly),
+            "280" => Some(ServiceClassCode::AutomatedAccountingAdvices),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &str {
+        match self {
+            ServiceClassCode::Mixed => "200",
+            ServiceClassCode::CreditsOnly => "220",
+            ServiceClassCode::DebitsOnly => "225",
+            ServiceClassCode::AutomatedAccountingAdvices => "280",
+            ServiceClassCode::Unknown(code) => code,
+        }
+    }
+
+    /// Whether an entry with `code` may appear in a batch of this class.
+    /// Advices go in batches of their own.
+    pub fn allows(&self, code: &TransactionCode) -> bool {
+        match self {
+            _ if code.is_unknown() => true,
+            ServiceClassCode::Unknown(_) => true,
+            ServiceClassCode::AutomatedAccountingAdvices => code.is_advice(),
+            _ if code.is_advice() => false,
+            ServiceClassCode::CreditsOnly => code.is_credit(),
+            ServiceClassCode::DebitsOnly => code.is_debit(),
+            ServiceClassCode::Mixed => true,
+        }
+    }
+}
+
+string_code!(ServiceClassCode);
+
+/// Standard Entry Class code of a batch, saying how the entries were
+/// authorized and how the records are laid out.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum StandardEntryClass {
+    Acknowledgment,
+    AutomatedAccounting,
+    AccountsReceivable,
+    ReturnAcknowledgment,
+    BackOffice,
+    Corporate,
+    CustomerInitiated,
+    NotificationOfChange,
+    CorporateTradeExchange,
+    DeathNotification,
+    Enrollment,
+    International,
+    MachineTransfer,
+    PointOfPurchase,
+    PointOfSale,
+    #[default]
+    PrearrangedPayment,
+    RepresentedCheck,
+    SharedNetwork,
+    Telephone,
+    Truncated,
+    TruncatedExchange,
+    Internet,
+    DestroyedCheck,
+    /// a code that isn't in the rules, kept as found by a lenient parse
+    Unknown(String),
+}
+
+const STANDARD_ENTRY_CLASSES: [(StandardEntryClass, &str); 23] = [
+    (StandardEntryClass::Acknowledgment, "ACK"),
+    (StandardEntryClass::AutomatedAccounting, "ADV"),
+    (StandardEntryClass::AccountsReceivable, "ARC"),
+    (StandardEntryClass::ReturnAcknowledgment, "ATX"),
+    (StandardEntryClass::BackOffice, "BOC"),
+    (StandardEntryClass::Corporate, "CCD"),
+    (StandardEntryClass::CustomerInitiated, "CIE"),
+    (StandardEntryClass::NotificationOfChange, "COR"),
+    (StandardEntryClass::CorporateTradeExchange, "CTX"),
+    (StandardEntryClass::DeathNotification, "DNE"),
+    (StandardEntryClass::Enrollment, "ENR"),
+    (StandardEntryClass::International, "IAT"),
+    (StandardEntryClass::MachineTransfer, "MTE"),
+    (StandardEntryClass::PointOfPurchase, "POP"),
+    (StandardEntryClass::PointOfSale, "POS"),
+    (StandardEntryClass::PrearrangedPayment, "PPD"),
+    (StandardEntryClass::RepresentedCheck, "RCK"),
+    (StandardEntryClass::SharedNetwork, "SHR"),
+    (StandardEntryClass::Telephone, "TEL"),
+    (StandardEntryClass::Truncated, "TRC"),
+    (StandardEntryClass::TruncatedExchange, "TRX"),
+    (StandardEntryClass::Internet, "WEB"),
+    (StandardEntryClass::DestroyedCheck, "XCK"),
+];
+
+impl StandardEntryClass {
+    pub fn from_code(code: &str) -> Option<StandardEntryClass> {
+        STANDARD_ENTRY_CLASSES
+            .iter()
+            .find(|(_, c)| *c == code)
+            .map(|(s, _)| s.clone())
+    }
+
+    pub fn as_str(&self) -> &str {
+        if let StandardEntryClass::Unknown(code) = self {
+            return code;
+        }
+        STANDARD_ENTRY_CLASSES
+            .iter()
+            .find(|(s, _)| s == self)
+            .map(|(_, c)| *c)
+            .expect("every SEC code has an entry in the table")
+    }
+}
+
+string_code!(StandardEntryClass);
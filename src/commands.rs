@@ -0,0 +1,411 @@
+use crate::Format;
+use anyhow::{bail, Context, Result};
+use log::warn;
+use nacha::{
+    layout, Amount, BatchHeader, CsvAddenda, Currency, Diagnostic, FileHeader, ImportConfig,
+    NachaFile, NachaReader, ReadError, Record, Severity, Validator,
+};
+use std::ffi::OsStr;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::path::{Path, PathBuf};
+
+/// exit code for input that failed to parse or validate
+pub const INVALID: u8 = 1;
+/// exit code for input that couldn't be read, or output that couldn't be written
+pub const IO_ERROR: u8 = 2;
+
+/// I/O errors anywhere in the chain are reported as such, everything else
+/// means the input was invalid
+pub fn exit_code(e: &anyhow::Error) -> u8 {
+    if e.chain().any(|cause| cause.is::<io::Error>()) {
+        IO_ERROR
+    } else {
+        INVALID
+    }
+}
+
+/// reads a file, or stdin for `-`
+fn read_bytes(path: &Path) -> Result<Vec<u8>> {
+    if path == Path::new("-") {
+        let mut input = Vec::new();
+        io::stdin()
+            .read_to_end(&mut input)
+            .context("could not read stdin")?;
+        Ok(input)
+    } else {
+        std::fs::read(path).with_context(|| format!("could not read file `{}`", path.display()))
+    }
+}
+
+/// Opens a file, or stdin for `-`, for reading a line at a time.
+fn open(path: &Path) -> Result<Box<dyn BufRead>> {
+    if path == Path::new("-") {
+        Ok(Box::new(io::stdin().lock()))
+    } else {
+        let file = std::fs::File::open(path)
+            .with_context(|| format!("could not read file `{}`", path.display()))?;
+        Ok(Box::new(BufReader::new(file)))
+    }
+}
+
+/// JSON and YAML, as written by `convert`, are told apart from ACH by their
+/// extension (or a leading `{`).
+fn is_serialized(path: &Path, input: &[u8]) -> bool {
+    let ext = path.extension().and_then(OsStr::to_str);
+    matches!(ext, Some("json") | Some("yaml") | Some("yml"))
+        || input.trim_ascii_start().starts_with(b"{")
+}
+
+/// Reads a file, or stdin for `-`, and parses it. JSON and YAML, as written
+/// by `convert`, are read back by their extension (or a leading `{`).
+pub fn read(path: &Path) -> Result<NachaFile> {
+    let input = read_bytes(path)?;
+    parse(path, input)
+}
+
+fn parse(path: &Path, input: Vec<u8>) -> Result<NachaFile> {
+    let context = || format!("could not parse file `{}`", path.display());
+    let ext = path.extension().and_then(OsStr::to_str);
+    if matches!(ext, Some("yaml") | Some("yml")) {
+        let yaml = String::from_utf8(input).with_context(context)?;
+        return NachaFile::from_yaml(&yaml, false).with_context(context);
+    }
+    if is_serialized(path, &input) {
+        let json = String::from_utf8(input).with_context(context)?;
+        return NachaFile::from_json(&json, false).with_context(context);
+    }
+    let (content, diagnostics) = nacha::normalize(&input);
+    for d in diagnostics {
+        warn!("{}: {}", path.display(), d);
+    }
+    NachaFile::parse(&content).with_context(context)
+}
+
+/// An ACH input, read a record at a time so that it's never held whole.
+/// JSON and YAML have to be read whole, and are given back as the `Err`.
+fn stream(path: &Path) -> Result<std::result::Result<NachaReader<Box<dyn BufRead>>, NachaFile>> {
+    let mut input = open(path)?;
+    let start = input
+        .fill_buf()
+        .with_context(|| format!("could not read `{}`", path.display()))?;
+    if !is_serialized(path, start) {
+        return Ok(Ok(NachaReader::lenient(input)));
+    }
+    let mut bytes = Vec::new();
+    input
+        .read_to_end(&mut bytes)
+        .with_context(|| format!("could not read `{}`", path.display()))?;
+    parse(path, bytes).map(Err)
+}
+
+/// Writes every input to `out`. A single input is written as one document,
+/// several as a JSON array or YAML sequence, and NDJSON gets one line each.
+/// CSV has a row per entry, whichever file it came from, and ACH takes just
+/// the one file.
+pub fn convert(
+    out: &mut dyn Write,
+    inputs: &[PathBuf],
+    format: Format,
+    addenda: CsvAddenda,
+    recompute_controls: bool,
+) -> Result<()> {
+    let read = |path: &PathBuf| {
+        let mut file = read(path)?;
+        if recompute_controls {
+            file.recompute_controls();
+        }
+        Ok(file)
+    };
+    if format == Format::Ndjson {
+        for path in inputs {
+            serde_json::to_writer(&mut *out, &read(path)?)?;
+            writeln!(out)?;
+        }
+        return Ok(());
+    }
+    let files = inputs.iter().map(read).collect::<Result<Vec<_>>>()?;
+    match (format, files.as_slice()) {
+        (Format::Ach, [file]) => write!(out, "{}", file.to_ach_string()?)?,
+        (Format::Ach, _) => bail!("ach output takes a single file"),
+        (Format::Csv, files) => nacha::write_csv(&mut *out, files, addenda)?,
+        (Format::Yaml, [file]) => write!(out, "{}", file.as_yaml())?,
+        (Format::Yaml, files) => serde_yaml::to_writer(&mut *out, files)?,
+        (_, [file]) => writeln!(out, "{}", file.as_json())?,
+        (_, files) => {
+            serde_json::to_writer_pretty(&mut *out, files)?;
+            writeln!(out)?;
+        }
+    }
+    Ok(())
+}
+
+/// Parses each input leniently, so that every problem is listed rather than
+/// just the first, then checks it. ACH input is checked a batch at a time as
+/// it's read. Returns whether all of them were valid.
+pub fn validate(out: &mut dyn Write, inputs: &[PathBuf]) -> Result<bool> {
+    let mut all_valid = true;
+    for path in inputs {
+        let (errors, diagnostics) = match stream(path)? {
+            Ok(mut reader) => {
+                let mut validator = Validator::new();
+                let mut diagnostics = Vec::new();
+                while let Some(result) = reader.next() {
+                    match result {
+                        Ok((_, record)) => validator.push(record),
+                        Err(ReadError::Parse(e)) => diagnostics.push(Diagnostic::from(e)),
+                        Err(ReadError::Io(e)) => {
+                            return Err(e)
+                                .with_context(|| format!("could not read `{}`", path.display()))
+                        }
+                    }
+                    diagnostics.extend(reader.take_diagnostics());
+                }
+                diagnostics.extend(reader.take_diagnostics());
+                (validator.finish(), diagnostics)
+            }
+            Err(file) => (file.validate(), Vec::new()),
+        };
+        let valid = errors.is_empty() && diagnostics.iter().all(|d| d.severity != Severity::Error);
+        writeln!(
+            out,
+            "{}: {}",
+            path.display(),
+            if valid { "valid" } else { "invalid" }
+        )?;
+        for d in &diagnostics {
+            writeln!(out, "  {}", d)?;
+        }
+        for e in &errors {
+            writeln!(out, "  error: {}", e)?;
+        }
+        all_valid &= valid;
+    }
+    Ok(all_valid)
+}
+
+/// Running totals for [`summary`], which prints a line for each batch as
+/// it ends.
+#[derive(Default)]
+struct Totals {
+    batches: usize,
+    entries: usize,
+    addenda: usize,
+    debit: Amount,
+    credit: Amount,
+}
+
+/// Prints the origin and destination of each input, a line per batch and
+/// the file's totals. ACH input is read a record at a time, so only one
+/// batch header is ever held.
+pub fn summary(out: &mut dyn Write, inputs: &[PathBuf]) -> Result<()> {
+    for path in inputs {
+        writeln!(out, "{}", path.display())?;
+        let mut reader = match stream(path)? {
+            Ok(reader) => reader,
+            Err(file) => {
+                summarize(out, &file)?;
+                continue;
+            }
+        };
+        let context = || format!("could not parse file `{}`", path.display());
+        let mut header_written = false;
+        let mut batch: Option<(BatchHeader, Totals)> = None;
+        let mut file = Totals::default();
+        while let Some(result) = reader.next() {
+            let (_, record) = match result {
+                Ok(record) => record,
+                Err(ReadError::Io(e)) => return Err(e).with_context(context),
+                Err(ReadError::Parse(e)) => return Err(e).with_context(context),
+            };
+            for d in reader.take_diagnostics() {
+                if d.severity == Severity::Error {
+                    return Err(anyhow::anyhow!(d.to_string())).with_context(context);
+                }
+                warn!("{}: {}", path.display(), d);
+            }
+            if !header_written {
+                let default = FileHeader::default();
+                let header = match &record {
+                    Record::FileHeader(header) => header,
+                    _ => &default,
+                };
+                write_file_header(out, header)?;
+                header_written = true;
+            }
+            match record {
+                Record::BatchHeader(header) => {
+                    if let Some((header, totals)) = batch.take() {
+                        write_batch(out, &header, &totals, &mut file)?;
+                    }
+                    batch = Some((header, Totals::default()));
+                }
+                Record::Entry(entry) => {
+                    if let Some((_, totals)) = &mut batch {
+                        totals.entries += 1;
+                        if entry.transaction_code.is_debit() {
+                            totals.debit = totals.debit.saturating_add(entry.amount);
+                        } else if entry.transaction_code.is_credit() {
+                            totals.credit = totals.credit.saturating_add(entry.amount);
+                        }
+                    }
+                }
+                Record::Addendum(_) => {
+                    if let Some((_, totals)) = &mut batch {
+                        totals.addenda += 1;
+                    }
+                }
+                _ => {}
+            }
+        }
+        for d in reader.take_diagnostics() {
+            warn!("{}: {}", path.display(), d);
+        }
+        if !header_written {
+            write_file_header(out, &FileHeader::default())?;
+        }
+        if let Some((header, totals)) = batch.take() {
+            write_batch(out, &header, &totals, &mut file)?;
+        }
+        write_totals(out, &file)?;
+    }
+    Ok(())
+}
+
+/// the summary of a file read whole, from JSON or YAML
+fn summarize(out: &mut dyn Write, file: &NachaFile) -> Result<()> {
+    write_file_header(out, &file.file_header)?;
+    let mut totals = Totals::default();
+    for batch in &file.batches {
+        let mut batch_totals = Totals::default();
+        for e in &batch.detail_entries {
+            if e.transaction_code.is_debit() {
+                batch_totals.debit = batch_totals.debit.saturating_add(e.amount);
+            } else if e.transaction_code.is_credit() {
+                batch_totals.credit = batch_totals.credit.saturating_add(e.amount);
+            }
+            batch_totals.addenda += e.addenda.len();
+        }
+        batch_totals.entries = batch.detail_entries.len();
+        write_batch(out, &batch.batch_header, &batch_totals, &mut totals)?;
+    }
+    write_totals(out, &totals)
+}
+
+fn write_file_header(out: &mut dyn Write, header: &FileHeader) -> Result<()> {
+    writeln!(
+        out,
+        "  from {} ({}) to {} ({}), created {}",
+        header.immediate_origin_name,
+        header.immediate_origin.trim(),
+        header.immediate_destination_name,
+        header.immediate_destination.trim(),
+        header
+            .file_creation_date
+            .map(|d| d.to_string())
+            .unwrap_or_default()
+    )?;
+    Ok(())
+}
+
+/// writes a batch's line and adds it to the file's totals
+fn write_batch(
+    out: &mut dyn Write,
+    bh: &BatchHeader,
+    batch: &Totals,
+    file: &mut Totals,
+) -> Result<()> {
+    file.batches += 1;
+    file.entries += batch.entries;
+    file.addenda += batch.addenda;
+    file.debit = file.debit.saturating_add(batch.debit);
+    file.credit = file.credit.saturating_add(batch.credit);
+    writeln!(
+        out,
+        "  batch {} {} {:<10} {:<16} effective {}: {} entries, debits {}, credits {}",
+        bh.batch_number,
+        bh.standard_entry_class_code,
+        bh.company_entry_description,
+        bh.company_name,
+        bh.effective_entry_date
+            .map(|d| d.to_string())
+            .unwrap_or_default(),
+        batch.entries,
+        batch.debit.pretty_dollars_cents(),
+        batch.credit.pretty_dollars_cents()
+    )?;
+    Ok(())
+}
+
+fn write_totals(out: &mut dyn Write, totals: &Totals) -> Result<()> {
+    writeln!(
+        out,
+        "  {} batches, {} entries, {} addenda, debits {}, credits {}",
+        totals.batches,
+        totals.entries,
+        totals.addenda,
+        totals.debit.pretty_dollars_cents(),
+        totals.credit.pretty_dollars_cents()
+    )?;
+    Ok(())
+}
+
+/// Builds a file from the CSV at `path`, as described by the YAML at
+/// `config`. Bad rows, and anything wrong with the result, are printed to
+/// stderr and give `None`.
+pub fn build(path: &Path, config_path: &Path) -> Result<Option<NachaFile>> {
+    let config: ImportConfig = serde_yaml::from_slice(&read_bytes(config_path)?)
+        .with_context(|| format!("could not parse config `{}`", config_path.display()))?;
+    let file = match NachaFile::from_csv(read_bytes(path)?.as_slice(), &config) {
+        Ok(file) => file,
+        Err(errors) => {
+            for e in errors {
+                let source = if e.line.is_some() { path } else { config_path };
+                eprintln!("{}: {}", source.display(), e);
+            }
+            return Ok(None);
+        }
+    };
+    let errors = file.validate();
+    for e in &errors {
+        eprintln!("{}: {}", path.display(), e);
+    }
+    Ok(errors.is_empty().then_some(file))
+}
+
+/// Prints the column layout of `record`, or of every record type.
+pub fn layout(out: &mut dyn Write, record: Option<&str>) -> Result<()> {
+    let layouts: Vec<_> = layout::ALL
+        .iter()
+        .filter(|(name, _)| record.map_or(true, |r| r == *name))
+        .collect();
+    if layouts.is_empty() {
+        let names: Vec<_> = layout::ALL.iter().map(|(name, _)| *name).collect();
+        bail!(
+            "no record called `{}`, try one of: {}",
+            record.unwrap_or_default(),
+            names.join(", ")
+        );
+    }
+    for (name, fields) in layouts {
+        writeln!(out, "{}", name)?;
+        let width = fields.iter().map(|f| f.name.len()).max().unwrap_or(0);
+        for field in fields.iter() {
+            writeln!(
+                out,
+                "  {:>2}-{:<2}  {:<width$}  {:<12}  {:<8}  {}",
+                field.start,
+                field.end,
+                field.name,
+                field.kind,
+                if field.required {
+                    "required"
+                } else {
+                    "optional"
+                },
+                field.description,
+                width = width
+            )?;
+        }
+    }
+    Ok(())
+}
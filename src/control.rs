@@ -0,0 +1,116 @@
+use crate::{Amount, Batch, BatchControl, DetailEntry, FileControl, NachaFile};
+
+const ENTRY_HASH_MODULUS: u64 = 10_000_000_000;
+
+/// Sum of the 8 digit RDFI routing prefixes, keeping the rightmost 10 digits.
+pub(crate) fn entry_hash<'a>(entries: impl Iterator<Item = &'a DetailEntry>) -> u64 {
+    entries
+        .map(|e| e.receiving_dfi_id.parse::<u64>().unwrap_or(0))
+        .fold(0, |sum, dfi| (sum + dfi) % ENTRY_HASH_MODULUS)
+}
+
+/// Total debits and total credits. Entries with an unknown transaction code
+/// are in neither.
+pub(crate) fn totals<'a>(entries: impl Iterator<Item = &'a DetailEntry>) -> (Amount, Amount) {
+    let (mut total_debit, mut total_credit) = (Amount::ZERO, Amount::ZERO);
+    for entry in entries {
+        if entry.transaction_code.is_debit() {
+            total_debit = total_debit.saturating_add(entry.amount);
+        } else if entry.transaction_code.is_credit() {
+            total_credit = total_credit.saturating_add(entry.amount);
+        }
+    }
+    (total_debit, total_credit)
+}
+
+impl Batch {
+    /// Works out what the batch control should hold from the batch header
+    /// and entries.
+    pub fn compute_control(&self) -> BatchControl {
+        let addenda: usize = self.detail_entries.iter().map(|e| e.addenda.len()).sum();
+        let (total_debit, total_credit) = totals(self.detail_entries.iter());
+        BatchControl {
+            record_type_code: "8".to_string(),
+            service_class_code: self.batch_header.service_class_code.clone(),
+            entry_addenda_count: format!("{:06}", self.detail_entries.len() + addenda),
+            entry_hash: format!("{:010}", entry_hash(self.detail_entries.iter())),
+            total_debit,
+            total_credit,
+            company_id: self.batch_header.company_id.clone(),
+            message_authentication_code: self.batch_control.message_authentication_code.clone(),
+            reserved: "".to_string(),
+            originating_dfi_id: self.batch_header.originating_dfi_id.clone(),
+            batch_number: self.batch_header.batch_number.clone(),
+        }
+    }
+
+    /// Number of records in the batch, including its header and control.
+    pub fn record_count(&self) -> usize {
+        let addenda: usize = self.detail_entries.iter().map(|e| e.addenda.len()).sum();
+        self.detail_entries.len() + addenda + 2
+    }
+}
+
+/// The file control's counts and totals, added up a batch at a time so
+/// that a file can be checked without holding every batch.
+#[derive(Debug, Default)]
+pub(crate) struct FileTotals {
+    batches: u32,
+    /// of every batch, headers and controls included
+    records: usize,
+    entries_and_addenda: u32,
+    entry_hash: u64,
+    total_debit: Amount,
+    total_credit: Amount,
+}
+
+impl FileTotals {
+    pub(crate) fn add(&mut self, batch: &Batch) {
+        let entries = batch.detail_entries.iter();
+        let (total_debit, total_credit) = totals(entries.clone());
+        self.batches += 1;
+        self.records += batch.record_count();
+        self.entries_and_addenda += entries
+            .clone()
+            .map(|e| 1 + e.addenda.len() as u32)
+            .sum::<u32>();
+        self.entry_hash = (self.entry_hash + entry_hash(entries)) % ENTRY_HASH_MODULUS;
+        self.total_debit = self.total_debit.saturating_add(total_debit);
+        self.total_credit = self.total_credit.saturating_add(total_credit);
+    }
+
+    /// what the file control should hold, counting the file header and
+    /// control records into the blocks
+    pub(crate) fn control(&self, blocking_factor: usize) -> FileControl {
+        FileControl {
+            record_type_code: "9".to_string(),
+            batch_count: self.batches,
+            block_count: (self.records + 2).div_ceil(blocking_factor) as u32,
+            entry_and_addenda_count: self.entries_and_addenda,
+            entry_hash: format!("{:010}", self.entry_hash),
+            total_debit: self.total_debit,
+            total_credit: self.total_credit,
+            reserved: "".to_string(),
+        }
+    }
+}
+
+impl NachaFile {
+    /// Works out what the file control should hold from the batches.
+    pub fn compute_control(&self) -> FileControl {
+        let mut totals = FileTotals::default();
+        for batch in &self.batches {
+            totals.add(batch);
+        }
+        totals.control(self.file_header.blocking_factor_or_default())
+    }
+
+    /// Overwrites every batch control and the file control with values
+    /// computed from the entries.
+    pub fn recompute_controls(&mut self) {
+        for batch in &mut self.batches {
+            batch.batch_control = batch.compute_control();
+        }
+        self.file_control = self.compute_control();
+    }
+}
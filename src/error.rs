@@ -0,0 +1,198 @@
+use serde::Serialize;
+use std::fmt;
+use std::io;
+use std::ops::Range;
+
+/// What went wrong while reading a field or record.
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ParseErrorKind {
+    /// the line ends before the field does
+    ShortLine,
+    /// a numeric field holds something other than digits
+    InvalidNumber,
+    /// a code field holds a value the NACHA rules don't define
+    InvalidCode,
+    /// the first character is not a known record type
+    UnknownRecordType,
+    /// an entry, addendum or batch control with no batch (or entry) to attach to
+    OrphanRecord,
+}
+
+impl fmt::Display for ParseErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            ParseErrorKind::ShortLine => "line too short",
+            ParseErrorKind::InvalidNumber => "invalid number",
+            ParseErrorKind::InvalidCode => "invalid code",
+            ParseErrorKind::UnknownRecordType => "unknown record type",
+            ParseErrorKind::OrphanRecord => "record has no parent",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+/// A problem found while parsing, pointing at the exact field responsible.
+#[derive(Debug, Serialize, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    pub kind: ParseErrorKind,
+    /// 1-based line number in the input
+    pub line: usize,
+    pub record_type: String,
+    pub field: &'static str,
+    /// the field's columns within the line, as characters counted like
+    /// [`Field::range`](crate::Field::range), so 0-based and exclusive;
+    /// they're shown as the 1-based record positions of the layout
+    pub range: Range<usize>,
+    /// the text found at `range`, or whatever was left of the line
+    pub text: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "line {}: {} in `{}` (record type {}, columns {}-{}): {:?}",
+            self.line,
+            self.kind,
+            self.field,
+            self.record_type,
+            self.range.start + 1,
+            self.range.end,
+            self.text
+        )
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// A value too wide for the field it's written to, which would otherwise
+/// have to be cut short.
+#[derive(Debug, Serialize, Clone, PartialEq, Eq)]
+pub struct WriteError {
+    pub record_type: String,
+    pub field: &'static str,
+    /// in characters
+    pub width: usize,
+    pub value: String,
+}
+
+impl fmt::Display for WriteError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:?} doesn't fit in `{}` (record type {}), which is {} characters wide",
+            self.value, self.field, self.record_type, self.width
+        )
+    }
+}
+
+impl std::error::Error for WriteError {}
+
+/// An error from [`NachaReader`](crate::NachaReader), which can fail to read
+/// the input as well as to parse it.
+#[derive(Debug)]
+pub enum ReadError {
+    Io(io::Error),
+    Parse(ParseError),
+}
+
+impl fmt::Display for ReadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReadError::Io(e) => write!(f, "{}", e),
+            ReadError::Parse(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for ReadError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ReadError::Io(e) => Some(e),
+            ReadError::Parse(e) => Some(e),
+        }
+    }
+}
+
+impl From<io::Error> for ReadError {
+    fn from(e: io::Error) -> ReadError {
+        ReadError::Io(e)
+    }
+}
+
+impl From<ParseError> for ReadError {
+    fn from(e: ParseError) -> ReadError {
+        ReadError::Parse(e)
+    }
+}
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Severity::Error => write!(f, "error"),
+            Severity::Warning => write!(f, "warning"),
+        }
+    }
+}
+
+/// A problem collected by [`NachaFile::parse_lenient`](crate::NachaFile::parse_lenient).
+/// Unlike [`ParseError`], a diagnostic may not point at a particular line or
+/// field, e.g. when a whole record is missing.
+#[derive(Debug, Serialize, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    /// 1-based line number in the input
+    pub line: Option<usize>,
+    pub field: Option<&'static str>,
+    /// the field's columns within the line, as in [`ParseError::range`]
+    pub range: Option<Range<usize>>,
+    pub message: String,
+}
+
+impl Diagnostic {
+    pub fn warning(line: Option<usize>, message: impl Into<String>) -> Diagnostic {
+        Diagnostic {
+            severity: Severity::Warning,
+            line,
+            field: None,
+            range: None,
+            message: message.into(),
+        }
+    }
+}
+
+impl From<ParseError> for Diagnostic {
+    fn from(e: ParseError) -> Diagnostic {
+        Diagnostic {
+            severity: Severity::Error,
+            line: Some(e.line),
+            field: Some(e.field),
+            range: Some(e.range.clone()),
+            message: format!("{} (record type {}): {:?}", e.kind, e.record_type, e.text),
+        }
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.severity)?;
+        if let Some(line) = self.line {
+            write!(f, ": line {}", line)?;
+        }
+        if let Some(field) = self.field {
+            write!(f, ": `{}`", field)?;
+        }
+        if let Some(range) = &self.range {
+            write!(f, " (columns {}-{})", range.start + 1, range.end)?;
+        }
+        write!(f, ": {}", self.message)
+    }
+}
@@ -0,0 +1,201 @@
+use crate::{Batch, DetailEntry, NachaFile};
+use std::io;
+
+/// How [`write_csv`] lays out an entry's addenda.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CsvAddenda {
+    /// left out altogether
+    None,
+    /// the summary of each addendum in one `addenda` column, separated by `; `
+    #[default]
+    Joined,
+    /// an `addenda_1`, `addenda_2`, ... column for each, as many as the
+    /// entry with the most addenda needs
+    Columns,
+}
+
+const COLUMNS: [&str; 14] = [
+    "batch_number",
+    "company_name",
+    "company_id",
+    "standard_entry_class_code",
+    "company_entry_description",
+    "effective_entry_date",
+    "trace_number",
+    "transaction_code",
+    "direction",
+    "amount",
+    "routing_number",
+    "dfi_account_number",
+    "individual_id_number",
+    "individual_name",
+];
+
+/// Writes the detail entries of `files` as CSV, one row per entry along with
+/// the header of its batch. Amounts are in dollars, e.g. `1234.56`.
+pub fn write_csv<W: io::Write>(
+    out: W,
+    files: &[NachaFile],
+    addenda: CsvAddenda,
+) -> csv::Result<()> {
+    let entries = || {
+        files.iter().flat_map(|f| {
+            f.batches
+                .iter()
+                .flat_map(|b| b.detail_entries.iter().map(move |e| (b, e)))
+        })
+    };
+    let addenda_columns = match addenda {
+        CsvAddenda::Columns => entries().map(|(_, e)| e.addenda.len()).max().unwrap_or(0),
+        _ => 0,
+    };
+
+    let mut writer = csv::Writer::from_writer(out);
+    let mut header: Vec<String> = COLUMNS.iter().map(|c| c.to_string()).collect();
+    match addenda {
+        CsvAddenda::None => {}
+        CsvAddenda::Joined => header.push("addenda".to_string()),
+        CsvAddenda::Columns => {
+            header.extend((1..=addenda_columns).map(|i| format!("addenda_{}", i)))
+        }
+    }
+    writer.write_record(&header)?;
+
+    for (batch, entry) in entries() {
+        let mut record = row(batch, entry);
+        let summaries = entry.addenda.iter().map(|a| a.summary());
+        match addenda {
+            CsvAddenda::None => {}
+            CsvAddenda::Joined => record.push(summaries.collect::<Vec<_>>().join("; ")),
+            CsvAddenda::Columns => {
+                record.extend(summaries);
+                record.resize(COLUMNS.len() + addenda_columns, String::new());
+            }
+        }
+        writer.write_record(&record)?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+fn row(batch: &Batch, entry: &DetailEntry) -> Vec<String> {
+    let header = &batch.batch_header;
+    let direction = if entry.transaction_code.is_debit() {
+        "debit"
+    } else if entry.transaction_code.is_credit() {
+        "credit"
+    } else {
+        ""
+    };
+    // IAT batch headers have no company name, so name the originator by id
+    let company_name = if header.company_name.is_empty() {
+        &header.company_id
+    } else {
+        &header.company_name
+    };
+    vec![
+        header.batch_number.clone(),
+        company_name.clone(),
+        header.company_id.clone(),
+        header.standard_entry_class_code.to_string(),
+        header.company_entry_description.clone(),
+        header
+            .effective_entry_date
+            .map(|d| d.to_string())
+            .unwrap_or_default(),
+        entry.trace_number.clone(),
+        entry.transaction_code.to_string(),
+        direction.to_string(),
+        entry.amount.to_string(),
+        format!("{}{}", entry.receiving_dfi_id, entry.check_digit),
+        entry.dfi_account_number.clone(),
+        entry.individual_id_number.clone(),
+        entry.receiver_name().to_string(),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// the IAT batch of full.ach, whose entries have nine addenda, and the
+    /// PPD batch of small.ach, with none
+    fn csv(addenda: CsvAddenda) -> Vec<Vec<String>> {
+        let files = [
+            NachaFile::parse(include_str!("../examples/full.ach")).unwrap(),
+            NachaFile::parse(include_str!("../examples/small.ach")).unwrap(),
+        ];
+        let mut out = Vec::new();
+        write_csv(&mut out, &files, addenda).unwrap();
+        csv::ReaderBuilder::new()
+            .has_headers(false)
+            .from_reader(out.as_slice())
+            .records()
+            .map(|r| r.unwrap().iter().map(str::to_string).collect())
+            .collect()
+    }
+
+    #[test]
+    fn names_iat_originators_by_their_id() {
+        let rows = csv(CsvAddenda::None);
+        assert_eq!(rows[0], COLUMNS);
+        assert_eq!(rows.len(), 4);
+        assert_eq!(rows[1][1..4], ["123456789", "123456789", "IAT"]);
+        assert_eq!(rows[3][1..4], ["Name on Account", "121042882", "PPD"]);
+        assert_eq!(
+            rows[1][6..],
+            [
+                "231380100000001",
+                "27",
+                "debit",
+                "1000.00",
+                "121042882",
+                "123456789",
+                "",
+                "BEK Enterprises"
+            ]
+        );
+    }
+
+    #[test]
+    fn joins_addenda_into_one_column() {
+        let rows = csv(CsvAddenda::Joined);
+        assert_eq!(rows[0].len(), COLUMNS.len() + 1);
+        assert_eq!(rows[0].last().unwrap(), "addenda");
+        let addenda: Vec<_> = rows[1].last().unwrap().split("; ").collect();
+        assert_eq!(addenda.len(), 9);
+        assert_eq!(
+            addenda[0],
+            "ANN 1,000.00 to BEK Enterprises (foreign trace 928383-23938)"
+        );
+        assert_eq!(addenda[7], "This is an international payment");
+        assert_eq!(rows[3].last().unwrap(), "");
+    }
+
+    #[test]
+    fn gives_each_addendum_a_column() {
+        let rows = csv(CsvAddenda::Columns);
+        let header = &rows[0][COLUMNS.len()..];
+        assert_eq!(
+            header,
+            (1..=9)
+                .map(|i| format!("addenda_{}", i))
+                .collect::<Vec<_>>()
+        );
+        for row in &rows {
+            assert_eq!(row.len(), COLUMNS.len() + 9);
+        }
+        let addenda = &rows[2][COLUMNS.len()..];
+        assert_eq!(
+            addenda[0],
+            "ANN 1,000.00 to ADCAF Enterprises (foreign trace 928383-23938)"
+        );
+        assert_eq!(addenda[5], "receiver: 987465493213987, 18 Fifth Street");
+        assert_eq!(
+            addenda[8],
+            "correspondent bank: Bank of France (01 456456456987987) FR"
+        );
+        // an entry without addenda leaves its columns empty
+        assert!(rows[3][COLUMNS.len()..].iter().all(String::is_empty));
+    }
+}
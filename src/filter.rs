@@ -0,0 +1,236 @@
+use nacha::{Amount, BatchHeader, Currency, DetailEntry};
+use std::cmp::Ordering;
+use std::fmt;
+
+/// Conditions an entry has to meet to stay in the table, all of them, e.g.
+/// `amount>10000 txn=27 sec=WEB`.
+pub struct Filter {
+    text: String,
+    conditions: Vec<Condition>,
+}
+
+struct Condition {
+    field: FilterField,
+    op: Op,
+    value: String,
+    /// the value of an `amount` condition
+    amount: Option<Amount>,
+}
+
+#[derive(Clone, Copy)]
+enum FilterField {
+    /// in dollars
+    Amount,
+    Txn,
+    Sec,
+    Name,
+    Account,
+    Trace,
+    Id,
+    Batch,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Op {
+    Eq,
+    Ne,
+    Gt,
+    Ge,
+    Lt,
+    Le,
+}
+
+impl Op {
+    fn holds(self, ordering: Ordering) -> bool {
+        match self {
+            Op::Eq => ordering == Ordering::Equal,
+            Op::Ne => ordering != Ordering::Equal,
+            Op::Gt => ordering == Ordering::Greater,
+            Op::Ge => ordering != Ordering::Less,
+            Op::Lt => ordering == Ordering::Less,
+            Op::Le => ordering != Ordering::Greater,
+        }
+    }
+}
+
+impl Filter {
+    /// Parses space separated `field<op>value` conditions. The fields are
+    /// `amount`, `txn`, `sec`, `name`, `account`, `trace`, `id` and `batch`,
+    /// and the operators `=`, `!=`, `>`, `>=`, `<` and `<=`.
+    pub fn parse(text: &str) -> Result<Filter, String> {
+        let conditions = text
+            .split_whitespace()
+            .map(Condition::parse)
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Filter {
+            text: text.trim().to_string(),
+            conditions,
+        })
+    }
+
+    pub fn matches(&self, entry: &DetailEntry, header: &BatchHeader) -> bool {
+        self.conditions.iter().all(|c| c.matches(entry, header))
+    }
+}
+
+impl fmt::Display for Filter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.text)
+    }
+}
+
+impl Condition {
+    fn parse(text: &str) -> Result<Condition, String> {
+        let at = text
+            .find(['=', '!', '>', '<'])
+            .ok_or_else(|| format!("`{}` needs an operator, e.g. amount>100", text))?;
+        let (name, rest) = text.split_at(at);
+        let (op, value) = [
+            ("!=", Op::Ne),
+            (">=", Op::Ge),
+            ("<=", Op::Le),
+            ("=", Op::Eq),
+            (">", Op::Gt),
+            ("<", Op::Lt),
+        ]
+        .iter()
+        .find_map(|(s, op)| rest.strip_prefix(s).map(|value| (*op, value)))
+        .ok_or_else(|| format!("unknown operator in `{}`", text))?;
+        let field = match name.to_lowercase().as_str() {
+            "amount" => FilterField::Amount,
+            "txn" => FilterField::Txn,
+            "sec" => FilterField::Sec,
+            "name" => FilterField::Name,
+            "account" => FilterField::Account,
+            "trace" => FilterField::Trace,
+            "id" => FilterField::Id,
+            "batch" => FilterField::Batch,
+            _ => return Err(format!("unknown field `{}`", name)),
+        };
+        let amount = match field {
+            FilterField::Amount => Some(value.parse::<Amount>().map_err(|e| e.to_string())?),
+            _ => None,
+        };
+        Ok(Condition {
+            field,
+            op,
+            value: value.to_string(),
+            amount,
+        })
+    }
+
+    fn matches(&self, entry: &DetailEntry, header: &BatchHeader) -> bool {
+        match self.field {
+            FilterField::Amount => self
+                .amount
+                .is_some_and(|amount| self.op.holds(entry.amount.cmp(&amount))),
+            FilterField::Txn => self.number(entry.transaction_code.as_str()),
+            FilterField::Batch => self.number(&header.batch_number),
+            FilterField::Sec => self.text(header.standard_entry_class_code.as_str()),
+            FilterField::Name => self.text(entry.receiver_name()),
+            FilterField::Account => self.text(&entry.dfi_account_number),
+            FilterField::Trace => self.text(&entry.trace_number),
+            FilterField::Id => self.text(&entry.individual_id_number),
+        }
+    }
+
+    /// codes and numbers compare by value, so `batch=1` finds `0000001`
+    fn number(&self, actual: &str) -> bool {
+        match (actual.trim().parse::<u64>(), self.value.parse::<u64>()) {
+            (Ok(a), Ok(v)) => self.op.holds(a.cmp(&v)),
+            _ => self.text(actual),
+        }
+    }
+
+    /// text matches if it contains the value, ignoring case; `>` and `<`
+    /// compare alphabetically
+    fn text(&self, actual: &str) -> bool {
+        let (actual, value) = (actual.to_lowercase(), self.value.to_lowercase());
+        match self.op {
+            Op::Eq => actual.contains(&value),
+            Op::Ne => !actual.contains(&value),
+            op => op.holds(actual.as_str().cmp(value.as_str())),
+        }
+    }
+}
+
+/// Whether `query` is in the entry's name, account number, trace number,
+/// individual id or amount, ignoring case.
+pub fn search_matches(entry: &DetailEntry, query: &str) -> bool {
+    let query = query.to_lowercase();
+    [
+        entry.receiver_name().to_lowercase(),
+        entry.dfi_account_number.to_lowercase(),
+        entry.trace_number.clone(),
+        entry.individual_id_number.to_lowercase(),
+        entry.amount.to_string(),
+        entry.amount.pretty_dollars_cents(),
+    ]
+    .iter()
+    .any(|field| field.contains(&query))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nacha::NachaFile;
+
+    fn matches(filter: &str) -> bool {
+        let file = NachaFile::parse(include_str!("../examples/small.ach")).unwrap();
+        let batch = &file.batches[0];
+        Filter::parse(filter)
+            .unwrap()
+            .matches(&batch.detail_entries[0], &batch.batch_header)
+    }
+
+    fn error(filter: &str) -> String {
+        Filter::parse(filter).err().unwrap()
+    }
+
+    #[test]
+    fn parses_every_operator() {
+        assert!(matches("amount=1000000"));
+        assert!(matches("amount!=1"));
+        assert!(matches("amount>999999.99"));
+        assert!(matches("amount>=1,000,000.00"));
+        assert!(matches("amount<1000000.01"));
+        assert!(matches("amount<=1000000"));
+        assert!(!matches("amount>1000000"));
+        assert!(!matches("amount<1000000"));
+    }
+
+    #[test]
+    fn all_conditions_have_to_hold() {
+        assert!(matches("  txn=27   sec=ppd  batch=1 "));
+        assert!(!matches("txn=27 sec=web"));
+        assert!(matches(""));
+        assert_eq!(
+            Filter::parse(" txn=27  sec=ppd ").unwrap().to_string(),
+            "txn=27  sec=ppd"
+        );
+    }
+
+    #[test]
+    fn numbers_compare_by_value_and_text_by_substring() {
+        assert!(matches("batch=0000001"));
+        assert!(matches("batch<2"));
+        assert!(matches("txn>22"));
+        assert!(matches("name=account"));
+        assert!(matches("NAME!=payroll"));
+        assert!(matches("trace=0000001"));
+        assert!(matches("sec>ccd"));
+    }
+
+    #[test]
+    fn reports_bad_conditions() {
+        assert_eq!(
+            error("amount"),
+            "`amount` needs an operator, e.g. amount>100"
+        );
+        assert_eq!(error("color=red"), "unknown field `color`");
+        assert_eq!(error("txn=27 foo<1"), "unknown field `foo`");
+        assert_eq!(error("amount!5"), "unknown operator in `amount!5`");
+        assert_eq!(error("amount>ten"), "invalid amount \"ten\"");
+        assert_eq!(error("amount=>5"), "invalid amount \">5\"");
+    }
+}
@@ -0,0 +1,430 @@
+//! Record layouts for International ACH Transactions (SEC code `IAT`), which
+//! move several batch header and entry fields around and carry the
+//! originator, receiver and bank details in addenda types 10 through 18.
+use crate::layout::{
+    iat_bank_addendum, iat_batch_header, iat_entry_detail, iat_originator_addendum,
+    iat_originator_address_addendum, iat_payment_addendum, iat_receiver_addendum,
+    iat_receiver_address_addendum,
+};
+use crate::writer::RecordWriter;
+use crate::{Amount, Line, ParseError, WriteError};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// The parts of an IAT batch header that take the place of the company name,
+/// discretionary data and descriptive date of other batches. The originator
+/// identification is held in [`BatchHeader::company_id`](crate::BatchHeader).
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq)]
+pub struct IatBatchHeader {
+    pub iat_indicator: String,
+    pub foreign_exchange_indicator: String,
+    pub foreign_exchange_reference_indicator: String,
+    pub foreign_exchange_reference: String,
+    pub iso_destination_country_code: String,
+    pub iso_originating_currency_code: String,
+    pub iso_destination_currency_code: String,
+}
+
+impl IatBatchHeader {
+    pub(crate) fn from_line(line: &Line) -> Result<IatBatchHeader, ParseError> {
+        Ok(IatBatchHeader {
+            iat_indicator: line.alpha(iat_batch_header::IAT_INDICATOR)?,
+            foreign_exchange_indicator: line.alpha(iat_batch_header::FOREIGN_EXCHANGE_INDICATOR)?,
+            foreign_exchange_reference_indicator: line
+                .alpha(iat_batch_header::FOREIGN_EXCHANGE_REFERENCE_INDICATOR)?,
+            foreign_exchange_reference: line.alpha(iat_batch_header::FOREIGN_EXCHANGE_REFERENCE)?,
+            iso_destination_country_code: line
+                .alpha(iat_batch_header::ISO_DESTINATION_COUNTRY_CODE)?,
+            iso_originating_currency_code: line
+                .alpha(iat_batch_header::ISO_ORIGINATING_CURRENCY_CODE)?,
+            iso_destination_currency_code: line
+                .alpha(iat_batch_header::ISO_DESTINATION_CURRENCY_CODE)?,
+        })
+    }
+}
+
+/// The parts of an IAT entry detail that differ from other entries. The
+/// foreign receiver's account number is held in
+/// [`DetailEntry::dfi_account_number`](crate::DetailEntry).
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq)]
+pub struct IatEntryDetail {
+    pub number_of_addenda: String,
+    pub gateway_ofac_screening_indicator: String,
+    pub secondary_ofac_screening_indicator: String,
+}
+
+impl IatEntryDetail {
+    pub(crate) fn from_line(line: &Line) -> Result<IatEntryDetail, ParseError> {
+        Ok(IatEntryDetail {
+            number_of_addenda: line.alpha(iat_entry_detail::NUMBER_OF_ADDENDA)?,
+            gateway_ofac_screening_indicator: line
+                .alpha(iat_entry_detail::GATEWAY_OFAC_SCREENING_INDICATOR)?,
+            secondary_ofac_screening_indicator: line
+                .alpha(iat_entry_detail::SECONDARY_OFAC_SCREENING_INDICATOR)?,
+        })
+    }
+}
+
+/// Addenda type 10: the payment itself.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq)]
+pub struct IatPaymentAddendum {
+    pub transaction_type_code: String,
+    pub foreign_payment_amount: Amount,
+    pub foreign_trace_number: String,
+    pub receiving_name: String,
+}
+
+impl IatPaymentAddendum {
+    pub(crate) fn from_line(line: &Line) -> Result<IatPaymentAddendum, ParseError> {
+        Ok(IatPaymentAddendum {
+            transaction_type_code: line.alpha(iat_payment_addendum::TRANSACTION_TYPE_CODE)?,
+            foreign_payment_amount: line.amount(iat_payment_addendum::FOREIGN_PAYMENT_AMOUNT)?,
+            foreign_trace_number: line.alpha(iat_payment_addendum::FOREIGN_TRACE_NUMBER)?,
+            receiving_name: line.alpha(iat_payment_addendum::RECEIVING_NAME)?,
+        })
+    }
+
+    pub(crate) fn write(&self, record: RecordWriter) -> Result<RecordWriter, WriteError> {
+        record
+            .put(
+                iat_payment_addendum::TRANSACTION_TYPE_CODE,
+                &self.transaction_type_code,
+            )?
+            .put(
+                iat_payment_addendum::FOREIGN_PAYMENT_AMOUNT,
+                &self.foreign_payment_amount.cents().to_string(),
+            )?
+            .put(
+                iat_payment_addendum::FOREIGN_TRACE_NUMBER,
+                &self.foreign_trace_number,
+            )?
+            .put(iat_payment_addendum::RECEIVING_NAME, &self.receiving_name)
+    }
+}
+
+/// Addenda type 11: who sent the payment.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq)]
+pub struct IatOriginatorAddendum {
+    pub originator_name: String,
+    pub originator_street_address: String,
+}
+
+impl IatOriginatorAddendum {
+    pub(crate) fn from_line(line: &Line) -> Result<IatOriginatorAddendum, ParseError> {
+        Ok(IatOriginatorAddendum {
+            originator_name: line.alpha(iat_originator_addendum::ORIGINATOR_NAME)?,
+            originator_street_address: line
+                .alpha(iat_originator_addendum::ORIGINATOR_STREET_ADDRESS)?,
+        })
+    }
+
+    pub(crate) fn write(&self, record: RecordWriter) -> Result<RecordWriter, WriteError> {
+        record
+            .put(
+                iat_originator_addendum::ORIGINATOR_NAME,
+                &self.originator_name,
+            )?
+            .put(
+                iat_originator_addendum::ORIGINATOR_STREET_ADDRESS,
+                &self.originator_street_address,
+            )
+    }
+}
+
+/// Addenda type 12: the rest of the originator's address.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq)]
+pub struct IatOriginatorAddressAddendum {
+    pub originator_city_state: String,
+    pub originator_country_postal_code: String,
+}
+
+impl IatOriginatorAddressAddendum {
+    pub(crate) fn from_line(line: &Line) -> Result<IatOriginatorAddressAddendum, ParseError> {
+        Ok(IatOriginatorAddressAddendum {
+            originator_city_state: line
+                .alpha(iat_originator_address_addendum::ORIGINATOR_CITY_STATE)?,
+            originator_country_postal_code: line
+                .alpha(iat_originator_address_addendum::ORIGINATOR_COUNTRY_POSTAL_CODE)?,
+        })
+    }
+
+    pub(crate) fn write(&self, record: RecordWriter) -> Result<RecordWriter, WriteError> {
+        record
+            .put(
+                iat_originator_address_addendum::ORIGINATOR_CITY_STATE,
+                &self.originator_city_state,
+            )?
+            .put(
+                iat_originator_address_addendum::ORIGINATOR_COUNTRY_POSTAL_CODE,
+                &self.originator_country_postal_code,
+            )
+    }
+}
+
+/// Addenda types 13, 14 and 18: the originating, receiving and foreign
+/// correspondent banks, which share a layout.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq)]
+pub struct IatBankAddendum {
+    pub bank_name: String,
+    pub bank_id_number_qualifier: String,
+    pub bank_id: String,
+    pub bank_branch_country_code: String,
+}
+
+impl IatBankAddendum {
+    pub(crate) fn from_line(line: &Line) -> Result<IatBankAddendum, ParseError> {
+        Ok(IatBankAddendum {
+            bank_name: line.alpha(iat_bank_addendum::BANK_NAME)?,
+            bank_id_number_qualifier: line.alpha(iat_bank_addendum::BANK_ID_NUMBER_QUALIFIER)?,
+            bank_id: line.alpha(iat_bank_addendum::BANK_ID)?,
+            bank_branch_country_code: line.alpha(iat_bank_addendum::BANK_BRANCH_COUNTRY_CODE)?,
+        })
+    }
+
+    pub(crate) fn write(&self, record: RecordWriter) -> Result<RecordWriter, WriteError> {
+        record
+            .put(iat_bank_addendum::BANK_NAME, &self.bank_name)?
+            .put(
+                iat_bank_addendum::BANK_ID_NUMBER_QUALIFIER,
+                &self.bank_id_number_qualifier,
+            )?
+            .put(iat_bank_addendum::BANK_ID, &self.bank_id)?
+            .put(
+                iat_bank_addendum::BANK_BRANCH_COUNTRY_CODE,
+                &self.bank_branch_country_code,
+            )
+    }
+}
+
+impl fmt::Display for IatBankAddendum {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} ({} {}) {}",
+            self.bank_name,
+            self.bank_id_number_qualifier,
+            self.bank_id,
+            self.bank_branch_country_code
+        )
+    }
+}
+
+/// Addenda type 15: who receives the payment.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq)]
+pub struct IatReceiverAddendum {
+    pub receiver_id_number: String,
+    pub receiver_street_address: String,
+}
+
+impl IatReceiverAddendum {
+    pub(crate) fn from_line(line: &Line) -> Result<IatReceiverAddendum, ParseError> {
+        Ok(IatReceiverAddendum {
+            receiver_id_number: line.alpha(iat_receiver_addendum::RECEIVER_ID_NUMBER)?,
+            receiver_street_address: line.alpha(iat_receiver_addendum::RECEIVER_STREET_ADDRESS)?,
+        })
+    }
+
+    pub(crate) fn write(&self, record: RecordWriter) -> Result<RecordWriter, WriteError> {
+        record
+            .put(
+                iat_receiver_addendum::RECEIVER_ID_NUMBER,
+                &self.receiver_id_number,
+            )?
+            .put(
+                iat_receiver_addendum::RECEIVER_STREET_ADDRESS,
+                &self.receiver_street_address,
+            )
+    }
+}
+
+/// Addenda type 16: the rest of the receiver's address.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq)]
+pub struct IatReceiverAddressAddendum {
+    pub receiver_city_state: String,
+    pub receiver_country_postal_code: String,
+}
+
+impl IatReceiverAddressAddendum {
+    pub(crate) fn from_line(line: &Line) -> Result<IatReceiverAddressAddendum, ParseError> {
+        Ok(IatReceiverAddressAddendum {
+            receiver_city_state: line.alpha(iat_receiver_address_addendum::RECEIVER_CITY_STATE)?,
+            receiver_country_postal_code: line
+                .alpha(iat_receiver_address_addendum::RECEIVER_COUNTRY_POSTAL_CODE)?,
+        })
+    }
+
+    pub(crate) fn write(&self, record: RecordWriter) -> Result<RecordWriter, WriteError> {
+        record
+            .put(
+                iat_receiver_address_addendum::RECEIVER_CITY_STATE,
+                &self.receiver_city_state,
+            )?
+            .put(
+                iat_receiver_address_addendum::RECEIVER_COUNTRY_POSTAL_CODE,
+                &self.receiver_country_postal_code,
+            )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::addenda::AddendaDetail;
+    use crate::{NachaFile, StandardEntryClass};
+
+    const FULL: &str = include_str!("../examples/full.ach");
+
+    fn bank(name: &str, id: &str, country: &str) -> IatBankAddendum {
+        IatBankAddendum {
+            bank_name: name.to_string(),
+            bank_id_number_qualifier: "01".to_string(),
+            bank_id: id.to_string(),
+            bank_branch_country_code: country.to_string(),
+        }
+    }
+
+    #[test]
+    fn reads_the_iat_batch_header() {
+        let file = NachaFile::parse(FULL).unwrap();
+        let header = &file.batches[0].batch_header;
+        assert_eq!(
+            header.standard_entry_class_code,
+            StandardEntryClass::International
+        );
+        assert_eq!(header.company_id, "123456789");
+        assert_eq!(header.company_name, "");
+        assert_eq!(
+            header.iat,
+            Some(IatBatchHeader {
+                iat_indicator: "".to_string(),
+                foreign_exchange_indicator: "FF".to_string(),
+                foreign_exchange_reference_indicator: "3".to_string(),
+                foreign_exchange_reference: "".to_string(),
+                iso_destination_country_code: "US".to_string(),
+                iso_originating_currency_code: "CAD".to_string(),
+                iso_destination_currency_code: "USD".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn reads_the_iat_entry_and_its_addenda() {
+        let file = NachaFile::parse(FULL).unwrap();
+        let entry = &file.batches[0].detail_entries[0];
+        assert_eq!(entry.dfi_account_number, "123456789");
+        assert_eq!(entry.amount, Amount::from_cents(100000));
+        assert_eq!(
+            entry.iat.as_ref().map(|iat| iat.number_of_addenda.as_str()),
+            Some("0007")
+        );
+
+        let details: Vec<_> = entry.addenda.iter().map(|a| a.detail.clone()).collect();
+        assert_eq!(
+            details,
+            [
+                Some(AddendaDetail::IatPayment(IatPaymentAddendum {
+                    transaction_type_code: "ANN".to_string(),
+                    foreign_payment_amount: Amount::from_cents(100000),
+                    foreign_trace_number: "928383-23938".to_string(),
+                    receiving_name: "BEK Enterprises".to_string(),
+                })),
+                Some(AddendaDetail::IatOriginator(IatOriginatorAddendum {
+                    originator_name: "BEK Solutions".to_string(),
+                    originator_street_address: "15 West Place Street".to_string(),
+                })),
+                Some(AddendaDetail::IatOriginatorAddress(
+                    IatOriginatorAddressAddendum {
+                        originator_city_state: "JacobsTown*PA\\".to_string(),
+                        originator_country_postal_code: "US*19305\\".to_string(),
+                    }
+                )),
+                Some(AddendaDetail::IatOriginatingBank(bank(
+                    "Wells Fargo",
+                    "231380104",
+                    "US"
+                ))),
+                Some(AddendaDetail::IatReceivingBank(bank(
+                    "Citadel Bank",
+                    "121042882",
+                    "CA"
+                ))),
+                Some(AddendaDetail::IatReceiver(IatReceiverAddendum {
+                    receiver_id_number: "987465493213987".to_string(),
+                    receiver_street_address: "2121 Front Street".to_string(),
+                })),
+                Some(AddendaDetail::IatReceiverAddress(
+                    IatReceiverAddressAddendum {
+                        receiver_city_state: "LetterTown*AB\\".to_string(),
+                        receiver_country_postal_code: "CA*80014\\".to_string(),
+                    }
+                )),
+                Some(AddendaDetail::IatRemittance),
+                Some(AddendaDetail::IatForeignCorrespondentBank(bank(
+                    "Bank of France",
+                    "456456456987987",
+                    "FR"
+                ))),
+            ]
+        );
+        assert_eq!(
+            entry.addenda[7].payment_related_info,
+            "This is an international payment"
+        );
+        for (addendum, code) in entry.addenda.iter().zip(10..) {
+            assert_eq!(addendum.addenda_type_code, code.to_string());
+            assert_eq!(addendum.entry_detail_sequence_number, "0000001");
+        }
+    }
+
+    #[test]
+    fn writes_each_iat_record_in_its_own_columns() {
+        let file = NachaFile::parse(FULL).unwrap();
+        let batch = &file.batches[0];
+        let mut records = vec![batch.batch_header.to_record().unwrap()];
+        for entry in &batch.detail_entries {
+            records.push(entry.to_record().unwrap());
+            for addendum in &entry.addenda {
+                records.push(addendum.to_record().unwrap());
+            }
+        }
+        records.push(batch.batch_control.to_record().unwrap());
+        let lines: Vec<_> = FULL.lines().skip(1).take(records.len()).collect();
+        assert_eq!(records, lines);
+    }
+
+    #[test]
+    fn writes_edited_iat_fields_back() {
+        let mut file = NachaFile::parse(FULL).unwrap();
+        let batch = &mut file.batches[0];
+        let iat = batch.batch_header.iat.as_mut().unwrap();
+        iat.foreign_exchange_indicator = "FV".to_string();
+        iat.foreign_exchange_reference = "1.3542".to_string();
+        iat.iso_destination_country_code = "CA".to_string();
+        iat.iso_destination_currency_code = "CAD".to_string();
+        batch.detail_entries[0].addenda[4].detail = Some(AddendaDetail::IatReceivingBank(bank(
+            "Bank of Montreal",
+            "000112345",
+            "CA",
+        )));
+
+        let written = file.to_ach_string().unwrap();
+        let lines: Vec<_> = written.lines().collect();
+        assert_eq!(
+            lines[1],
+            "5200                FV31.3542         CA123456789 IATTRADEPAYMTCADCAD190816   0231380100000001"
+        );
+        assert_eq!(
+            lines[7],
+            "714Bank of Montreal                   01000112345                         CA           0000001"
+        );
+        let reread = NachaFile::parse(&written).unwrap();
+        assert_eq!(
+            reread.batches[0].batch_header.iat,
+            file.batches[0].batch_header.iat
+        );
+        assert_eq!(
+            reread.batches[0].detail_entries[0].addenda[4].detail,
+            file.batches[0].detail_entries[0].addenda[4].detail
+        );
+    }
+}
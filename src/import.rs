@@ -0,0 +1,491 @@
+use crate::layout::{addendum, entry_detail, Field};
+use crate::{
+    AccountType, Amount, BatchBuilder, Currency, DetailEntry, NachaFile, NachaFileBuilder,
+    RoutingNumber, StandardEntryClass, TransactionCode,
+};
+use chrono::NaiveDate;
+use serde::Deserialize;
+use std::fmt;
+use std::io;
+
+/// Everything [`NachaFile::from_csv`] needs besides the payments themselves:
+/// who the file is from and to, the company originating the batches and
+/// which CSV columns hold each field. Usually read from YAML.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ImportConfig {
+    pub immediate_destination: String,
+    pub immediate_origin: String,
+    #[serde(default)]
+    pub destination_name: String,
+    #[serde(default)]
+    pub origin_name: String,
+    pub company_name: String,
+    pub company_id: String,
+    pub standard_entry_class_code: StandardEntryClass,
+    pub company_entry_description: String,
+    pub effective_entry_date: NaiveDate,
+    pub originating_dfi_id: String,
+    /// for rows that don't have a direction column
+    #[serde(default)]
+    pub direction: Direction,
+    #[serde(default)]
+    pub columns: ImportColumns,
+}
+
+/// Whether an entry pays the receiver or collects from them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Direction {
+    #[default]
+    Credit,
+    Debit,
+}
+
+/// The CSV header of each field. Name, routing, account and amount columns
+/// are required; the rest may be left out of the CSV.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct ImportColumns {
+    pub name: String,
+    /// the full 9 digit routing number, check digit included
+    pub routing: String,
+    pub account: String,
+    /// `checking`, `savings`, `general_ledger` or `loan`; checking if blank.
+    /// Loans can only be credited
+    pub account_type: String,
+    /// in dollars, e.g. `1234.56`
+    pub amount: String,
+    pub id: String,
+    /// payment related information, added as a type 05 addendum
+    pub addenda: String,
+    /// `credit` or `debit`, overriding the config's direction
+    pub direction: String,
+    /// rows with the same value go in the same batch, in order of first
+    /// appearance; without it everything is one batch
+    pub batch: String,
+}
+
+impl Default for ImportColumns {
+    fn default() -> ImportColumns {
+        ImportColumns {
+            name: "name".to_string(),
+            routing: "routing".to_string(),
+            account: "account".to_string(),
+            account_type: "account_type".to_string(),
+            amount: "amount".to_string(),
+            id: "id".to_string(),
+            addenda: "addenda".to_string(),
+            direction: "direction".to_string(),
+            batch: "batch".to_string(),
+        }
+    }
+}
+
+/// A row of payment instructions that can't be turned into an entry, or a
+/// bad value in the config.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImportError {
+    /// 1-based line number in the CSV, the header being line 1; `None` for
+    /// a problem with the config
+    pub line: Option<usize>,
+    /// the CSV column, or the config key
+    pub column: Option<String>,
+    pub message: String,
+}
+
+impl ImportError {
+    fn config(key: &str, message: String) -> ImportError {
+        ImportError {
+            line: None,
+            column: Some(key.to_string()),
+            message,
+        }
+    }
+}
+
+impl fmt::Display for ImportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.line {
+            Some(line) => write!(f, "line {}", line)?,
+            None => write!(f, "config")?,
+        }
+        if let Some(column) = &self.column {
+            write!(f, ": `{}`", column)?;
+        }
+        write!(f, ": {}", self.message)
+    }
+}
+
+impl std::error::Error for ImportError {}
+
+/// Where each configured column is in the CSV header.
+struct Positions {
+    name: usize,
+    routing: usize,
+    account: usize,
+    amount: usize,
+    account_type: Option<usize>,
+    id: Option<usize>,
+    addenda: Option<usize>,
+    direction: Option<usize>,
+    batch: Option<usize>,
+}
+
+impl Positions {
+    fn new(header: &csv::StringRecord, columns: &ImportColumns) -> Result<Positions, ImportError> {
+        let find = |column: &str| header.iter().position(|h| h.trim() == column);
+        let require = |column: &str| {
+            find(column).ok_or_else(|| ImportError {
+                line: Some(1),
+                column: Some(column.to_string()),
+                message: "column not found in header".to_string(),
+            })
+        };
+        Ok(Positions {
+            name: require(&columns.name)?,
+            routing: require(&columns.routing)?,
+            account: require(&columns.account)?,
+            amount: require(&columns.amount)?,
+            account_type: find(&columns.account_type),
+            id: find(&columns.id),
+            addenda: find(&columns.addenda),
+            direction: find(&columns.direction),
+            batch: find(&columns.batch),
+        })
+    }
+}
+
+/// the code of a live entry, if there is one: a loan can be paid into, but
+/// the only loan debit is the reversal of a credit, which an import can't be
+fn transaction_code(account_type: AccountType, direction: Direction) -> Option<TransactionCode> {
+    let code = match (account_type, direction) {
+        (AccountType::Checking, Direction::Credit) => TransactionCode::CheckingCredit,
+        (AccountType::Checking, Direction::Debit) => TransactionCode::CheckingDebit,
+        (AccountType::Savings, Direction::Credit) => TransactionCode::SavingsCredit,
+        (AccountType::Savings, Direction::Debit) => TransactionCode::SavingsDebit,
+        (AccountType::GeneralLedger, Direction::Credit) => TransactionCode::GeneralLedgerCredit,
+        (AccountType::GeneralLedger, Direction::Debit) => TransactionCode::GeneralLedgerDebit,
+        (AccountType::Loan, Direction::Credit) => TransactionCode::LoanCredit,
+        (AccountType::Loan, Direction::Debit) => return None,
+    };
+    Some(code)
+}
+
+impl ImportConfig {
+    /// The routing numbers the file is addressed with, which the rows can't
+    /// make up for.
+    fn errors(&self) -> Vec<ImportError> {
+        let mut errors = Vec::new();
+        let immediate = [
+            ("immediate_destination", &self.immediate_destination),
+            ("immediate_origin", &self.immediate_origin),
+        ];
+        for (key, value) in immediate {
+            if let Err(e) = RoutingNumber::from_immediate(value) {
+                errors.push(ImportError::config(key, e.to_string()));
+            }
+        }
+        // the ODFI is the first 8 digits only, so there's no check digit to
+        // test, just the shape
+        if let Err(e) = RoutingNumber::from_dfi_id(&self.originating_dfi_id) {
+            errors.push(ImportError::config("originating_dfi_id", e.to_string()));
+        }
+        errors
+    }
+}
+
+/// Whether `value` fits in `field`, counting characters.
+fn fits(value: &str, field: Field) -> bool {
+    value.chars().count() <= field.width()
+}
+
+impl NachaFile {
+    /// Builds a file from CSV payment instructions, one entry per row.
+    /// The config's routing numbers and every row's routing number, amount
+    /// and the other columns are checked first, and every problem is
+    /// reported rather than just the first.
+    pub fn from_csv<R: io::Read>(
+        input: R,
+        config: &ImportConfig,
+    ) -> Result<NachaFile, Vec<ImportError>> {
+        let mut reader = csv::ReaderBuilder::new().flexible(true).from_reader(input);
+        let csv_error = |e: csv::Error| ImportError {
+            line: Some(e.position().map_or(1, |p| p.line() as usize)),
+            column: None,
+            message: e.to_string(),
+        };
+        let header = reader.headers().map_err(|e| vec![csv_error(e)])?.clone();
+        let positions = Positions::new(&header, &config.columns).map_err(|e| vec![e])?;
+
+        let mut errors = config.errors();
+        let mut batches: Vec<(String, Vec<DetailEntry>)> = Vec::new();
+        for record in reader.records() {
+            let record = match record {
+                Ok(record) => record,
+                Err(e) => {
+                    errors.push(csv_error(e));
+                    continue;
+                }
+            };
+            let line = record.position().map(|p| p.line() as usize);
+            let get = |i: usize| record.get(i).unwrap_or("").trim();
+            let optional = |i: Option<usize>| i.map(get).unwrap_or("");
+            let mut error = |column: &str, message: String| {
+                errors.push(ImportError {
+                    line,
+                    column: Some(column.to_string()),
+                    message,
+                })
+            };
+            let columns = &config.columns;
+
+            let routing = RoutingNumber::new(get(positions.routing));
+            if let Err(e) = &routing {
+                error(&columns.routing, e.to_string());
+            }
+            let amount = get(positions.amount).parse::<Amount>();
+            match &amount {
+                Err(e) => error(&columns.amount, e.to_string()),
+                // every code an import can produce is a live entry, which
+                // has to move money
+                Ok(amount) if *amount == Amount::ZERO => {
+                    error(&columns.amount, "amount is zero".to_string())
+                }
+                Ok(amount) if !fits(&amount.cents().to_string(), entry_detail::AMOUNT) => error(
+                    &columns.amount,
+                    format!(
+                        "amount {} is over 99,999,999.99",
+                        amount.pretty_dollars_cents()
+                    ),
+                ),
+                Ok(_) => {}
+            }
+            let name = get(positions.name);
+            if name.is_empty() {
+                error(&columns.name, "name is blank".to_string());
+            } else if !fits(name, entry_detail::INDIVIDUAL_NAME) {
+                error(
+                    &columns.name,
+                    format!("name is over 22 characters: {:?}", name),
+                );
+            }
+            let account = get(positions.account);
+            if account.is_empty() || !fits(account, entry_detail::DFI_ACCOUNT_NUMBER) {
+                error(
+                    &columns.account,
+                    format!("account number should be 1 to 17 characters: {:?}", account),
+                );
+            }
+            let id = optional(positions.id);
+            if !fits(id, entry_detail::INDIVIDUAL_ID_NUMBER) {
+                error(&columns.id, format!("id is over 15 characters: {:?}", id));
+            }
+            let addenda = optional(positions.addenda);
+            if !fits(addenda, addendum::PAYMENT_RELATED_INFO) {
+                error(
+                    &columns.addenda,
+                    format!("addenda is over 80 characters: {:?}", addenda),
+                );
+            }
+            let account_type = match optional(positions.account_type).to_lowercase().as_str() {
+                "" | "checking" => Some(AccountType::Checking),
+                "savings" => Some(AccountType::Savings),
+                "general_ledger" => Some(AccountType::GeneralLedger),
+                "loan" => Some(AccountType::Loan),
+                other => {
+                    error(
+                        &columns.account_type,
+                        format!("unknown account type {:?}", other),
+                    );
+                    None
+                }
+            };
+            let direction = match optional(positions.direction).to_lowercase().as_str() {
+                "" => Some(config.direction),
+                "credit" => Some(Direction::Credit),
+                "debit" => Some(Direction::Debit),
+                other => {
+                    error(&columns.direction, format!("unknown direction {:?}", other));
+                    None
+                }
+            };
+
+            let code = account_type
+                .zip(direction)
+                .and_then(|(account_type, direction)| {
+                    let code = transaction_code(account_type, direction);
+                    if code.is_none() {
+                        error(
+                            &columns.account_type,
+                            "a loan account can't be debited".to_string(),
+                        );
+                    }
+                    code
+                });
+
+            let (Ok(routing), Ok(amount), Some(code)) = (routing, amount, code) else {
+                continue;
+            };
+            let mut entry = DetailEntry::new(code, routing.as_str(), account, amount, name)
+                .with_individual_id(id);
+            if !addenda.is_empty() {
+                entry = entry.with_addendum(addenda);
+            }
+
+            let key = optional(positions.batch);
+            match batches.iter_mut().find(|(k, _)| k == key) {
+                Some((_, entries)) => entries.push(entry),
+                None => batches.push((key.to_string(), vec![entry])),
+            }
+        }
+        if !errors.is_empty() {
+            return Err(errors);
+        }
+
+        let file = batches.into_iter().fold(
+            NachaFileBuilder::new(&config.immediate_destination, &config.immediate_origin)
+                .destination_name(&config.destination_name)
+                .origin_name(&config.origin_name),
+            |file, (_, entries)| {
+                file.batch(
+                    BatchBuilder::new(
+                        &config.company_name,
+                        &config.company_id,
+                        config.standard_entry_class_code.clone(),
+                        &config.company_entry_description,
+                        config.effective_entry_date,
+                        &config.originating_dfi_id,
+                    )
+                    .entries(entries),
+                )
+            },
+        );
+        // what's left are config values too wide for the headers, or totals
+        // too big for the controls
+        file.build().map_err(|e| {
+            vec![ImportError {
+                line: None,
+                column: None,
+                message: e.to_string(),
+            }]
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::AddendaDetail;
+
+    const PAYMENTS: &str = include_str!("../examples/payments.csv");
+
+    fn config() -> ImportConfig {
+        serde_yaml::from_str(include_str!("../examples/origin.yaml")).unwrap()
+    }
+
+    #[test]
+    fn maps_the_configured_columns() {
+        let file = NachaFile::from_csv(PAYMENTS.as_bytes(), &config()).unwrap();
+        assert_eq!(file.batches.len(), 1);
+        let batch = &file.batches[0];
+        assert_eq!(batch.batch_header.company_name, "Name on Account");
+        assert_eq!(batch.batch_header.company_entry_description, "PAYROLL");
+        let entries = &batch.detail_entries;
+        assert_eq!(entries.len(), 3);
+
+        let entry = &entries[0];
+        assert_eq!(entry.individual_name, "Julian Price");
+        assert_eq!(entry.individual_id_number, "A271");
+        assert_eq!(
+            (entry.receiving_dfi_id.as_str(), entry.check_digit.as_str()),
+            ("02120002", "5")
+        );
+        assert_eq!(entry.dfi_account_number, "998412345");
+        assert_eq!(entry.amount, Amount::from_cents(215000));
+        assert_eq!(entry.transaction_code, TransactionCode::CheckingCredit);
+        assert!(entry.addenda.is_empty());
+
+        let entry = &entries[1];
+        assert_eq!(entry.transaction_code, TransactionCode::SavingsCredit);
+        assert_eq!(entry.amount, Amount::from_cents(187550));
+        assert_eq!(entry.addenda.len(), 1);
+        assert_eq!(entry.addenda[0].payment_related_info, "June salary");
+        assert!(matches!(
+            entry.addenda[0].detail,
+            Some(AddendaDetail::PaymentRelated)
+        ));
+
+        // a blank account type is checking
+        assert_eq!(entries[2].transaction_code, TransactionCode::CheckingCredit);
+    }
+
+    #[test]
+    fn groups_rows_into_batches_in_order_of_first_appearance() {
+        let mut config = config();
+        config.columns.batch = "batch".to_string();
+        let csv = "\
+employee,routing_number,account_number,net_pay,batch,direction
+Julian Price,021200025,998412345,10.00,east,
+Sydney Butler,231380104,12345678,20.00,west,debit
+Avery Stone,121042882,55501234,30.00,east,
+";
+        let file = NachaFile::from_csv(csv.as_bytes(), &config).unwrap();
+        let names: Vec<Vec<&str>> = file
+            .batches
+            .iter()
+            .map(|b| {
+                b.detail_entries
+                    .iter()
+                    .map(|e| e.individual_name.as_str())
+                    .collect()
+            })
+            .collect();
+        assert_eq!(
+            names,
+            vec![vec!["Julian Price", "Avery Stone"], vec!["Sydney Butler"]]
+        );
+        assert_eq!(
+            file.batches[1].detail_entries[0].transaction_code,
+            TransactionCode::CheckingDebit
+        );
+    }
+
+    #[test]
+    fn reports_every_bad_row() {
+        let csv = "\
+employee,routing_number,account_number,account_type,net_pay,direction
+Julian Price,021200026,998412345,,10.00,
+Sydney Butler,231380104,12345678,,ten,
+Avery Stone,121042882,55501234,,0.00,
+A Name Much Too Long For The Field,121042882,55501234,,5.00,
+Sam Lee,121042882,55501234,loan,5.00,debit
+";
+        let errors = NachaFile::from_csv(csv.as_bytes(), &config()).unwrap_err();
+        let errors: Vec<(Option<usize>, Option<&str>, &str)> = errors
+            .iter()
+            .map(|e| (e.line, e.column.as_deref(), e.message.as_str()))
+            .collect();
+        assert_eq!(
+            errors,
+            vec![
+                (
+                    Some(2),
+                    Some("routing_number"),
+                    "routing number check digit should be 5 but is 6"
+                ),
+                (Some(3), Some("net_pay"), "invalid amount \"ten\""),
+                (Some(4), Some("net_pay"), "amount is zero"),
+                (
+                    Some(5),
+                    Some("employee"),
+                    "name is over 22 characters: \"A Name Much Too Long For The Field\""
+                ),
+                (
+                    Some(6),
+                    Some("account_type"),
+                    "a loan account can't be debited"
+                ),
+            ]
+        );
+    }
+}
@@ -0,0 +1,1374 @@
+//! The column layout of every record type, as given by the NACHA rules.
+//! Positions are 1-based and inclusive, the way the rules print them, so
+//! that they can be checked against the rules by eye; [`Field::range`] gives
+//! the 0-based range used to slice a line.
+//!
+//! Parsing, writing and validation all take their columns from these tables.
+use crate::StandardEntryClass;
+use serde::Serialize;
+use std::fmt;
+use std::ops::Range;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FieldType {
+    /// digits, right-justified and zero-padded
+    Numeric,
+    /// any text, left-justified and space-padded
+    Alphanumeric,
+    /// `YYMMDD`
+    Date,
+}
+
+impl fmt::Display for FieldType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(match self {
+            FieldType::Numeric => "numeric",
+            FieldType::Alphanumeric => "alphanumeric",
+            FieldType::Date => "date",
+        })
+    }
+}
+
+/// One field of a record layout.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct Field {
+    pub name: &'static str,
+    /// first column, counting from 1
+    pub start: usize,
+    /// last column, inclusive
+    pub end: usize,
+    #[serde(rename = "type")]
+    pub kind: FieldType,
+    /// whether the rules call for a value, rather than leaving it blank
+    pub required: bool,
+    pub description: &'static str,
+}
+
+impl Field {
+    /// the 0-based, exclusive range of characters the field covers
+    pub fn range(&self) -> Range<usize> {
+        self.start - 1..self.end
+    }
+
+    pub fn width(&self) -> usize {
+        self.end + 1 - self.start
+    }
+
+    /// the field's text in `record`, or as much of it as a short record
+    /// has; positions count characters, not bytes
+    pub fn text(&self, record: &str) -> String {
+        record
+            .chars()
+            .skip(self.start - 1)
+            .take(self.width())
+            .collect()
+    }
+}
+
+const fn numeric(
+    name: &'static str,
+    start: usize,
+    end: usize,
+    required: bool,
+    description: &'static str,
+) -> Field {
+    Field {
+        name,
+        start,
+        end,
+        kind: FieldType::Numeric,
+        required,
+        description,
+    }
+}
+
+const fn alpha(
+    name: &'static str,
+    start: usize,
+    end: usize,
+    required: bool,
+    description: &'static str,
+) -> Field {
+    Field {
+        name,
+        start,
+        end,
+        kind: FieldType::Alphanumeric,
+        required,
+        description,
+    }
+}
+
+const fn date(
+    name: &'static str,
+    start: usize,
+    end: usize,
+    required: bool,
+    description: &'static str,
+) -> Field {
+    Field {
+        name,
+        start,
+        end,
+        kind: FieldType::Date,
+        required,
+        description,
+    }
+}
+
+const REQUIRED: bool = true;
+const OPTIONAL: bool = false;
+
+/// the first column of every record
+pub const RECORD_TYPE_CODE: Field = numeric(
+    "record_type_code",
+    1,
+    1,
+    REQUIRED,
+    "1 file header, 5 batch header, 6 entry, 7 addendum, 8 batch control, 9 file control",
+);
+
+/// How the entries of a batch are laid out, which hangs on the batch's
+/// standard entry class: IAT and ADV entries move fields around.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EntryLayout {
+    #[default]
+    Standard,
+    Iat,
+    Adv,
+}
+
+impl EntryLayout {
+    pub fn for_class(standard_entry_class_code: &StandardEntryClass) -> EntryLayout {
+        match standard_entry_class_code {
+            StandardEntryClass::International => EntryLayout::Iat,
+            StandardEntryClass::AutomatedAccounting => EntryLayout::Adv,
+            _ => EntryLayout::Standard,
+        }
+    }
+
+    pub fn fields(self) -> &'static [Field] {
+        match self {
+            EntryLayout::Standard => entry_detail::FIELDS,
+            EntryLayout::Iat => iat_entry_detail::FIELDS,
+            EntryLayout::Adv => adv_entry_detail::FIELDS,
+        }
+    }
+}
+
+/// Picks the layout of a record by its record type code, and addenda by
+/// their type code. Entries in IAT and ADV batches are laid out
+/// differently, so the caller says which batch the record is in.
+pub fn for_record(record: &str, entries: EntryLayout) -> Option<&'static [Field]> {
+    let layout = match RECORD_TYPE_CODE.text(record).as_str() {
+        "1" => file_header::FIELDS,
+        "5" if batch_header::STANDARD_ENTRY_CLASS_CODE.text(record) == "IAT" => {
+            iat_batch_header::FIELDS
+        }
+        "5" => batch_header::FIELDS,
+        "6" => entries.fields(),
+        "7" => for_addenda(&addendum::ADDENDA_TYPE_CODE.text(record)),
+        "8" => batch_control::FIELDS,
+        "9" => file_control::FIELDS,
+        _ => return None,
+    };
+    Some(layout)
+}
+
+/// the layout of an addendum with the given addenda type code
+pub fn for_addenda(addenda_type_code: &str) -> &'static [Field] {
+    match addenda_type_code {
+        "02" => terminal_addendum::FIELDS,
+        "98" => notification_of_change_addendum::FIELDS,
+        "99" => return_addendum::FIELDS,
+        "10" => iat_payment_addendum::FIELDS,
+        "11" => iat_originator_addendum::FIELDS,
+        "12" => iat_originator_address_addendum::FIELDS,
+        "13" | "14" => iat_bank_addendum::FIELDS,
+        "15" => iat_receiver_addendum::FIELDS,
+        "16" => iat_receiver_address_addendum::FIELDS,
+        "18" => iat_foreign_correspondent_bank_addendum::FIELDS,
+        _ => addendum::FIELDS,
+    }
+}
+
+/// Every layout along with the record it's for, e.g. to print the tables.
+pub const ALL: &[(&str, &[Field])] = &[
+    ("file_header", file_header::FIELDS),
+    ("batch_header", batch_header::FIELDS),
+    ("iat_batch_header", iat_batch_header::FIELDS),
+    ("entry_detail", entry_detail::FIELDS),
+    ("iat_entry_detail", iat_entry_detail::FIELDS),
+    ("adv_entry_detail", adv_entry_detail::FIELDS),
+    ("addendum", addendum::FIELDS),
+    ("terminal_addendum", terminal_addendum::FIELDS),
+    (
+        "notification_of_change_addendum",
+        notification_of_change_addendum::FIELDS,
+    ),
+    ("return_addendum", return_addendum::FIELDS),
+    ("iat_payment_addendum", iat_payment_addendum::FIELDS),
+    ("iat_originator_addendum", iat_originator_addendum::FIELDS),
+    (
+        "iat_originator_address_addendum",
+        iat_originator_address_addendum::FIELDS,
+    ),
+    ("iat_bank_addendum", iat_bank_addendum::FIELDS),
+    ("iat_receiver_addendum", iat_receiver_addendum::FIELDS),
+    (
+        "iat_receiver_address_addendum",
+        iat_receiver_address_addendum::FIELDS,
+    ),
+    (
+        "iat_foreign_correspondent_bank_addendum",
+        iat_foreign_correspondent_bank_addendum::FIELDS,
+    ),
+    ("batch_control", batch_control::FIELDS),
+    ("file_control", file_control::FIELDS),
+];
+
+pub mod file_header {
+    pub use super::RECORD_TYPE_CODE;
+    use super::*;
+
+    pub const PRIORITY_CODE: Field = numeric("priority_code", 2, 3, REQUIRED, "always 01");
+    pub const IMMEDIATE_DESTINATION: Field = alpha(
+        "immediate_destination",
+        4,
+        13,
+        REQUIRED,
+        "routing number of the receiving point, preceded by a blank",
+    );
+    pub const IMMEDIATE_ORIGIN: Field = alpha(
+        "immediate_origin",
+        14,
+        23,
+        REQUIRED,
+        "routing number of the sending point, preceded by a blank",
+    );
+    pub const FILE_CREATION_DATE: Field = date(
+        "file_creation_date",
+        24,
+        29,
+        REQUIRED,
+        "date the file was created",
+    );
+    pub const FILE_CREATION_TIME: Field = numeric(
+        "file_creation_time",
+        30,
+        33,
+        OPTIONAL,
+        "time the file was created, HHMM",
+    );
+    pub const FILE_ID_MODIFIER: Field = alpha(
+        "file_id_modifier",
+        34,
+        34,
+        REQUIRED,
+        "A to Z then 0 to 9, telling apart files sent on the same day",
+    );
+    pub const RECORD_SIZE: Field = numeric("record_size", 35, 37, REQUIRED, "always 094");
+    pub const BLOCKING_FACTOR: Field = numeric("blocking_factor", 38, 39, REQUIRED, "always 10");
+    pub const FORMAT_CODE: Field = numeric("format_code", 40, 40, REQUIRED, "always 1");
+    pub const IMMEDIATE_DESTINATION_NAME: Field = alpha(
+        "immediate_destination_name",
+        41,
+        63,
+        OPTIONAL,
+        "name of the receiving point",
+    );
+    pub const IMMEDIATE_ORIGIN_NAME: Field = alpha(
+        "immediate_origin_name",
+        64,
+        86,
+        OPTIONAL,
+        "name of the sending point",
+    );
+    pub const REFERENCE_CODE: Field = alpha(
+        "reference_code",
+        87,
+        94,
+        OPTIONAL,
+        "for the originator's own use",
+    );
+
+    pub const FIELDS: &[Field] = &[
+        RECORD_TYPE_CODE,
+        PRIORITY_CODE,
+        IMMEDIATE_DESTINATION,
+        IMMEDIATE_ORIGIN,
+        FILE_CREATION_DATE,
+        FILE_CREATION_TIME,
+        FILE_ID_MODIFIER,
+        RECORD_SIZE,
+        BLOCKING_FACTOR,
+        FORMAT_CODE,
+        IMMEDIATE_DESTINATION_NAME,
+        IMMEDIATE_ORIGIN_NAME,
+        REFERENCE_CODE,
+    ];
+}
+
+pub mod batch_header {
+    pub use super::RECORD_TYPE_CODE;
+    use super::*;
+
+    pub const SERVICE_CLASS_CODE: Field = numeric(
+        "service_class_code",
+        2,
+        4,
+        REQUIRED,
+        "200 mixed, 220 credits only, 225 debits only",
+    );
+    pub const COMPANY_NAME: Field = alpha(
+        "company_name",
+        5,
+        20,
+        REQUIRED,
+        "name of the originator, as known to the receiver",
+    );
+    pub const COMPANY_DISCRETIONARY_DATA: Field = alpha(
+        "company_discretionary_data",
+        21,
+        40,
+        OPTIONAL,
+        "for the originator's own use",
+    );
+    pub const COMPANY_ID: Field = alpha(
+        "company_id",
+        41,
+        50,
+        REQUIRED,
+        "identifies the originator, often 1 followed by its EIN",
+    );
+    pub const STANDARD_ENTRY_CLASS_CODE: Field = alpha(
+        "standard_entry_class_code",
+        51,
+        53,
+        REQUIRED,
+        "SEC code of the batch's entries, e.g. PPD, CCD or WEB",
+    );
+    pub const COMPANY_ENTRY_DESCRIPTION: Field = alpha(
+        "company_entry_description",
+        54,
+        63,
+        REQUIRED,
+        "purpose of the entries, shown to the receiver, e.g. PAYROLL",
+    );
+    pub const COMPANY_DESCRIPTIVE_DATE: Field = alpha(
+        "company_descriptive_date",
+        64,
+        69,
+        OPTIONAL,
+        "date shown to the receiver, in any format",
+    );
+    pub const EFFECTIVE_ENTRY_DATE: Field = date(
+        "effective_entry_date",
+        70,
+        75,
+        REQUIRED,
+        "date the originator intends the entries to settle",
+    );
+    pub const SETTLEMENT_DATE: Field = numeric(
+        "settlement_date",
+        76,
+        78,
+        OPTIONAL,
+        "Julian day of settlement, filled in by the ACH operator",
+    );
+    pub const ORIGINATOR_STATUS_CODE: Field = alpha(
+        "originator_status_code",
+        79,
+        79,
+        REQUIRED,
+        "1 for a depository financial institution",
+    );
+    pub const ORIGINATING_DFI_ID: Field = numeric(
+        "originating_dfi_id",
+        80,
+        87,
+        REQUIRED,
+        "first 8 digits of the ODFI's routing number",
+    );
+    pub const BATCH_NUMBER: Field = numeric(
+        "batch_number",
+        88,
+        94,
+        REQUIRED,
+        "numbers the batches of the file in ascending order",
+    );
+
+    pub const FIELDS: &[Field] = &[
+        RECORD_TYPE_CODE,
+        SERVICE_CLASS_CODE,
+        COMPANY_NAME,
+        COMPANY_DISCRETIONARY_DATA,
+        COMPANY_ID,
+        STANDARD_ENTRY_CLASS_CODE,
+        COMPANY_ENTRY_DESCRIPTION,
+        COMPANY_DESCRIPTIVE_DATE,
+        EFFECTIVE_ENTRY_DATE,
+        SETTLEMENT_DATE,
+        ORIGINATOR_STATUS_CODE,
+        ORIGINATING_DFI_ID,
+        BATCH_NUMBER,
+    ];
+}
+
+pub mod iat_batch_header {
+    pub use super::batch_header::{
+        BATCH_NUMBER, COMPANY_ENTRY_DESCRIPTION, EFFECTIVE_ENTRY_DATE, ORIGINATING_DFI_ID,
+        ORIGINATOR_STATUS_CODE, SERVICE_CLASS_CODE, SETTLEMENT_DATE, STANDARD_ENTRY_CLASS_CODE,
+    };
+    pub use super::RECORD_TYPE_CODE;
+    use super::*;
+
+    pub const IAT_INDICATOR: Field = alpha(
+        "iat_indicator",
+        5,
+        20,
+        OPTIONAL,
+        "IATCOR for corrections, otherwise blank",
+    );
+    pub const FOREIGN_EXCHANGE_INDICATOR: Field = alpha(
+        "foreign_exchange_indicator",
+        21,
+        22,
+        REQUIRED,
+        "FV fixed to variable, VF variable to fixed, FF fixed to fixed",
+    );
+    pub const FOREIGN_EXCHANGE_REFERENCE_INDICATOR: Field = numeric(
+        "foreign_exchange_reference_indicator",
+        23,
+        23,
+        REQUIRED,
+        "1 exchange rate, 2 reference number, 3 space filled",
+    );
+    pub const FOREIGN_EXCHANGE_REFERENCE: Field = alpha(
+        "foreign_exchange_reference",
+        24,
+        38,
+        OPTIONAL,
+        "the exchange rate or reference the indicator calls for",
+    );
+    pub const ISO_DESTINATION_COUNTRY_CODE: Field = alpha(
+        "iso_destination_country_code",
+        39,
+        40,
+        REQUIRED,
+        "ISO 3166 code of the country the entries go to",
+    );
+    pub const ORIGINATOR_IDENTIFICATION: Field = alpha(
+        "company_id",
+        41,
+        50,
+        REQUIRED,
+        "originator identification, held in company_id",
+    );
+    pub const ISO_ORIGINATING_CURRENCY_CODE: Field = alpha(
+        "iso_originating_currency_code",
+        64,
+        66,
+        REQUIRED,
+        "ISO 4217 code of the currency the entries were sent in",
+    );
+    pub const ISO_DESTINATION_CURRENCY_CODE: Field = alpha(
+        "iso_destination_currency_code",
+        67,
+        69,
+        REQUIRED,
+        "ISO 4217 code of the currency the entries are received in",
+    );
+
+    pub const FIELDS: &[Field] = &[
+        RECORD_TYPE_CODE,
+        SERVICE_CLASS_CODE,
+        IAT_INDICATOR,
+        FOREIGN_EXCHANGE_INDICATOR,
+        FOREIGN_EXCHANGE_REFERENCE_INDICATOR,
+        FOREIGN_EXCHANGE_REFERENCE,
+        ISO_DESTINATION_COUNTRY_CODE,
+        ORIGINATOR_IDENTIFICATION,
+        STANDARD_ENTRY_CLASS_CODE,
+        COMPANY_ENTRY_DESCRIPTION,
+        ISO_ORIGINATING_CURRENCY_CODE,
+        ISO_DESTINATION_CURRENCY_CODE,
+        EFFECTIVE_ENTRY_DATE,
+        SETTLEMENT_DATE,
+        ORIGINATOR_STATUS_CODE,
+        ORIGINATING_DFI_ID,
+        BATCH_NUMBER,
+    ];
+}
+
+pub mod entry_detail {
+    pub use super::RECORD_TYPE_CODE;
+    use super::*;
+
+    pub const TRANSACTION_CODE: Field = numeric(
+        "transaction_code",
+        2,
+        3,
+        REQUIRED,
+        "account type and whether it's a credit or debit, e.g. 22 or 27",
+    );
+    pub const RECEIVING_DFI_ID: Field = numeric(
+        "receiving_dfi_id",
+        4,
+        11,
+        REQUIRED,
+        "first 8 digits of the RDFI's routing number",
+    );
+    pub const CHECK_DIGIT: Field = numeric(
+        "check_digit",
+        12,
+        12,
+        REQUIRED,
+        "last digit of the RDFI's routing number",
+    );
+    pub const DFI_ACCOUNT_NUMBER: Field = alpha(
+        "dfi_account_number",
+        13,
+        29,
+        REQUIRED,
+        "receiver's account number at the RDFI",
+    );
+    pub const AMOUNT: Field = numeric("amount", 30, 39, REQUIRED, "in cents");
+    pub const INDIVIDUAL_ID_NUMBER: Field = alpha(
+        "individual_id_number",
+        40,
+        54,
+        OPTIONAL,
+        "the originator's reference for the receiver",
+    );
+    pub const INDIVIDUAL_NAME: Field =
+        alpha("individual_name", 55, 76, REQUIRED, "name of the receiver");
+    pub const DISCRETIONARY_DATA: Field = alpha(
+        "discretionary_data",
+        77,
+        78,
+        OPTIONAL,
+        "for the ODFI's own use",
+    );
+    pub const ADDENDA_RECORD_INDICATOR: Field = numeric(
+        "addenda_record_indicator",
+        79,
+        79,
+        REQUIRED,
+        "1 if addenda follow, otherwise 0",
+    );
+    pub const TRACE_NUMBER: Field = numeric(
+        "trace_number",
+        80,
+        94,
+        REQUIRED,
+        "ODFI routing number (8 digits) and a sequence number",
+    );
+
+    pub const FIELDS: &[Field] = &[
+        RECORD_TYPE_CODE,
+        TRANSACTION_CODE,
+        RECEIVING_DFI_ID,
+        CHECK_DIGIT,
+        DFI_ACCOUNT_NUMBER,
+        AMOUNT,
+        INDIVIDUAL_ID_NUMBER,
+        INDIVIDUAL_NAME,
+        DISCRETIONARY_DATA,
+        ADDENDA_RECORD_INDICATOR,
+        TRACE_NUMBER,
+    ];
+}
+
+pub mod iat_entry_detail {
+    pub use super::entry_detail::{
+        ADDENDA_RECORD_INDICATOR, AMOUNT, CHECK_DIGIT, RECEIVING_DFI_ID, TRACE_NUMBER,
+        TRANSACTION_CODE,
+    };
+    pub use super::RECORD_TYPE_CODE;
+    use super::*;
+
+    pub const NUMBER_OF_ADDENDA: Field = numeric(
+        "number_of_addenda",
+        13,
+        16,
+        REQUIRED,
+        "number of addenda that follow",
+    );
+    pub const RESERVED: Field = alpha("reserved", 17, 29, OPTIONAL, "blank");
+    pub const FOREIGN_RECEIVER_ACCOUNT_NUMBER: Field = alpha(
+        "dfi_account_number",
+        40,
+        74,
+        REQUIRED,
+        "foreign receiver's account number, held in dfi_account_number",
+    );
+    pub const RESERVED_2: Field = alpha("reserved", 75, 76, OPTIONAL, "blank");
+    pub const GATEWAY_OFAC_SCREENING_INDICATOR: Field = alpha(
+        "gateway_ofac_screening_indicator",
+        77,
+        77,
+        OPTIONAL,
+        "1 if the gateway found a possible OFAC match",
+    );
+    pub const SECONDARY_OFAC_SCREENING_INDICATOR: Field = alpha(
+        "secondary_ofac_screening_indicator",
+        78,
+        78,
+        OPTIONAL,
+        "1 if another party found a possible OFAC match",
+    );
+
+    pub const FIELDS: &[Field] = &[
+        RECORD_TYPE_CODE,
+        TRANSACTION_CODE,
+        RECEIVING_DFI_ID,
+        CHECK_DIGIT,
+        NUMBER_OF_ADDENDA,
+        RESERVED,
+        AMOUNT,
+        FOREIGN_RECEIVER_ACCOUNT_NUMBER,
+        RESERVED_2,
+        GATEWAY_OFAC_SCREENING_INDICATOR,
+        SECONDARY_OFAC_SCREENING_INDICATOR,
+        ADDENDA_RECORD_INDICATOR,
+        TRACE_NUMBER,
+    ];
+}
+
+/// Automated accounting advices (SEC code `ADV`), which an ACH operator
+/// sends to tell an institution of its settlement entries.
+pub mod adv_entry_detail {
+    pub use super::entry_detail::{
+        ADDENDA_RECORD_INDICATOR, CHECK_DIGIT, DISCRETIONARY_DATA, RECEIVING_DFI_ID,
+        TRANSACTION_CODE,
+    };
+    pub use super::RECORD_TYPE_CODE;
+    use super::*;
+
+    pub const DFI_ACCOUNT_NUMBER: Field = alpha(
+        "dfi_account_number",
+        13,
+        27,
+        REQUIRED,
+        "account number at the institution the advice is for",
+    );
+    pub const AMOUNT: Field = numeric("amount", 28, 39, REQUIRED, "in cents");
+    pub const ADVICE_ROUTING_NUMBER: Field = numeric(
+        "advice_routing_number",
+        40,
+        48,
+        REQUIRED,
+        "routing number of the institution the advice is for",
+    );
+    pub const FILE_IDENTIFICATION: Field = alpha(
+        "file_identification",
+        49,
+        53,
+        OPTIONAL,
+        "the file the advice is about",
+    );
+    pub const ACH_OPERATOR_DATA: Field = alpha(
+        "ach_operator_data",
+        54,
+        54,
+        OPTIONAL,
+        "for the ACH operator",
+    );
+    pub const INDIVIDUAL_NAME: Field = alpha(
+        "individual_name",
+        55,
+        76,
+        REQUIRED,
+        "name of the institution the advice is for",
+    );
+    pub const ACH_OPERATOR_ROUTING_NUMBER: Field = numeric(
+        "ach_operator_routing_number",
+        80,
+        87,
+        REQUIRED,
+        "first 8 digits of the ACH operator's routing number",
+    );
+    pub const JULIAN_DATE_CREATED: Field = numeric(
+        "julian_date_created",
+        88,
+        90,
+        REQUIRED,
+        "day of the year the advice was created",
+    );
+    pub const SEQUENCE_NUMBER: Field = numeric(
+        "sequence_number",
+        91,
+        94,
+        REQUIRED,
+        "number of the advice within the batch",
+    );
+
+    pub const FIELDS: &[Field] = &[
+        RECORD_TYPE_CODE,
+        TRANSACTION_CODE,
+        RECEIVING_DFI_ID,
+        CHECK_DIGIT,
+        DFI_ACCOUNT_NUMBER,
+        AMOUNT,
+        ADVICE_ROUTING_NUMBER,
+        FILE_IDENTIFICATION,
+        ACH_OPERATOR_DATA,
+        INDIVIDUAL_NAME,
+        DISCRETIONARY_DATA,
+        ADDENDA_RECORD_INDICATOR,
+        ACH_OPERATOR_ROUTING_NUMBER,
+        JULIAN_DATE_CREATED,
+        SEQUENCE_NUMBER,
+    ];
+}
+
+/// Addenda with no layout of their own, such as type 05 and the IAT
+/// remittance type 17, along with the columns all addenda share.
+pub mod addendum {
+    pub use super::RECORD_TYPE_CODE;
+    use super::*;
+
+    pub const ADDENDA_TYPE_CODE: Field = numeric(
+        "addenda_type_code",
+        2,
+        3,
+        REQUIRED,
+        "which kind of addendum this is, e.g. 05 or 99",
+    );
+    pub const PAYMENT_RELATED_INFO: Field = alpha(
+        "payment_related_info",
+        4,
+        83,
+        OPTIONAL,
+        "free-form text about the payment",
+    );
+    pub const ADDENDA_SEQUENCE_NUMBER: Field = numeric(
+        "addenda_sequence_number",
+        84,
+        87,
+        REQUIRED,
+        "numbers the addenda of an entry from 1",
+    );
+    pub const ENTRY_DETAIL_SEQUENCE_NUMBER: Field = numeric(
+        "entry_detail_sequence_number",
+        88,
+        94,
+        REQUIRED,
+        "last 7 digits of the entry's trace number",
+    );
+
+    pub const FIELDS: &[Field] = &[
+        RECORD_TYPE_CODE,
+        ADDENDA_TYPE_CODE,
+        PAYMENT_RELATED_INFO,
+        ADDENDA_SEQUENCE_NUMBER,
+        ENTRY_DETAIL_SEQUENCE_NUMBER,
+    ];
+}
+
+/// Addenda type 02.
+pub mod terminal_addendum {
+    pub use super::addendum::ADDENDA_TYPE_CODE;
+    pub use super::entry_detail::TRACE_NUMBER;
+    pub use super::RECORD_TYPE_CODE;
+    use super::*;
+
+    pub const REFERENCE_INFORMATION_1: Field = alpha(
+        "reference_information_1",
+        4,
+        10,
+        OPTIONAL,
+        "for the originator's own use",
+    );
+    pub const REFERENCE_INFORMATION_2: Field = alpha(
+        "reference_information_2",
+        11,
+        13,
+        OPTIONAL,
+        "for the originator's own use",
+    );
+    pub const TERMINAL_IDENTIFICATION_CODE: Field = alpha(
+        "terminal_identification_code",
+        14,
+        19,
+        REQUIRED,
+        "identifies the terminal",
+    );
+    pub const TRANSACTION_SERIAL_NUMBER: Field = alpha(
+        "transaction_serial_number",
+        20,
+        25,
+        REQUIRED,
+        "assigned by the terminal",
+    );
+    pub const TRANSACTION_DATE: Field = alpha(
+        "transaction_date",
+        26,
+        29,
+        REQUIRED,
+        "MMDD of the transaction",
+    );
+    pub const AUTHORIZATION_CODE_OR_CARD_EXPIRATION_DATE: Field = alpha(
+        "authorization_code_or_card_expiration_date",
+        30,
+        35,
+        OPTIONAL,
+        "authorization code, or the card's expiration date",
+    );
+    pub const TERMINAL_LOCATION: Field = alpha(
+        "terminal_location",
+        36,
+        62,
+        REQUIRED,
+        "street address of the terminal",
+    );
+    pub const TERMINAL_CITY: Field =
+        alpha("terminal_city", 63, 77, REQUIRED, "city of the terminal");
+    pub const TERMINAL_STATE: Field =
+        alpha("terminal_state", 78, 79, REQUIRED, "state of the terminal");
+
+    pub const FIELDS: &[Field] = &[
+        RECORD_TYPE_CODE,
+        ADDENDA_TYPE_CODE,
+        REFERENCE_INFORMATION_1,
+        REFERENCE_INFORMATION_2,
+        TERMINAL_IDENTIFICATION_CODE,
+        TRANSACTION_SERIAL_NUMBER,
+        TRANSACTION_DATE,
+        AUTHORIZATION_CODE_OR_CARD_EXPIRATION_DATE,
+        TERMINAL_LOCATION,
+        TERMINAL_CITY,
+        TERMINAL_STATE,
+        TRACE_NUMBER,
+    ];
+}
+
+/// Addenda type 98.
+pub mod notification_of_change_addendum {
+    pub use super::addendum::ADDENDA_TYPE_CODE;
+    pub use super::entry_detail::TRACE_NUMBER;
+    pub use super::RECORD_TYPE_CODE;
+    use super::*;
+
+    pub const CHANGE_CODE: Field = alpha(
+        "change_code",
+        4,
+        6,
+        REQUIRED,
+        "C01 to C14, which details are being corrected",
+    );
+    pub const ORIGINAL_ENTRY_TRACE_NUMBER: Field = numeric(
+        "original_entry_trace_number",
+        7,
+        21,
+        REQUIRED,
+        "trace number of the entry being corrected",
+    );
+    pub const RESERVED: Field = alpha("reserved", 22, 27, OPTIONAL, "blank");
+    pub const ORIGINAL_RDFI_ID: Field = numeric(
+        "original_rdfi_id",
+        28,
+        35,
+        REQUIRED,
+        "receiving DFI id of the entry being corrected",
+    );
+    pub const CORRECTED_DATA: Field =
+        alpha("corrected_data", 36, 64, REQUIRED, "the correct details");
+    pub const RESERVED_2: Field = alpha("reserved", 65, 79, OPTIONAL, "blank");
+
+    pub const FIELDS: &[Field] = &[
+        RECORD_TYPE_CODE,
+        ADDENDA_TYPE_CODE,
+        CHANGE_CODE,
+        ORIGINAL_ENTRY_TRACE_NUMBER,
+        RESERVED,
+        ORIGINAL_RDFI_ID,
+        CORRECTED_DATA,
+        RESERVED_2,
+        TRACE_NUMBER,
+    ];
+}
+
+/// Addenda type 99.
+pub mod return_addendum {
+    pub use super::addendum::ADDENDA_TYPE_CODE;
+    pub use super::entry_detail::TRACE_NUMBER;
+    pub use super::RECORD_TYPE_CODE;
+    use super::*;
+
+    pub const RETURN_REASON_CODE: Field = alpha(
+        "return_reason_code",
+        4,
+        6,
+        REQUIRED,
+        "R01 to R85, why the entry was returned",
+    );
+    pub const ORIGINAL_ENTRY_TRACE_NUMBER: Field = numeric(
+        "original_entry_trace_number",
+        7,
+        21,
+        REQUIRED,
+        "trace number of the entry being returned",
+    );
+    pub const DATE_OF_DEATH: Field =
+        date("date_of_death", 22, 27, OPTIONAL, "for R14 and R15 returns");
+    pub const ORIGINAL_RDFI_ID: Field = numeric(
+        "original_rdfi_id",
+        28,
+        35,
+        REQUIRED,
+        "receiving DFI id of the entry being returned",
+    );
+    pub const ADDENDA_INFORMATION: Field = alpha(
+        "addenda_information",
+        36,
+        79,
+        OPTIONAL,
+        "further details of the return",
+    );
+
+    pub const FIELDS: &[Field] = &[
+        RECORD_TYPE_CODE,
+        ADDENDA_TYPE_CODE,
+        RETURN_REASON_CODE,
+        ORIGINAL_ENTRY_TRACE_NUMBER,
+        DATE_OF_DEATH,
+        ORIGINAL_RDFI_ID,
+        ADDENDA_INFORMATION,
+        TRACE_NUMBER,
+    ];
+}
+
+/// IAT addenda type 10.
+pub mod iat_payment_addendum {
+    pub use super::addendum::{ADDENDA_TYPE_CODE, ENTRY_DETAIL_SEQUENCE_NUMBER};
+    pub use super::RECORD_TYPE_CODE;
+    use super::*;
+
+    pub const TRANSACTION_TYPE_CODE: Field = alpha(
+        "transaction_type_code",
+        4,
+        6,
+        REQUIRED,
+        "purpose of the payment, e.g. ANN, BUS or SAL",
+    );
+    pub const FOREIGN_PAYMENT_AMOUNT: Field = numeric(
+        "foreign_payment_amount",
+        7,
+        24,
+        REQUIRED,
+        "in cents, for inbound entries",
+    );
+    pub const FOREIGN_TRACE_NUMBER: Field = alpha(
+        "foreign_trace_number",
+        25,
+        46,
+        OPTIONAL,
+        "trace number given to the entry abroad",
+    );
+    pub const RECEIVING_NAME: Field =
+        alpha("receiving_name", 47, 81, REQUIRED, "name of the receiver");
+    pub const RESERVED: Field = alpha("reserved", 82, 87, OPTIONAL, "blank");
+
+    pub const FIELDS: &[Field] = &[
+        RECORD_TYPE_CODE,
+        ADDENDA_TYPE_CODE,
+        TRANSACTION_TYPE_CODE,
+        FOREIGN_PAYMENT_AMOUNT,
+        FOREIGN_TRACE_NUMBER,
+        RECEIVING_NAME,
+        RESERVED,
+        ENTRY_DETAIL_SEQUENCE_NUMBER,
+    ];
+}
+
+/// IAT addenda type 11.
+pub mod iat_originator_addendum {
+    pub use super::addendum::{ADDENDA_TYPE_CODE, ENTRY_DETAIL_SEQUENCE_NUMBER};
+    pub use super::RECORD_TYPE_CODE;
+    use super::*;
+
+    pub const ORIGINATOR_NAME: Field =
+        alpha("originator_name", 4, 38, REQUIRED, "name of the originator");
+    pub const ORIGINATOR_STREET_ADDRESS: Field = alpha(
+        "originator_street_address",
+        39,
+        73,
+        REQUIRED,
+        "street address of the originator",
+    );
+    pub const RESERVED: Field = alpha("reserved", 74, 87, OPTIONAL, "blank");
+
+    pub const FIELDS: &[Field] = &[
+        RECORD_TYPE_CODE,
+        ADDENDA_TYPE_CODE,
+        ORIGINATOR_NAME,
+        ORIGINATOR_STREET_ADDRESS,
+        RESERVED,
+        ENTRY_DETAIL_SEQUENCE_NUMBER,
+    ];
+}
+
+/// IAT addenda type 12.
+pub mod iat_originator_address_addendum {
+    pub use super::addendum::{ADDENDA_TYPE_CODE, ENTRY_DETAIL_SEQUENCE_NUMBER};
+    pub use super::RECORD_TYPE_CODE;
+    use super::*;
+
+    pub const ORIGINATOR_CITY_STATE: Field = alpha(
+        "originator_city_state",
+        4,
+        38,
+        REQUIRED,
+        "city and state or province, separated by `*` and ended by `\\`",
+    );
+    pub const ORIGINATOR_COUNTRY_POSTAL_CODE: Field = alpha(
+        "originator_country_postal_code",
+        39,
+        73,
+        REQUIRED,
+        "country and postal code, separated by `*` and ended by `\\`",
+    );
+    pub const RESERVED: Field = alpha("reserved", 74, 87, OPTIONAL, "blank");
+
+    pub const FIELDS: &[Field] = &[
+        RECORD_TYPE_CODE,
+        ADDENDA_TYPE_CODE,
+        ORIGINATOR_CITY_STATE,
+        ORIGINATOR_COUNTRY_POSTAL_CODE,
+        RESERVED,
+        ENTRY_DETAIL_SEQUENCE_NUMBER,
+    ];
+}
+
+/// IAT addenda types 13 and 14, for the originating and receiving banks.
+pub mod iat_bank_addendum {
+    pub use super::addendum::{ADDENDA_TYPE_CODE, ENTRY_DETAIL_SEQUENCE_NUMBER};
+    pub use super::RECORD_TYPE_CODE;
+    use super::*;
+
+    pub const BANK_NAME: Field = alpha("bank_name", 4, 38, REQUIRED, "name of the bank");
+    pub const BANK_ID_NUMBER_QUALIFIER: Field = alpha(
+        "bank_id_number_qualifier",
+        39,
+        40,
+        REQUIRED,
+        "01 national clearing system, 02 BIC, 03 IBAN",
+    );
+    pub const BANK_ID: Field = alpha(
+        "bank_id",
+        41,
+        74,
+        REQUIRED,
+        "the bank's id under the qualifier's scheme",
+    );
+    pub const BANK_BRANCH_COUNTRY_CODE: Field = alpha(
+        "bank_branch_country_code",
+        75,
+        77,
+        REQUIRED,
+        "ISO 3166 code of the branch's country",
+    );
+    pub const RESERVED: Field = alpha("reserved", 78, 87, OPTIONAL, "blank");
+
+    pub const FIELDS: &[Field] = &[
+        RECORD_TYPE_CODE,
+        ADDENDA_TYPE_CODE,
+        BANK_NAME,
+        BANK_ID_NUMBER_QUALIFIER,
+        BANK_ID,
+        BANK_BRANCH_COUNTRY_CODE,
+        RESERVED,
+        ENTRY_DETAIL_SEQUENCE_NUMBER,
+    ];
+}
+
+/// IAT addenda type 15.
+pub mod iat_receiver_addendum {
+    pub use super::addendum::{ADDENDA_TYPE_CODE, ENTRY_DETAIL_SEQUENCE_NUMBER};
+    pub use super::RECORD_TYPE_CODE;
+    use super::*;
+
+    pub const RECEIVER_ID_NUMBER: Field = alpha(
+        "receiver_id_number",
+        4,
+        18,
+        OPTIONAL,
+        "the originator's reference for the receiver",
+    );
+    pub const RECEIVER_STREET_ADDRESS: Field = alpha(
+        "receiver_street_address",
+        19,
+        53,
+        REQUIRED,
+        "street address of the receiver",
+    );
+    pub const RESERVED: Field = alpha("reserved", 54, 87, OPTIONAL, "blank");
+
+    pub const FIELDS: &[Field] = &[
+        RECORD_TYPE_CODE,
+        ADDENDA_TYPE_CODE,
+        RECEIVER_ID_NUMBER,
+        RECEIVER_STREET_ADDRESS,
+        RESERVED,
+        ENTRY_DETAIL_SEQUENCE_NUMBER,
+    ];
+}
+
+/// IAT addenda type 16.
+pub mod iat_receiver_address_addendum {
+    pub use super::addendum::{ADDENDA_TYPE_CODE, ENTRY_DETAIL_SEQUENCE_NUMBER};
+    pub use super::RECORD_TYPE_CODE;
+    use super::*;
+
+    pub const RECEIVER_CITY_STATE: Field = alpha(
+        "receiver_city_state",
+        4,
+        38,
+        REQUIRED,
+        "city and state or province, separated by `*` and ended by `\\`",
+    );
+    pub const RECEIVER_COUNTRY_POSTAL_CODE: Field = alpha(
+        "receiver_country_postal_code",
+        39,
+        73,
+        REQUIRED,
+        "country and postal code, separated by `*` and ended by `\\`",
+    );
+    pub const RESERVED: Field = alpha("reserved", 74, 87, OPTIONAL, "blank");
+
+    pub const FIELDS: &[Field] = &[
+        RECORD_TYPE_CODE,
+        ADDENDA_TYPE_CODE,
+        RECEIVER_CITY_STATE,
+        RECEIVER_COUNTRY_POSTAL_CODE,
+        RESERVED,
+        ENTRY_DETAIL_SEQUENCE_NUMBER,
+    ];
+}
+
+/// IAT addenda type 18, which shares the bank columns of types 13 and 14
+/// but, as there can be several, has an addenda sequence number too.
+pub mod iat_foreign_correspondent_bank_addendum {
+    pub use super::addendum::{
+        ADDENDA_SEQUENCE_NUMBER, ADDENDA_TYPE_CODE, ENTRY_DETAIL_SEQUENCE_NUMBER,
+    };
+    pub use super::iat_bank_addendum::{
+        BANK_BRANCH_COUNTRY_CODE, BANK_ID, BANK_ID_NUMBER_QUALIFIER, BANK_NAME,
+    };
+    pub use super::RECORD_TYPE_CODE;
+    use super::*;
+
+    pub const RESERVED: Field = alpha("reserved", 78, 83, OPTIONAL, "blank");
+
+    pub const FIELDS: &[Field] = &[
+        RECORD_TYPE_CODE,
+        ADDENDA_TYPE_CODE,
+        BANK_NAME,
+        BANK_ID_NUMBER_QUALIFIER,
+        BANK_ID,
+        BANK_BRANCH_COUNTRY_CODE,
+        RESERVED,
+        ADDENDA_SEQUENCE_NUMBER,
+        ENTRY_DETAIL_SEQUENCE_NUMBER,
+    ];
+}
+
+pub mod batch_control {
+    pub use super::batch_header::{BATCH_NUMBER, ORIGINATING_DFI_ID, SERVICE_CLASS_CODE};
+    pub use super::RECORD_TYPE_CODE;
+    use super::*;
+
+    pub const ENTRY_ADDENDA_COUNT: Field = numeric(
+        "entry_addenda_count",
+        5,
+        10,
+        REQUIRED,
+        "number of entries and addenda in the batch",
+    );
+    pub const ENTRY_HASH: Field = numeric(
+        "entry_hash",
+        11,
+        20,
+        REQUIRED,
+        "sum of the entries' receiving DFI ids, keeping the last 10 digits",
+    );
+    pub const TOTAL_DEBIT: Field = numeric(
+        "total_debit",
+        21,
+        32,
+        REQUIRED,
+        "sum of the debit entries, in cents",
+    );
+    pub const TOTAL_CREDIT: Field = numeric(
+        "total_credit",
+        33,
+        44,
+        REQUIRED,
+        "sum of the credit entries, in cents",
+    );
+    pub const COMPANY_ID: Field = alpha(
+        "company_id",
+        45,
+        54,
+        OPTIONAL,
+        "same as in the batch header, though IAT batches often leave it blank",
+    );
+    pub const MESSAGE_AUTHENTICATION_CODE: Field = alpha(
+        "message_authentication_code",
+        55,
+        73,
+        OPTIONAL,
+        "for validating the batch's authenticity",
+    );
+    pub const RESERVED: Field = alpha("reserved", 74, 79, OPTIONAL, "blank");
+
+    pub const FIELDS: &[Field] = &[
+        RECORD_TYPE_CODE,
+        SERVICE_CLASS_CODE,
+        ENTRY_ADDENDA_COUNT,
+        ENTRY_HASH,
+        TOTAL_DEBIT,
+        TOTAL_CREDIT,
+        COMPANY_ID,
+        MESSAGE_AUTHENTICATION_CODE,
+        RESERVED,
+        ORIGINATING_DFI_ID,
+        BATCH_NUMBER,
+    ];
+}
+
+pub mod file_control {
+    pub use super::RECORD_TYPE_CODE;
+    use super::*;
+
+    pub const BATCH_COUNT: Field = numeric(
+        "batch_count",
+        2,
+        7,
+        REQUIRED,
+        "number of batches in the file",
+    );
+    pub const BLOCK_COUNT: Field = numeric(
+        "block_count",
+        8,
+        13,
+        REQUIRED,
+        "number of 10 record blocks, padding included",
+    );
+    pub const ENTRY_AND_ADDENDA_COUNT: Field = numeric(
+        "entry_and_addenda_count",
+        14,
+        21,
+        REQUIRED,
+        "number of entries and addenda in the file",
+    );
+    pub const ENTRY_HASH: Field = numeric(
+        "entry_hash",
+        22,
+        31,
+        REQUIRED,
+        "sum of the batches' entry hashes, keeping the last 10 digits",
+    );
+    pub const TOTAL_DEBIT: Field = numeric(
+        "total_debit",
+        32,
+        43,
+        REQUIRED,
+        "sum of the debit entries, in cents",
+    );
+    pub const TOTAL_CREDIT: Field = numeric(
+        "total_credit",
+        44,
+        55,
+        REQUIRED,
+        "sum of the credit entries, in cents",
+    );
+    pub const RESERVED: Field = alpha("reserved", 56, 94, OPTIONAL, "blank");
+
+    pub const FIELDS: &[Field] = &[
+        RECORD_TYPE_CODE,
+        BATCH_COUNT,
+        BLOCK_COUNT,
+        ENTRY_AND_ADDENDA_COUNT,
+        ENTRY_HASH,
+        TOTAL_DEBIT,
+        TOTAL_CREDIT,
+        RESERVED,
+    ];
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::writer::RECORD_SIZE;
+
+    #[test]
+    fn every_layout_covers_the_record_once() {
+        for (record, fields) in ALL {
+            let mut next = 1;
+            for field in *fields {
+                assert_eq!(
+                    field.start,
+                    next,
+                    "{}.{} starts at column {} after column {}",
+                    record,
+                    field.name,
+                    field.start,
+                    next - 1
+                );
+                assert!(field.end >= field.start, "{}.{}", record, field.name);
+                next = field.end + 1;
+            }
+            assert_eq!(
+                next,
+                RECORD_SIZE + 1,
+                "{} ends at column {}",
+                record,
+                next - 1
+            );
+            assert_eq!(fields[0], RECORD_TYPE_CODE, "{}", record);
+        }
+    }
+
+    #[test]
+    fn dates_are_six_columns_wide() {
+        for (record, fields) in ALL {
+            for field in fields.iter().filter(|f| f.kind == FieldType::Date) {
+                assert_eq!(field.width(), 6, "{}.{} is YYMMDD", record, field.name);
+            }
+        }
+    }
+
+    #[test]
+    fn example_records_hold_digits_in_numeric_fields() {
+        let examples = [
+            include_str!("../examples/small.ach"),
+            include_str!("../examples/full.ach"),
+            include_str!("../examples/large.ach"),
+            include_str!("../examples/adv.ach"),
+        ];
+        for content in examples {
+            let mut entries = EntryLayout::Standard;
+            for record in content.lines().filter(|l| !l.starts_with("9999")) {
+                if RECORD_TYPE_CODE.text(record) == "5" {
+                    let class = batch_header::STANDARD_ENTRY_CLASS_CODE.text(record);
+                    entries = EntryLayout::for_class(&class.parse().unwrap());
+                }
+                let fields = for_record(record, entries).unwrap();
+                for field in fields.iter().filter(|f| f.kind != FieldType::Alphanumeric) {
+                    let text = field.text(record);
+                    assert!(
+                        text.trim().chars().all(|c| c.is_ascii_digit()),
+                        "{} holds {:?} in {}",
+                        field.name,
+                        text,
+                        record
+                    );
+                }
+            }
+        }
+    }
+}
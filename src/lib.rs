@@ -1,9 +1,55 @@
-use chrono::{NaiveDate, NaiveTime};
-use log::{debug, info};
-use serde::{Deserialize, Serialize, Serializer};
-use thousands::Separable;
+use chrono::{Datelike, NaiveDate, NaiveTime};
+use codes::Code;
+use log::warn;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::cell::{Cell, RefCell};
+use std::ops::Range;
+use std::str::FromStr;
+use writer::Sources;
 
-const FORMAT: &'static str = "%H:%M";
+mod addenda;
+mod adv;
+mod amount;
+mod borrowed;
+mod builder;
+mod codes;
+mod control;
+mod error;
+mod export;
+mod iat;
+mod import;
+pub mod layout;
+mod normalize;
+mod reader;
+mod routing;
+mod validate;
+mod writer;
+
+pub use addenda::{AddendaDetail, NotificationOfChangeAddendum, ReturnAddendum, TerminalAddendum};
+pub use adv::AdvEntryDetail;
+pub use amount::{Amount, Currency, InvalidAmount};
+pub use borrowed::{
+    AddendumRef, BatchControlRef, BatchHeaderRef, BatchRef, DetailEntryRef, FileControlRef,
+    FileHeaderRef, NachaFileRef,
+};
+pub use builder::{BatchBuilder, NachaFileBuilder};
+pub use codes::{AccountType, ServiceClassCode, StandardEntryClass, TransactionCode, UnknownCode};
+pub use error::{Diagnostic, ParseError, ParseErrorKind, ReadError, Severity, WriteError};
+pub use export::{write_csv, CsvAddenda};
+pub use iat::{
+    IatBankAddendum, IatBatchHeader, IatEntryDetail, IatOriginatorAddendum,
+    IatOriginatorAddressAddendum, IatPaymentAddendum, IatReceiverAddendum,
+    IatReceiverAddressAddendum,
+};
+pub use import::{Direction, ImportColumns, ImportConfig, ImportError};
+pub use layout::{EntryLayout, Field, FieldType};
+pub use normalize::normalize;
+pub use reader::{NachaReader, Record};
+pub use routing::{RoutingNumber, RoutingNumberError};
+pub use validate::{ValidationError, Validator};
+pub use writer::RECORD_SIZE;
+
+const FORMAT: &str = "%H:%M";
 
 pub fn hh_mm_format<S>(time: &Option<NaiveTime>, serializer: S) -> Result<S::Ok, S::Error>
 where
@@ -18,79 +64,270 @@ where
     }
 }
 
-pub trait Currency {
-    fn pretty_dollars_cents(&self) -> String;
+/// reads back what [`hh_mm_format`] writes, a blank string being `None`
+pub fn hh_mm_parse<'de, D>(deserializer: D) -> Result<Option<NaiveTime>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let s = String::deserialize(deserializer)?;
+    if s.trim().is_empty() {
+        return Ok(None);
+    }
+    NaiveTime::parse_from_str(s.trim(), FORMAT)
+        .map(Some)
+        .map_err(serde::de::Error::custom)
 }
 
-impl Currency for u32 {
-    fn pretty_dollars_cents(&self) -> String {
-        return format!("{:.2}", *self as f32 / 100.0).separate_with_commas();
+/// One line of input along with its line number, used to slice out fields
+/// and to build a [`ParseError`] when a field can't be read.
+///
+/// When a `sink` is attached the line is lenient: field errors are pushed to
+/// the sink as diagnostics and the field falls back to its default value, or
+/// for a code, to its `Unknown` variant.
+pub(crate) struct Line<'a, 's> {
+    text: &'a str,
+    number: usize,
+    sink: Option<&'s RefCell<Vec<Diagnostic>>>,
+    reported_short: Cell<bool>,
+    ascii: bool,
+}
+
+impl<'a, 's> Line<'a, 's> {
+    pub(crate) fn new(text: &'a str, number: usize) -> Line<'a, 's> {
+        Line {
+            text,
+            number,
+            sink: None,
+            reported_short: Cell::new(false),
+            ascii: text.is_ascii(),
+        }
+    }
+
+    pub(crate) fn lenient(
+        text: &'a str,
+        number: usize,
+        sink: Option<&'s RefCell<Vec<Diagnostic>>>,
+    ) -> Line<'a, 's> {
+        Line {
+            sink,
+            ..Line::new(text, number)
+        }
+    }
+
+    pub(crate) fn error(&self, kind: ParseErrorKind, field: Field) -> ParseError {
+        let range = field.range();
+        let text = self
+            .byte_range(range.clone())
+            .or_else(|| self.byte_range(range.start..self.len()))
+            .map_or(self.text, |r| &self.text[r]);
+        ParseError {
+            kind,
+            line: self.number,
+            record_type: self
+                .text
+                .chars()
+                .next()
+                .map(String::from)
+                .unwrap_or_default(),
+            field: field.name,
+            range,
+            text: text.to_string(),
+        }
+    }
+
+    /// length in characters
+    fn len(&self) -> usize {
+        if self.ascii {
+            self.text.len()
+        } else {
+            self.text.chars().count()
+        }
+    }
+
+    /// Field positions count characters, which only match byte offsets
+    /// while the line is all ASCII.
+    fn byte_range(&self, range: Range<usize>) -> Option<Range<usize>> {
+        if self.ascii {
+            return (range.start <= range.end && range.end <= self.text.len()).then_some(range);
+        }
+        let mut offsets = self
+            .text
+            .char_indices()
+            .map(|(i, _)| i)
+            .chain(std::iter::once(self.text.len()));
+        let start = offsets.nth(range.start)?;
+        let end = match range.end.checked_sub(range.start)? {
+            0 => start,
+            n => offsets.nth(n - 1)?,
+        };
+        Some(start..end)
+    }
+
+    /// in lenient mode, swallows `result`'s error into the sink
+    fn recover<T: Default>(&self, result: Result<T, ParseError>) -> Result<T, ParseError> {
+        match (result, self.sink) {
+            (Err(e), Some(sink)) => {
+                sink.borrow_mut().push(e.into());
+                Ok(T::default())
+            }
+            (result, _) => result,
+        }
+    }
+
+    pub(crate) fn slice(&self, field: Field) -> Result<&'a str, ParseError> {
+        let range = field.range();
+        if let Some(r) = self.byte_range(range.clone()) {
+            return Ok(&self.text[r]);
+        }
+        let error = self.error(ParseErrorKind::ShortLine, field);
+        let sink = match self.sink {
+            Some(sink) => sink,
+            None => return Err(error),
+        };
+        // a short line is reported once, at the first field it cuts off,
+        // and every later field gets whatever is left of the line
+        if !self.reported_short.replace(true) {
+            sink.borrow_mut().push(error.into());
+        }
+        let end = range.end.min(self.len());
+        Ok(self
+            .byte_range(range.start.min(end)..end)
+            .map_or("", |r| &self.text[r]))
+    }
+
+    /// trimmed alphanumeric field, borrowed from the line
+    pub(crate) fn trimmed(&self, field: Field) -> Result<&'a str, ParseError> {
+        Ok(self.slice(field)?.trim())
+    }
+
+    /// trimmed alphanumeric field
+    pub(crate) fn alpha(&self, field: Field) -> Result<String, ParseError> {
+        self.trimmed(field).map(str::to_string)
+    }
+
+    pub(crate) fn numeric<T: FromStr + Default>(&self, field: Field) -> Result<T, ParseError> {
+        let parsed = self
+            .slice(field)?
+            .trim()
+            .parse()
+            .map_err(|_| self.error(ParseErrorKind::InvalidNumber, field));
+        self.recover(parsed)
+    }
+
+    /// amounts are held in the file as a whole number of cents
+    pub(crate) fn amount(&self, field: Field) -> Result<Amount, ParseError> {
+        self.numeric(field).map(Amount::from_cents)
+    }
+
+    /// One of the code enums, e.g. [`TransactionCode`]. In lenient mode a
+    /// code that isn't in the rules is kept as its `Unknown` variant, so it's
+    /// written back as it was found.
+    pub(crate) fn code<T: Code>(&self, field: Field) -> Result<T, ParseError> {
+        let text = self.slice(field)?.trim();
+        match (T::from_code(text), self.sink) {
+            (Some(code), _) => Ok(code),
+            (None, Some(sink)) => {
+                let error = self.error(ParseErrorKind::InvalidCode, field);
+                sink.borrow_mut().push(error.into());
+                Ok(T::unknown(text))
+            }
+            (None, None) => Err(self.error(ParseErrorKind::InvalidCode, field)),
+        }
+    }
+
+    /// dates are optional in several records, so anything unparseable is `None`
+    pub(crate) fn date(&self, field: Field) -> Result<Option<NaiveDate>, ParseError> {
+        Ok(NaiveDate::parse_from_str(self.slice(field)?.trim(), "%y%m%d").ok())
+    }
+
+    /// A Julian day of the year, as the ACH operator writes the settlement
+    /// date. The record doesn't say which year, so it's whichever puts the
+    /// day nearest `near`, the effective entry date.
+    pub(crate) fn julian(
+        &self,
+        field: Field,
+        near: Option<NaiveDate>,
+    ) -> Result<Option<NaiveDate>, ParseError> {
+        let day = self.slice(field)?.trim().parse::<u32>().ok();
+        Ok(day.zip(near).and_then(|(day, near)| julian_date(day, near)))
+    }
+
+    pub(crate) fn time(&self, field: Field) -> Result<Option<NaiveTime>, ParseError> {
+        Ok(NaiveTime::parse_from_str(self.slice(field)?.trim(), "%H%M").ok())
     }
 }
 
+/// the date of day `day` in the year before, of or after `near`, whichever is
+/// nearest, so that settlement just across new year lands in the right year
+fn julian_date(day: u32, near: NaiveDate) -> Option<NaiveDate> {
+    (near.year() - 1..=near.year() + 1)
+        .filter_map(|year| NaiveDate::from_yo_opt(year, day))
+        .min_by_key(|date| (*date - near).num_days().abs())
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct NachaFile {
     pub file_header: FileHeader,
     pub batches: Vec<Batch>,
     pub file_control: FileControl,
-    #[serde(skip_serializing)]
-    #[allow(dead_code)]
+    #[serde(skip)]
     raw: String,
+    /// where in `raw` each record was read from
+    #[serde(skip)]
+    sources: Sources,
 }
 
 impl NachaFile {
+    /// Parses `content` on a best-effort basis, logging any problems found.
+    /// Use [`NachaFile::parse`] or [`NachaFile::parse_lenient`] to get at them.
     pub fn new(content: String) -> NachaFile {
-        let _content = content.clone();
-        let mut file = NachaFile {
-            file_header: FileHeader::new(),
-            batches: Vec::new(),
-            file_control: FileControl::new(),
-            raw: _content,
-        };
-
-        for linestr in content.lines() {
-            let line = linestr.to_string();
-            let record_type = &line[0..1];
-            match record_type {
-                "1" => {
-                    debug!("file header found");
-                    file.file_header.parse(line);
-                }
-                "5" => {
-                    debug!("batch header found");
-                    let batch = Batch {
-                        batch_header: BatchHeader::parse(line),
-                        detail_entries: Vec::new(),
-                        batch_control: BatchControl::new(),
-                    };
-                    file.batches.push(batch);
-                }
-                "6" => {
-                    debug!("detail entry found");
-                    file.last_batch().new_entry(line);
-                }
-                "7" => {
-                    debug!("addendum entry found");
-                    file.last_batch().last_entry().add_addenda(line);
-                }
-                "8" => {
-                    debug!("batch control found");
-                    file.last_batch().batch_control.parse(line);
-                }
-                "9" => {
-                    debug!("file control found");
-                    file.file_control.parse(line);
-                    break;
-                }
-                _ => debug!("unknown record found"),
-            }
+        let (file, diagnostics) = NachaFile::parse_lenient(&content);
+        for d in diagnostics {
+            warn!("{}", d);
         }
-        info!("Done parsing file");
-        return file;
+        file
+    }
+
+    /// Parses the content of a NACHA file, stopping at the first malformed
+    /// record or field.
+    pub fn parse(content: &str) -> Result<NachaFile, ParseError> {
+        NachaFile::read(content, None)
+    }
+
+    /// Parses the content of a NACHA file, carrying on past malformed records
+    /// and fields. The content is [`normalize`]d first, codes that aren't in
+    /// the rules are kept as their `Unknown` variant, other bad fields are
+    /// left at their default value and orphan or unknown records are skipped;
+    /// every problem and change is returned alongside the best-effort file.
+    pub fn parse_lenient(content: &str) -> (NachaFile, Vec<Diagnostic>) {
+        NachaFile::parse_bytes_lenient(content.as_bytes())
+    }
+
+    /// Like [`NachaFile::parse_lenient`], for input that may not be UTF-8.
+    pub fn parse_bytes_lenient(input: &[u8]) -> (NachaFile, Vec<Diagnostic>) {
+        let (content, mut diagnostics) = normalize(input);
+        let sink = RefCell::new(Vec::new());
+        let file = NachaFile::read(&content, Some(&sink))
+            .expect("lenient parsing reports errors as diagnostics");
+        diagnostics.extend(sink.into_inner());
+        (file, diagnostics)
+    }
+
+    fn read(
+        content: &str,
+        sink: Option<&RefCell<Vec<Diagnostic>>>,
+    ) -> Result<NachaFile, ParseError> {
+        NachaFileRef::read(content, sink).map(|file| file.into_owned())
+    }
+
+    /// the text the file was parsed from, or for a file read from JSON or
+    /// YAML, the records as written by [`NachaFile::to_ach_string`]
+    pub fn raw(&self) -> &str {
+        &self.raw
     }
 
-    pub fn last_batch(&mut self) -> &mut Batch {
-        return self.batches.last_mut().unwrap();
+    pub fn last_batch(&mut self) -> Option<&mut Batch> {
+        self.batches.last_mut()
     }
     pub fn as_json(&self) -> String {
         serde_json::to_string_pretty(self).unwrap()
@@ -98,16 +335,43 @@ impl NachaFile {
     pub fn as_yaml(&self) -> String {
         serde_yaml::to_string(self).unwrap()
     }
+    /// Reads back what [`NachaFile::as_json`] writes, e.g. after editing it
+    /// by hand. The controls are kept as they are unless `recompute_controls`
+    /// is set, in which case they're worked out from the entries.
+    pub fn from_json(json: &str, recompute_controls: bool) -> serde_json::Result<NachaFile> {
+        NachaFile::deserialized(serde_json::from_str(json)?, recompute_controls)
+    }
+    /// Like [`NachaFile::from_json`], for what [`NachaFile::as_yaml`] writes.
+    pub fn from_yaml(yaml: &str, recompute_controls: bool) -> serde_yaml::Result<NachaFile> {
+        NachaFile::deserialized(serde_yaml::from_str(yaml)?, recompute_controls)
+    }
+    /// a value too wide for its field fails like any other bad input
+    fn deserialized<E: serde::de::Error>(
+        mut file: NachaFile,
+        recompute_controls: bool,
+    ) -> Result<NachaFile, E> {
+        if recompute_controls {
+            file.recompute_controls();
+        }
+        file.raw = file.to_ach_string().map_err(E::custom)?;
+        Ok(file)
+    }
+    /// one row per detail entry, see [`write_csv`]
+    pub fn as_csv(&self, addenda: CsvAddenda) -> String {
+        let mut out = Vec::new();
+        write_csv(&mut out, std::slice::from_ref(self), addenda).unwrap();
+        String::from_utf8(out).unwrap()
+    }
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
 pub struct FileHeader {
     pub record_type_code: String,
     pub priority_code: String,
     pub immediate_destination: String,
     pub immediate_origin: String,
     pub file_creation_date: Option<NaiveDate>,
-    #[serde(serialize_with = "hh_mm_format")]
+    #[serde(serialize_with = "hh_mm_format", deserialize_with = "hh_mm_parse")]
     pub file_creation_time: Option<NaiveTime>,
     pub file_id_modifier: String,
     pub record_size: String,
@@ -120,47 +384,22 @@ pub struct FileHeader {
 
 impl FileHeader {
     pub fn new() -> FileHeader {
-        FileHeader {
-            record_type_code: "".to_string(),
-            priority_code: "".to_string(),
-            immediate_destination: "".to_string(),
-            immediate_origin: "".to_string(),
-            file_creation_date: None,
-            file_creation_time: None,
-            file_id_modifier: "".to_string(),
-            record_size: "".to_string(),
-            blocking_factor: "".to_string(),
-            format_code: "".to_string(),
-            immediate_destination_name: "".to_string(),
-            immediate_origin_name: "".to_string(),
-            reference_code: "".to_string(),
-        }
+        FileHeader::default()
+    }
+    pub fn parse(text: &str, line_number: usize) -> Result<FileHeader, ParseError> {
+        FileHeader::from_line(&Line::new(text, line_number))
     }
-    pub fn parse(&mut self, line: String) {
-        let maybe_date = NaiveDate::parse_from_str(line[23..29].trim(), "%y%m%d");
-        let date = match maybe_date {
-            Ok(d) => Some(d),
-            Err(_) => None,
-        };
-        let maybe_time = NaiveTime::parse_from_str(line[29..33].trim(), "%H%M");
-        let time = match maybe_time {
-            Ok(t) => Some(t),
-            Err(_) => None,
-        };
 
-        self.record_type_code = line[0..1].trim().to_string();
-        self.priority_code = line[1..3].trim().to_string();
-        self.immediate_destination = line[3..13].trim().to_string();
-        self.immediate_origin = line[13..23].trim().to_string();
-        self.file_creation_date = date;
-        self.file_creation_time = time;
-        self.file_id_modifier = line[33..34].trim().to_string();
-        self.record_size = line[34..37].trim().to_string();
-        self.blocking_factor = line[37..39].trim().to_string();
-        self.format_code = line[39..40].trim().to_string();
-        self.immediate_destination_name = line[40..63].trim().to_string();
-        self.immediate_origin_name = line[63..86].trim().to_string();
-        self.reference_code = line[86..94].trim().to_string();
+    pub(crate) fn from_line(line: &Line) -> Result<FileHeader, ParseError> {
+        FileHeaderRef::from_line(line).map(|r| r.into_owned())
+    }
+
+    pub fn destination_routing_number(&self) -> Result<RoutingNumber, RoutingNumberError> {
+        RoutingNumber::from_immediate(&self.immediate_destination)
+    }
+
+    pub fn origin_routing_number(&self) -> Result<RoutingNumber, RoutingNumberError> {
+        RoutingNumber::from_immediate(&self.immediate_origin)
     }
 }
 
@@ -172,24 +411,30 @@ pub struct Batch {
 }
 
 impl Batch {
-    pub fn new_entry(&mut self, line: String) {
-        let detail = DetailEntry::parse(line);
+    /// the batch control as it was in the file, or as last recomputed
+    pub fn batch_control(&self) -> &BatchControl {
+        &self.batch_control
+    }
+
+    pub fn new_entry(&mut self, text: &str, line_number: usize) -> Result<(), ParseError> {
+        let detail = DetailEntry::parse(text, line_number)?;
         self.detail_entries.push(detail);
+        Ok(())
     }
 
-    pub fn last_entry(&mut self) -> &mut DetailEntry {
-        return self.detail_entries.last_mut().unwrap();
+    pub fn last_entry(&mut self) -> Option<&mut DetailEntry> {
+        self.detail_entries.last_mut()
     }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct BatchHeader {
     pub record_type_code: String,
-    pub service_class_code: String,
+    pub service_class_code: ServiceClassCode,
     pub company_name: String,
     pub company_discretionary_data: String,
     pub company_id: String,
-    pub standard_entry_class_code: String,
+    pub standard_entry_class_code: StandardEntryClass,
     pub company_entry_description: String,
     pub company_descriptive_date: String,
     pub effective_entry_date: Option<NaiveDate>,
@@ -197,48 +442,44 @@ pub struct BatchHeader {
     pub originator_status_code: String,
     pub originating_dfi_id: String,
     pub batch_number: String,
+    /// the IAT layout of columns 5-40 and 64-69, which replaces the company
+    /// name, discretionary data and descriptive date
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub iat: Option<IatBatchHeader>,
 }
 
 impl BatchHeader {
-    pub fn parse(line: String) -> BatchHeader {
-        let maybe_effective_date = NaiveDate::parse_from_str(line[69..75].trim(), "%y%m%d");
-        let edate = match maybe_effective_date {
-            Ok(d) => Some(d),
-            Err(_) => None,
-        };
-        let maybe_settlement_date = NaiveDate::parse_from_str(line[75..78].trim(), "%y%m%d");
-        let sdate = match maybe_settlement_date {
-            Ok(d) => Some(d),
-            Err(_) => None,
-        };
+    pub fn parse(text: &str, line_number: usize) -> Result<BatchHeader, ParseError> {
+        BatchHeader::from_line(&Line::new(text, line_number))
+    }
 
-        let bh = BatchHeader {
-            record_type_code: line[0..1].trim().to_string(),
-            service_class_code: line[1..4].trim().to_string(),
-            company_name: line[4..20].trim().to_string(),
-            company_discretionary_data: line[20..40].trim().to_string(),
-            company_id: line[40..50].trim().to_string(),
-            standard_entry_class_code: line[50..53].trim().to_string(),
-            company_entry_description: line[53..63].trim().to_string(),
-            company_descriptive_date: line[63..69].trim().to_string(),
-            effective_entry_date: edate,
-            settlement_date: sdate,
-            originator_status_code: line[78..79].trim().to_string(),
-            originating_dfi_id: line[79..87].trim().to_string(),
-            batch_number: line[87..94].trim().to_string(),
-        };
-        return bh;
+    pub(crate) fn from_line(line: &Line) -> Result<BatchHeader, ParseError> {
+        BatchHeaderRef::from_line(line).map(|r| r.into_owned())
+    }
+
+    pub fn is_iat(&self) -> bool {
+        self.standard_entry_class_code == StandardEntryClass::International
+    }
+
+    /// how the batch's entries are laid out
+    pub fn entry_layout(&self) -> EntryLayout {
+        EntryLayout::for_class(&self.standard_entry_class_code)
+    }
+
+    /// the ODFI routing number, with its check digit worked out
+    pub fn odfi_routing_number(&self) -> Result<RoutingNumber, RoutingNumberError> {
+        RoutingNumber::from_dfi_id(&self.originating_dfi_id)
     }
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
 pub struct BatchControl {
     pub record_type_code: String,
-    pub service_class_code: String,
+    pub service_class_code: ServiceClassCode,
     pub entry_addenda_count: String,
     pub entry_hash: String,
-    pub total_debit: u32,
-    pub total_credit: u32,
+    pub total_debit: Amount,
+    pub total_credit: Amount,
     pub company_id: String,
     pub message_authentication_code: String,
     pub reserved: String,
@@ -248,77 +489,92 @@ pub struct BatchControl {
 
 impl BatchControl {
     pub fn new() -> BatchControl {
-        BatchControl {
-            record_type_code: "".to_string(),
-            service_class_code: "".to_string(),
-            entry_addenda_count: "".to_string(),
-            entry_hash: "".to_string(),
-            total_debit: 0,
-            total_credit: 0,
-            company_id: "".to_string(),
-            message_authentication_code: "".to_string(),
-            reserved: "".to_string(),
-            originating_dfi_id: "".to_string(),
-            batch_number: "".to_string(),
-        }
+        BatchControl::default()
     }
-    pub fn parse(&mut self, line: String) {
-        self.record_type_code = line[0..1].trim().to_string();
-        self.service_class_code = line[1..4].trim().to_string();
-        self.entry_addenda_count = line[4..10].trim().to_string();
-        self.entry_hash = line[10..20].trim().to_string();
-        self.total_debit = line[20..32].trim().parse().unwrap();
-        self.total_credit = line[32..43].trim().parse().unwrap();
-        self.company_id = line[43..54].trim().to_string();
-        self.message_authentication_code = line[54..73].trim().to_string();
-        self.reserved = line[73..79].trim().to_string();
-        self.originating_dfi_id = line[79..87].trim().to_string();
-        self.batch_number = line[87..94].trim().to_string();
+    pub fn parse(text: &str, line_number: usize) -> Result<BatchControl, ParseError> {
+        BatchControl::from_line(&Line::new(text, line_number))
+    }
+
+    pub(crate) fn from_line(line: &Line) -> Result<BatchControl, ParseError> {
+        BatchControlRef::from_line(line).map(|r| r.into_owned())
     }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct DetailEntry {
     pub record_type_code: String,
-    pub transaction_code: String,
+    pub transaction_code: TransactionCode,
     pub receiving_dfi_id: String,
     pub check_digit: String,
     pub dfi_account_number: String,
-    pub amount: u32,
+    pub amount: Amount,
     pub individual_id_number: String,
     pub individual_name: String,
     pub discretionary_data: String,
     pub addenda_record_indicator: String,
     pub trace_number: String,
+    /// the IAT-only fields, which take the place of the individual id, name
+    /// and discretionary data
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub iat: Option<IatEntryDetail>,
+    /// the ADV-only fields, which take the place of the individual id and
+    /// trace number
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub adv: Option<AdvEntryDetail>,
     pub addenda: Vec<Addendum>,
 }
 
 impl DetailEntry {
-    pub fn parse(line: String) -> DetailEntry {
-        let entry = DetailEntry {
-            record_type_code: line[0..1].trim().to_string(),
-            transaction_code: line[1..3].trim().to_string(),
-            receiving_dfi_id: line[3..11].trim().to_string(),
-            check_digit: line[11..12].trim().to_string(),
-            dfi_account_number: line[12..29].trim().to_string(),
-            amount: line[29..39].trim().parse().unwrap(),
-            individual_id_number: line[39..54].trim().to_string(),
-            individual_name: line[54..76].trim().to_string(),
-            discretionary_data: line[76..78].trim().to_string(),
-            addenda_record_indicator: line[78..79].trim().to_string(),
-            trace_number: line[79..94].trim().to_string(),
-            addenda: Vec::new(),
-        };
-        return entry;
+    pub fn parse(text: &str, line_number: usize) -> Result<DetailEntry, ParseError> {
+        DetailEntry::from_line(&Line::new(text, line_number))
+    }
+
+    pub(crate) fn from_line(line: &Line) -> Result<DetailEntry, ParseError> {
+        DetailEntryRef::from_line(line).map(|r| r.into_owned())
+    }
+
+    /// parses an entry in an IAT batch, where the foreign receiver's account
+    /// number is held in `dfi_account_number`
+    pub fn parse_iat(text: &str, line_number: usize) -> Result<DetailEntry, ParseError> {
+        DetailEntry::from_iat_line(&Line::new(text, line_number))
+    }
+
+    pub(crate) fn from_iat_line(line: &Line) -> Result<DetailEntry, ParseError> {
+        DetailEntryRef::from_iat_line(line).map(|r| r.into_owned())
+    }
+
+    /// parses an entry in an ADV batch
+    pub fn parse_adv(text: &str, line_number: usize) -> Result<DetailEntry, ParseError> {
+        DetailEntry::from_adv_line(&Line::new(text, line_number))
+    }
+
+    pub(crate) fn from_adv_line(line: &Line) -> Result<DetailEntry, ParseError> {
+        DetailEntryRef::from_adv_line(line).map(|r| r.into_owned())
     }
 
-    pub fn add_addenda(&mut self, line: String) {
-        let new_addendum = Addendum::parse(line);
+    pub fn add_addenda(&mut self, text: &str, line_number: usize) -> Result<(), ParseError> {
+        let new_addendum = Addendum::parse(text, line_number)?;
         self.addenda.push(new_addendum);
+        Ok(())
+    }
+
+    pub fn rdfi_routing_number(&self) -> Result<RoutingNumber, RoutingNumberError> {
+        RoutingNumber::from_parts(&self.receiving_dfi_id, &self.check_digit)
     }
 
     pub fn has_addenda(&self) -> bool {
-        self.addenda.len() > 0
+        !self.addenda.is_empty()
+    }
+
+    /// the receiver's name, which IAT entries carry in their 10 addendum
+    pub fn receiver_name(&self) -> &str {
+        self.addenda
+            .iter()
+            .find_map(|a| match &a.detail {
+                Some(AddendaDetail::IatPayment(p)) => Some(p.receiving_name.as_str()),
+                _ => None,
+            })
+            .unwrap_or(&self.individual_name)
     }
 }
 
@@ -326,57 +582,368 @@ impl DetailEntry {
 pub struct Addendum {
     pub record_type_code: String,
     pub addenda_type_code: String,
+    /// the free-form text of types 05 and 17, and of addenda types without
+    /// a [`AddendaDetail`]; it's what gets written for them
     pub payment_related_info: String,
     pub addenda_sequence_number: String,
     pub entry_detail_sequence_number: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detail: Option<AddendaDetail>,
 }
 
 impl Addendum {
-    pub fn parse(line: String) -> Addendum {
-        let a = Addendum {
-            record_type_code: line[0..1].trim().to_string(),
-            addenda_type_code: line[1..3].trim().to_string(),
-            payment_related_info: line[3..83].trim().to_string(),
-            addenda_sequence_number: line[83..87].trim().to_string(),
-            entry_detail_sequence_number: line[87..94].trim().to_string(),
-        };
-        return a;
+    pub fn parse(text: &str, line_number: usize) -> Result<Addendum, ParseError> {
+        Addendum::from_line(&Line::new(text, line_number))
+    }
+
+    pub(crate) fn from_line(line: &Line) -> Result<Addendum, ParseError> {
+        AddendumRef::from_line(line).map(|r| r.into_owned())
+    }
+
+    /// the addendum's contents as a single line of text
+    pub fn summary(&self) -> String {
+        match &self.detail {
+            Some(detail) if !detail.is_free_text() => detail.to_string(),
+            _ => self.payment_related_info.clone(),
+        }
     }
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
 pub struct FileControl {
     pub record_type_code: String,
     pub batch_count: u32,
     pub block_count: u32,
     pub entry_and_addenda_count: u32,
     pub entry_hash: String,
-    pub total_debit: u32,
-    pub total_credit: u32,
+    pub total_debit: Amount,
+    pub total_credit: Amount,
     pub reserved: String,
 }
 
 impl FileControl {
     pub fn new() -> FileControl {
-        FileControl {
-            record_type_code: "".to_string(),
-            batch_count: 0,
-            block_count: 0,
-            entry_and_addenda_count: 0,
-            entry_hash: "".to_string(),
-            total_debit: 0,
-            total_credit: 0,
-            reserved: "".to_string(),
+        FileControl::default()
+    }
+    pub fn parse(text: &str, line_number: usize) -> Result<FileControl, ParseError> {
+        FileControl::from_line(&Line::new(text, line_number))
+    }
+
+    pub(crate) fn from_line(line: &Line) -> Result<FileControl, ParseError> {
+        FileControlRef::from_line(line).map(|r| r.into_owned())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SMALL: &str = include_str!("../examples/small.ach");
+
+    /// every example file, for tests that should hold for all of them
+    pub(crate) const EXAMPLES: [&str; 6] = [
+        SMALL,
+        include_str!("../examples/full.ach"),
+        include_str!("../examples/large.ach"),
+        include_str!("../examples/large_no_addenda.ach"),
+        include_str!("../examples/partial.ach"),
+        include_str!("../examples/adv.ach"),
+    ];
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    #[test]
+    fn julian_date_wraps_across_new_year() {
+        assert_eq!(julian_date(1, date(2019, 12, 31)), Some(date(2020, 1, 1)));
+        assert_eq!(julian_date(365, date(2020, 1, 2)), Some(date(2019, 12, 31)));
+        assert_eq!(julian_date(176, date(2019, 6, 24)), Some(date(2019, 6, 25)));
+    }
+
+    #[test]
+    fn julian_date_day_366_is_only_in_leap_years() {
+        assert_eq!(julian_date(366, date(2020, 6, 1)), Some(date(2020, 12, 31)));
+        assert_eq!(julian_date(366, date(2021, 1, 2)), Some(date(2020, 12, 31)));
+        assert_eq!(
+            julian_date(366, date(2019, 12, 30)),
+            Some(date(2020, 12, 31))
+        );
+        assert_eq!(julian_date(366, date(2022, 6, 1)), None);
+    }
+
+    #[test]
+    fn julian_date_out_of_range() {
+        assert_eq!(julian_date(0, date(2020, 6, 1)), None);
+        assert_eq!(julian_date(367, date(2020, 6, 1)), None);
+    }
+
+    #[test]
+    fn julian_round_trips_through_the_writer() {
+        for near in [date(2019, 12, 31), date(2020, 12, 30), date(2021, 1, 1)] {
+            let written = writer::julian(&Some(near));
+            let day = written.parse().unwrap();
+            assert_eq!(julian_date(day, near), Some(near), "{}", written);
         }
+        assert_eq!(writer::julian(&Some(date(2020, 1, 1))), "001");
+        assert_eq!(writer::julian(&None), "   ");
     }
-    pub fn parse(&mut self, line: String) {
-        self.record_type_code = line[0..1].trim().to_string();
-        self.batch_count = line[1..7].trim().parse().unwrap();
-        self.block_count = line[7..13].trim().parse().unwrap();
-        self.entry_and_addenda_count = line[13..21].trim().parse().unwrap();
-        self.entry_hash = line[21..31].trim().to_string();
-        self.total_debit = line[31..43].trim().parse().unwrap();
-        self.total_credit = line[43..55].trim().parse().unwrap();
-        self.reserved = line[55..94].trim().to_string();
+
+    #[test]
+    fn lenient_parse_reports_each_bad_field() {
+        let content = SMALL
+            .replacen("5225", "522X", 1)
+            .replacen("PPDREG", "PPXREG", 1)
+            .replacen("627231", "62X231", 1)
+            .replacen("0100000000 ", "01000000AB ", 1);
+        let (file, diagnostics) = NachaFile::parse_lenient(&content);
+        let found: Vec<_> = diagnostics
+            .iter()
+            .map(|d| (d.severity, d.line, d.field))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                (Severity::Error, Some(2), Some("standard_entry_class_code")),
+                (Severity::Error, Some(2), Some("service_class_code")),
+                (Severity::Error, Some(3), Some("transaction_code")),
+                (Severity::Error, Some(3), Some("amount")),
+            ]
+        );
+
+        // unknown codes are kept as found rather than replaced by a default
+        let batch = &file.batches[0];
+        assert_eq!(
+            batch.batch_header.service_class_code,
+            ServiceClassCode::Unknown("22X".to_string())
+        );
+        assert_eq!(
+            batch.batch_header.standard_entry_class_code,
+            StandardEntryClass::Unknown("PPX".to_string())
+        );
+        let code = &batch.detail_entries[0].transaction_code;
+        assert_eq!(code, &TransactionCode::Unknown("2X".to_string()));
+        assert!(!code.is_debit() && !code.is_credit());
+        let written = batch.detail_entries[0].to_record().unwrap();
+        assert!(written.starts_with("62X"));
+        assert!(batch.batch_header.to_record().unwrap().starts_with("522X"));
+    }
+
+    /// (kind, line, record_type, field, range, text) of the error parsing
+    /// `content` gives
+    fn parse_error(content: &str) -> (ParseErrorKind, usize, String, &str, Range<usize>, String) {
+        let e = NachaFile::parse(content).unwrap_err();
+        (e.kind, e.line, e.record_type, e.field, e.range, e.text)
+    }
+
+    fn replace_line(line: usize, with: &str) -> String {
+        let mut lines: Vec<&str> = SMALL.lines().collect();
+        lines[line - 1] = with;
+        lines.join("\n")
+    }
+
+    #[test]
+    fn error_points_at_the_field_a_short_line_cuts_off() {
+        let entry = SMALL.lines().nth(2).unwrap();
+        assert_eq!(
+            parse_error(&replace_line(3, &entry[..45])),
+            (
+                ParseErrorKind::ShortLine,
+                3,
+                "6".to_string(),
+                "individual_id_number",
+                39..54,
+                "      ".to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn error_points_at_a_non_numeric_amount() {
+        let content = SMALL.replacen("0100000000 ", "01000000AB ", 1);
+        assert_eq!(
+            parse_error(&content),
+            (
+                ParseErrorKind::InvalidNumber,
+                3,
+                "6".to_string(),
+                "amount",
+                29..39,
+                "01000000AB".to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn error_points_at_an_unknown_record_type() {
+        let control = SMALL.lines().nth(3).unwrap();
+        let content = replace_line(4, &format!("4{}", &control[1..]));
+        assert_eq!(
+            parse_error(&content),
+            (
+                ParseErrorKind::UnknownRecordType,
+                4,
+                "4".to_string(),
+                "record_type_code",
+                0..1,
+                "4".to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn error_points_at_orphan_entries_and_addenda() {
+        // an entry with no batch header before it
+        let mut lines: Vec<&str> = SMALL.lines().collect();
+        lines.remove(1);
+        assert_eq!(
+            parse_error(&lines.join("\n")),
+            (
+                ParseErrorKind::OrphanRecord,
+                2,
+                "6".to_string(),
+                "record_type_code",
+                0..1,
+                "6".to_string()
+            )
+        );
+
+        // an addendum with no entry before it
+        let addendum = format!("705{:<80}00010000001", "orphan");
+        let mut lines: Vec<&str> = SMALL.lines().collect();
+        lines.insert(2, &addendum);
+        assert_eq!(
+            parse_error(&lines.join("\n")),
+            (
+                ParseErrorKind::OrphanRecord,
+                3,
+                "7".to_string(),
+                "record_type_code",
+                0..1,
+                "7".to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn error_columns_count_characters_in_multi_byte_lines() {
+        let content =
+            SMALL
+                .replacen("Receiver", "Receivér", 1)
+                .replacen("0100000000 ", "01000000éé ", 1);
+        assert_eq!(
+            parse_error(&content),
+            (
+                ParseErrorKind::InvalidNumber,
+                3,
+                "6".to_string(),
+                "amount",
+                29..39,
+                "01000000éé".to_string()
+            )
+        );
+
+        // cut short partway through a name with multi-byte characters
+        let entry = SMALL
+            .lines()
+            .nth(2)
+            .unwrap()
+            .replacen("Receiver", "Récéivér", 1);
+        let short: String = entry.chars().take(62).collect();
+        assert_eq!(
+            parse_error(&replace_line(3, &short)),
+            (
+                ParseErrorKind::ShortLine,
+                3,
+                "6".to_string(),
+                "individual_name",
+                54..76,
+                "Récéivér".to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn strict_parse_stops_at_an_unknown_code() {
+        let content = SMALL.replacen("PPDREG", "PPXREG", 1);
+        let e = NachaFile::parse(&content).unwrap_err();
+        assert_eq!(e.kind, ParseErrorKind::InvalidCode);
+        assert_eq!((e.line, e.field), (2, "standard_entry_class_code"));
+    }
+
+    /// the lines of a file without the filler padding out its last block,
+    /// which the writer adds where an example may leave it out
+    fn records(content: &str) -> Vec<&str> {
+        content
+            .lines()
+            .filter(|l| !l.chars().all(|c| c == '9'))
+            .collect()
+    }
+
+    #[test]
+    fn json_round_trip_writes_the_same_records() {
+        // partial.ach has no controls to write back
+        let partial = EXAMPLES[4];
+        for content in EXAMPLES.into_iter().filter(|c| *c != partial) {
+            let json = NachaFile::parse(content).unwrap().as_json();
+            let file = NachaFile::from_json(&json, false).unwrap();
+            assert_eq!(records(&file.to_ach_string().unwrap()), records(content));
+        }
+    }
+
+    #[test]
+    fn json_round_trip_recomputes_only_the_controls() {
+        let not_controls = |content: &str| -> Vec<String> {
+            records(content)
+                .into_iter()
+                .filter(|l| !l.starts_with(['8', '9']))
+                .map(str::to_string)
+                .collect()
+        };
+        for content in EXAMPLES {
+            let json = NachaFile::parse(content).unwrap().as_json();
+            let file = NachaFile::from_json(&json, true).unwrap();
+            let written = file.to_ach_string().unwrap();
+            assert_eq!(not_controls(&written), not_controls(content));
+            let errors = NachaFile::parse(&written).unwrap().validate();
+            assert!(errors.is_empty(), "{:?}", errors);
+        }
+        // the controls of these were right already; full.ach's batch control
+        // leaves out the company id, which recomputing fills in
+        for content in [EXAMPLES[0], EXAMPLES[5]] {
+            let json = NachaFile::parse(content).unwrap().as_json();
+            let file = NachaFile::from_json(&json, true).unwrap();
+            assert_eq!(records(&file.to_ach_string().unwrap()), records(content));
+        }
+    }
+
+    #[test]
+    fn json_edits_to_amounts_carry_into_recomputed_controls() {
+        let full = EXAMPLES[1];
+        let mut json: serde_json::Value =
+            serde_json::from_str(&NachaFile::parse(full).unwrap().as_json()).unwrap();
+        let entries = &mut json["batches"][0]["detail_entries"];
+        // a debit and a credit
+        entries[0]["amount"] = 150000.into();
+        entries[1]["amount"] = 2500.into();
+        let json = json.to_string();
+
+        let file = NachaFile::from_json(&json, true).unwrap();
+        let control = &file.batches[0].batch_control;
+        assert_eq!(control.total_debit, Amount::from_cents(150000));
+        assert_eq!(control.total_credit, Amount::from_cents(2500));
+        assert_eq!(file.file_control.total_debit, Amount::from_cents(150000));
+        assert_eq!(file.file_control.total_credit, Amount::from_cents(2500));
+        assert!(file.validate().is_empty());
+        let written = file.to_ach_string().unwrap();
+        let reread = NachaFile::parse(&written).unwrap();
+        assert_eq!(reread.file_control.total_debit, Amount::from_cents(150000));
+
+        // without recomputing, the controls keep the old totals
+        let file = NachaFile::from_json(&json, false).unwrap();
+        let control = &file.batches[0].batch_control;
+        assert_eq!(control.total_debit, Amount::from_cents(100000));
+        assert_eq!(control.total_credit, Amount::from_cents(100000));
+        assert!(!file.validate().is_empty());
     }
 }
@@ -1,68 +1,315 @@
 use crate::term::run;
-use anyhow::{Context, Result};
-use clap::Parser;
+use anyhow::{anyhow, Context, Result};
+use clap::{ArgEnum, Args, Parser, Subcommand};
 use env_logger::Env;
+use nacha::CsvAddenda;
+use std::ffi::OsStr;
+use std::fs::File;
+use std::io::{self, BufWriter, IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
 use std::time::Duration;
-use std::{ffi::OsStr, fs::File, io::Write};
 mod app;
-mod lib;
+mod commands;
+mod filter;
 mod term;
 mod ui;
 
 /// Parse a NACHA file into a rust object
+///
+/// Exits with 0 when the input is valid, 1 when it isn't and 2 when it
+/// couldn't be read or the output couldn't be written.
 #[derive(Parser)]
 #[clap(author, version, about, long_about = None)]
+#[clap(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
 struct Cli {
-    /// path to your NACHA file
-    #[clap(parse(from_os_str))]
-    path: std::path::PathBuf,
-    /// name of output file, leave unset for stdout. json and yaml are supported if extension is provided, If no extension is detected, defaults to json.
-    #[clap(parse(from_os_str))]
-    output: Option<std::path::PathBuf>,
+    #[clap(subcommand)]
+    command: Option<Command>,
+    /// with no subcommand, opens a single file in the viewer, or converts
+    /// like `convert` when there's more to do than that
+    #[clap(flatten)]
+    convert: ConvertArgs,
 }
 
-impl Cli {
-    fn output(&self, data: &lib::NachaFile) {
-        if let Some(output_path) = &self.output {
-            let ext = output_path.extension().and_then(OsStr::to_str);
-            match ext {
-                Some("json") => {
-                    let mut output_file = File::create(output_path).unwrap();
-                    write!(output_file, "{}", data.as_json()).expect("could not write json file");
-                }
-                Some("yaml") => {
-                    let mut output_file = File::create(output_path).unwrap();
-                    write!(output_file, "{}", data.as_yaml()).expect("could not write yaml file");
-                }
-                Some("yml") => {
-                    let mut output_file = File::create(output_path).unwrap();
-                    write!(output_file, "{}", data.as_yaml()).expect("could not write yaml file");
-                }
-                None => {}
-                _ => {}
+#[derive(Subcommand)]
+enum Command {
+    /// explore a file in the terminal
+    View {
+        /// path of your NACHA file, `-` for stdin
+        #[clap(parse(from_os_str))]
+        path: PathBuf,
+    },
+    /// convert files to json, ndjson, yaml or csv, or json and yaml back to ach
+    Convert(ConvertArgs),
+    /// check files against their control records and routing numbers
+    Validate(Inputs),
+    /// print the batches and totals of each file
+    Summary(Inputs),
+    /// build an ACH file from CSV payment instructions
+    Build {
+        /// CSV with a row per payment, `-` for stdin
+        #[clap(parse(from_os_str))]
+        path: PathBuf,
+        /// YAML giving the origin, company and CSV column names
+        #[clap(short, long, parse(from_os_str))]
+        config: PathBuf,
+        /// name of output file, leave unset for stdout
+        #[clap(short, long, parse(from_os_str))]
+        output: Option<PathBuf>,
+    },
+    /// print the column layout of each record type
+    Layout {
+        /// just this record, e.g. `entry_detail`
+        record: Option<String>,
+    },
+}
+
+#[derive(Args)]
+struct Inputs {
+    /// paths or glob patterns of your NACHA files, `-` for stdin
+    #[clap(required = true, parse(from_os_str))]
+    paths: Vec<PathBuf>,
+}
+
+#[derive(Args)]
+struct ConvertArgs {
+    #[clap(flatten)]
+    inputs: Inputs,
+    /// name of output file, leave unset for stdout. json, ndjson, yaml, csv and ach are supported if extension is provided, If no extension is detected, defaults to json.
+    #[clap(short, long, parse(from_os_str))]
+    output: Option<PathBuf>,
+    /// output format, overriding the output file's extension
+    #[clap(short, long, arg_enum)]
+    format: Option<Format>,
+    /// work out the batch and file controls from the entries, rather than
+    /// keeping those in the input
+    #[clap(long)]
+    recompute_controls: bool,
+    /// how csv output lays out each entry's addenda
+    #[clap(long, arg_enum, default_value = "joined")]
+    addenda: Addenda,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ArgEnum)]
+enum Format {
+    Json,
+    /// one file per line, as compact json
+    Ndjson,
+    Yaml,
+    /// one row per detail entry
+    Csv,
+    /// fixed-width NACHA records, e.g. from edited json
+    Ach,
+}
+
+#[derive(Clone, Copy, ArgEnum)]
+enum Addenda {
+    None,
+    /// in one column, separated by `; `
+    Joined,
+    /// a column for each
+    Columns,
+}
+
+impl From<Addenda> for CsvAddenda {
+    fn from(a: Addenda) -> CsvAddenda {
+        match a {
+            Addenda::None => CsvAddenda::None,
+            Addenda::Joined => CsvAddenda::Joined,
+            Addenda::Columns => CsvAddenda::Columns,
+        }
+    }
+}
+
+impl Inputs {
+    /// the input paths, with any glob patterns expanded
+    fn expand(&self) -> Result<Vec<PathBuf>> {
+        let mut inputs = Vec::new();
+        for path in &self.paths {
+            let pattern = path.to_string_lossy();
+            if !pattern.contains(['*', '?', '[']) {
+                inputs.push(path.clone());
+                continue;
+            }
+            let mut matches = glob::glob(&pattern)
+                .with_context(|| format!("invalid glob pattern `{}`", pattern))?
+                .collect::<Result<Vec<_>, _>>()?;
+            if matches.is_empty() {
+                let message = format!("no files match `{}`", pattern);
+                return Err(io::Error::new(io::ErrorKind::NotFound, message).into());
             }
+            matches.sort();
+            inputs.append(&mut matches);
         }
+        Ok(inputs)
     }
 }
 
-fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let env = Env::default()
-        .filter_or("LOG_LEVEL", "warning")
-        .write_style_or("LOG_STYLE", "always");
-    env_logger::init_from_env(env);
+impl ConvertArgs {
+    /// Takes the old `nacha in.ach out.json` form, from before there was
+    /// `-o`, as `nacha in.ach -o out.json`, with a deprecation warning.
+    fn legacy_output(&mut self) {
+        let is_json_or_yaml = |path: &PathBuf| {
+            matches!(
+                path.extension().and_then(OsStr::to_str),
+                Some("json") | Some("yaml") | Some("yml")
+            )
+        };
+        match self.inputs.paths.as_slice() {
+            [input, output]
+                if self.output.is_none()
+                    && self.format.is_none()
+                    && !is_json_or_yaml(input)
+                    && is_json_or_yaml(output) =>
+            {
+                eprintln!(
+                    "warning: `nacha {} {}` is deprecated, use `nacha {} -o {}`",
+                    input.display(),
+                    output.display(),
+                    input.display(),
+                    output.display()
+                );
+                self.output = self.inputs.paths.pop();
+            }
+            _ => {}
+        }
+    }
 
-    let cli = Cli::parse();
+    fn format(&self) -> Format {
+        if let Some(format) = self.format {
+            return format;
+        }
+        let ext = self
+            .output
+            .as_ref()
+            .and_then(|p| p.extension())
+            .and_then(OsStr::to_str);
+        match ext {
+            Some("yaml") | Some("yml") => Format::Yaml,
+            Some("ndjson") | Some("jsonl") => Format::Ndjson,
+            Some("csv") => Format::Csv,
+            Some("ach") => Format::Ach,
+            _ => Format::Json,
+        }
+    }
+
+    /// the TUI is only for a single file, when nothing asked for output and
+    /// stdout isn't being piped somewhere
+    fn interactive(&self) -> bool {
+        self.inputs.paths.len() == 1
+            && self.output.is_none()
+            && self.format.is_none()
+            && io::stdout().is_terminal()
+    }
 
-    let content = std::fs::read_to_string(&cli.path)
-        .with_context(|| format!("could not read file `{}`", &cli.path.display()))?;
+    fn convert(&self) -> Result<bool> {
+        let inputs = self.inputs.expand()?;
+        let convert = |out: &mut dyn Write| {
+            commands::convert(
+                out,
+                &inputs,
+                self.format(),
+                self.addenda.into(),
+                self.recompute_controls,
+            )
+        };
+        if self.output.is_some() {
+            // converted in full before the file is created, so that a bad
+            // input doesn't leave it truncated
+            let mut converted = Vec::new();
+            convert(&mut converted)?;
+            let mut out = writer(self.output.as_deref())?;
+            out.write_all(&converted)?;
+            out.flush()?;
+        } else {
+            let mut out = writer(None)?;
+            convert(&mut out)?;
+            out.flush()?;
+        }
+        Ok(true)
+    }
+}
 
-    let mut nacha_file = lib::NachaFile::new(content);
+/// the output file, or stdout when there isn't one
+fn writer(output: Option<&Path>) -> Result<Box<dyn Write>> {
+    Ok(match output {
+        Some(path) => {
+            Box::new(BufWriter::new(File::create(path).with_context(|| {
+                format!("could not create file `{}`", path.display())
+            })?))
+        }
+        None => Box::new(BufWriter::new(io::stdout().lock())),
+    })
+}
 
-    cli.output(&nacha_file);
+fn view(path: &Path) -> Result<bool> {
+    let mut nacha_file = commands::read(path)?;
+    run(Duration::from_millis(1000), &mut nacha_file).map_err(|e| anyhow!("{}", e))?;
+    Ok(true)
+}
 
-    if let None = cli.output {
-        run(Duration::from_millis(1000), &mut nacha_file)?;
+/// runs the command, returning whether its input was valid
+fn execute(mut cli: Cli) -> Result<bool> {
+    let stdout = || BufWriter::new(io::stdout().lock());
+    match cli.command {
+        Some(Command::View { path }) => view(&path),
+        Some(Command::Convert(args)) => args.convert(),
+        Some(Command::Validate(inputs)) => {
+            let mut out = stdout();
+            let valid = commands::validate(&mut out, &inputs.expand()?)?;
+            out.flush()?;
+            Ok(valid)
+        }
+        Some(Command::Summary(inputs)) => {
+            let mut out = stdout();
+            commands::summary(&mut out, &inputs.expand()?)?;
+            out.flush()?;
+            Ok(true)
+        }
+        Some(Command::Build {
+            path,
+            config,
+            output,
+        }) => {
+            let file = match commands::build(&path, &config)? {
+                Some(file) => file,
+                None => return Ok(false),
+            };
+            let ach = file.to_ach_string()?;
+            let mut out = writer(output.as_deref())?;
+            write!(out, "{}", ach)?;
+            out.flush()?;
+            Ok(true)
+        }
+        Some(Command::Layout { record }) => {
+            let mut out = stdout();
+            commands::layout(&mut out, record.as_deref())?;
+            out.flush()?;
+            Ok(true)
+        }
+        None => {
+            cli.convert.legacy_output();
+            if cli.convert.interactive() {
+                view(&cli.convert.inputs.paths[0])
+            } else {
+                cli.convert.convert()
+            }
+        }
     }
+}
 
-    Ok(())
+fn main() -> ExitCode {
+    let env = Env::default()
+        .filter_or("LOG_LEVEL", "warning")
+        .write_style_or("LOG_STYLE", "always");
+    env_logger::init_from_env(env);
+
+    match execute(Cli::parse()) {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::from(commands::INVALID),
+        Err(e) => {
+            eprintln!("Error: {:#}", e);
+            ExitCode::from(commands::exit_code(&e))
+        }
+    }
 }
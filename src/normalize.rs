@@ -0,0 +1,277 @@
+use crate::{Diagnostic, RECORD_SIZE};
+
+/// Cleans up raw input so that it can be parsed as one 94 character record
+/// per line, reporting each change made as a warning:
+///
+/// - input that isn't valid UTF-8 is read as Latin-1, and a byte order mark
+///   is dropped
+/// - CRLF and lone CR line endings become LF
+/// - input with no line breaks at all is split into 94 character records
+/// - whitespace past the 94th character is trimmed, and lines of nothing
+///   but whitespace are emptied (they're skipped by the parser, so line
+///   numbers still match the input)
+/// - lines short of 94 characters are padded with spaces, as many editors
+///   strip trailing blanks
+///
+/// Non-ASCII characters are reported but kept; fields are read by character
+/// position, so they don't throw the columns off.
+pub fn normalize(input: &[u8]) -> (String, Vec<Diagnostic>) {
+    let mut diagnostics = Vec::new();
+    let mut content = decode(input, None, &mut diagnostics);
+
+    if let Some(rest) = content.strip_prefix('\u{feff}') {
+        content = rest.to_string();
+        diagnostics.push(Diagnostic::warning(Some(1), "removed byte order mark"));
+    }
+
+    if content.contains('\r') {
+        content = content.replace("\r\n", "\n").replace('\r', "\n");
+        diagnostics.push(Diagnostic::warning(
+            None,
+            "converted CRLF (or CR) line endings to LF",
+        ));
+    }
+
+    let trimmed = content.trim_end();
+    if !trimmed.contains('\n') && trimmed.chars().count() > RECORD_SIZE {
+        let chars: Vec<char> = trimmed.chars().collect();
+        let lines: Vec<String> = chars
+            .chunks(RECORD_SIZE)
+            .map(|c| c.iter().collect())
+            .collect();
+        diagnostics.push(Diagnostic::warning(
+            None,
+            format!(
+                "input has no line breaks, split it into {} records of {} characters",
+                lines.len(),
+                RECORD_SIZE
+            ),
+        ));
+        content = lines.join("\n");
+    }
+
+    let mut fixes = LineFixes::default();
+    let lines: Vec<String> = content
+        .split('\n')
+        .enumerate()
+        .map(|(i, line)| fixes.fix(line, i + 1, &mut diagnostics))
+        .collect();
+    fixes.report(&mut diagnostics);
+
+    // blank lines at the end aren't records, but a file that ends in a line
+    // break keeps it, so that a clean file comes through unchanged
+    let mut content = lines.join("\n");
+    let end = content.trim_end_matches('\n').len();
+    content.truncate((end + 1).min(content.len()));
+    (content, diagnostics)
+}
+
+/// UTF-8, or Latin-1 when it isn't valid UTF-8
+pub(crate) fn decode(
+    input: &[u8],
+    line: Option<usize>,
+    diagnostics: &mut Vec<Diagnostic>,
+) -> String {
+    match std::str::from_utf8(input) {
+        Ok(s) => s.to_string(),
+        Err(e) => {
+            diagnostics.push(Diagnostic::warning(
+                line,
+                format!(
+                    "input isn't valid UTF-8 (at byte {}), read it as Latin-1",
+                    e.valid_up_to()
+                ),
+            ));
+            input.iter().map(|&b| b as char).collect()
+        }
+    }
+}
+
+/// How many lines had a kind of fix, and the first of them.
+#[derive(Default)]
+struct Fixed {
+    first: Option<usize>,
+    count: usize,
+}
+
+impl Fixed {
+    fn add(&mut self, line: usize) {
+        self.first.get_or_insert(line);
+        self.count += 1;
+    }
+}
+
+/// The line by line part of [`normalize`], which
+/// [`NachaReader::lenient`](crate::NachaReader::lenient) also uses without
+/// ever having the whole input.
+#[derive(Default)]
+pub(crate) struct LineFixes {
+    trimmed: Fixed,
+    blank: Fixed,
+    padded: Fixed,
+}
+
+impl LineFixes {
+    /// Empties whitespace-only lines, trims whitespace past the 94th
+    /// character and pads short lines with spaces.
+    pub(crate) fn fix(
+        &mut self,
+        line: &str,
+        number: usize,
+        diagnostics: &mut Vec<Diagnostic>,
+    ) -> String {
+        if !line.is_ascii() {
+            diagnostics.push(Diagnostic::warning(
+                Some(number),
+                "line contains non-ASCII characters",
+            ));
+        }
+        let length = line.chars().count();
+        if line.trim().is_empty() {
+            if !line.is_empty() {
+                self.blank.add(number);
+            }
+            String::new()
+        } else if length > RECORD_SIZE && line.chars().skip(RECORD_SIZE).all(char::is_whitespace) {
+            self.trimmed.add(number);
+            line.chars().take(RECORD_SIZE).collect()
+        } else if length < RECORD_SIZE {
+            self.padded.add(number);
+            format!("{:<width$}", line, width = RECORD_SIZE)
+        } else {
+            line.to_string()
+        }
+    }
+
+    /// each kind of fix is reported once, at the first line affected
+    pub(crate) fn report(self, diagnostics: &mut Vec<Diagnostic>) {
+        for (fixed, what) in [
+            (self.trimmed, "trimmed whitespace past 94 characters"),
+            (self.blank, "emptied whitespace-only line"),
+            (
+                self.padded,
+                "padded short line with spaces to 94 characters",
+            ),
+        ] {
+            if let Some(first) = fixed.first {
+                diagnostics.push(Diagnostic::warning(
+                    Some(first),
+                    format!("{} ({} in all)", what, fixed.count),
+                ));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::NachaFile;
+
+    const SMALL: &str = include_str!("../examples/small.ach");
+
+    fn messages(diagnostics: &[Diagnostic]) -> Vec<String> {
+        diagnostics.iter().map(|d| d.to_string()).collect()
+    }
+
+    #[test]
+    fn leaves_a_clean_file_alone() {
+        for input in [SMALL.to_string(), format!("{}\n", SMALL)] {
+            let (content, diagnostics) = normalize(input.as_bytes());
+            assert_eq!(content, input);
+            assert_eq!(diagnostics, vec![]);
+        }
+        let (content, _) = normalize(format!("{}\n\n  \n", SMALL).as_bytes());
+        assert_eq!(content, format!("{}\n", SMALL));
+    }
+
+    #[test]
+    fn converts_crlf_and_cr_line_endings() {
+        for ending in ["\r\n", "\r"] {
+            let input = SMALL.replace('\n', ending);
+            let (content, diagnostics) = normalize(input.as_bytes());
+            assert_eq!(content, SMALL.trim_end_matches('\n'));
+            assert_eq!(
+                messages(&diagnostics),
+                vec!["warning: converted CRLF (or CR) line endings to LF"]
+            );
+        }
+    }
+
+    #[test]
+    fn pads_short_lines_and_trims_trailing_whitespace() {
+        let input: Vec<String> = SMALL
+            .lines()
+            .enumerate()
+            .map(|(i, line)| match i {
+                0 | 4 => line.trim_end().to_string(),
+                1 => format!("{}   ", line),
+                _ => line.to_string(),
+            })
+            .collect();
+        let (content, diagnostics) = normalize(input.join("\n").as_bytes());
+        assert_eq!(content, SMALL.trim_end_matches('\n'));
+        assert_eq!(
+            messages(&diagnostics),
+            vec![
+                "warning: line 2: trimmed whitespace past 94 characters (1 in all)",
+                "warning: line 1: padded short line with spaces to 94 characters (2 in all)",
+            ]
+        );
+    }
+
+    #[test]
+    fn splits_input_with_no_line_breaks() {
+        let input = SMALL.replace('\n', "");
+        let (content, diagnostics) = normalize(input.as_bytes());
+        assert_eq!(content, SMALL.trim_end_matches('\n'));
+        assert_eq!(
+            messages(&diagnostics),
+            vec!["warning: input has no line breaks, split it into 10 records of 94 characters"]
+        );
+    }
+
+    #[test]
+    fn keeps_filler_and_blank_lines_as_lines() {
+        let input = SMALL.replacen('\n', "\n  \n", 1);
+        let (content, diagnostics) = normalize(input.as_bytes());
+        let lines: Vec<&str> = content.lines().collect();
+        assert_eq!(lines.len(), 11);
+        assert_eq!(lines[1], "");
+        assert!(lines[6..].iter().all(|l| *l == "9".repeat(RECORD_SIZE)));
+        assert_eq!(
+            messages(&diagnostics),
+            vec!["warning: line 2: emptied whitespace-only line (1 in all)"]
+        );
+        // the blank line is skipped and the filler after the file control
+        // isn't read, so the file is the same as without them
+        let file = NachaFile::parse(&content).unwrap();
+        assert_eq!(file.batches.len(), 1);
+        assert_eq!(file.file_control.batch_count, 1);
+    }
+
+    #[test]
+    fn reads_latin_1_and_drops_a_byte_order_mark() {
+        let mut input = "\u{feff}".as_bytes().to_vec();
+        input.extend(SMALL.replace("Receiver", "Receivér").as_bytes());
+        let (content, diagnostics) = normalize(&input);
+        assert_eq!(content, SMALL.replace("Receiver", "Receivér").trim_end());
+        assert_eq!(
+            messages(&diagnostics),
+            vec![
+                "warning: line 1: removed byte order mark",
+                "warning: line 3: line contains non-ASCII characters",
+            ]
+        );
+
+        let mut latin = SMALL.as_bytes().to_vec();
+        latin[95 * 2 + 60] = 0xe9;
+        let (content, diagnostics) = normalize(&latin);
+        assert_eq!(content.lines().nth(2).unwrap().chars().nth(60), Some('é'));
+        assert_eq!(content.lines().nth(2).unwrap().chars().count(), RECORD_SIZE);
+        assert_eq!(
+            messages(&diagnostics)[0],
+            "warning: input isn't valid UTF-8 (at byte 250), read it as Latin-1"
+        );
+    }
+}
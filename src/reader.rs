@@ -0,0 +1,543 @@
+use crate::borrowed::RecordRef;
+use crate::layout::{EntryLayout, RECORD_TYPE_CODE};
+use crate::normalize::{decode, LineFixes};
+use crate::RECORD_SIZE;
+use crate::{
+    Addendum, BatchControl, BatchHeader, DetailEntry, Diagnostic, FileControl, FileHeader, Line,
+    ParseError, ParseErrorKind, ReadError,
+};
+use serde::Serialize;
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::io::{self, BufRead};
+
+/// A single parsed record, as yielded by [`NachaReader`].
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "snake_case")]
+pub enum Record {
+    FileHeader(FileHeader),
+    BatchHeader(BatchHeader),
+    Entry(DetailEntry),
+    Addendum(Addendum),
+    BatchControl(BatchControl),
+    FileControl(FileControl),
+}
+
+impl Record {
+    /// Parses one line by its record type code. Entries in IAT and ADV
+    /// batches are laid out differently, so the caller says which batch
+    /// it's in.
+    pub(crate) fn from_line(line: &Line, entries: EntryLayout) -> Result<Record, ParseError> {
+        RecordRef::from_line(line, entries).map(|r| r.into_owned())
+    }
+}
+
+/// Reads a NACHA file one record at a time, holding only the current line
+/// in memory, so that files too big to load whole can still be checked.
+///
+/// Each item is a record along with its 1-based line number. A record that
+/// can't be parsed yields an error and reading carries on with the next
+/// line; an I/O error ends the iteration. Like [`NachaFile::parse`](crate::NachaFile::parse),
+/// reading stops after the file control record.
+pub struct NachaReader<R> {
+    reader: R,
+    buffer: Vec<u8>,
+    line_number: usize,
+    in_batch: bool,
+    has_entry: bool,
+    entries: EntryLayout,
+    seen_header: bool,
+    done: bool,
+    /// set by [`NachaReader::lenient`]
+    lenient: Option<Lenient>,
+}
+
+/// What a lenient reader keeps besides the current line.
+#[derive(Default)]
+struct Lenient {
+    diagnostics: RefCell<Vec<Diagnostic>>,
+    fixes: LineFixes,
+    /// the rest of an over-long line, split into records
+    pending: VecDeque<String>,
+    /// the line number and record count of input with no line breaks,
+    /// which is read a record at a time
+    unbroken: Option<(usize, usize)>,
+    /// whether CRLF or CR line endings have been reported
+    carriage_returns: bool,
+}
+
+/// What ended a piece of input read by a lenient reader.
+#[derive(Debug, PartialEq, Eq)]
+enum LineEnd {
+    Lf,
+    /// a CR or CRLF
+    Cr,
+    Eof,
+    /// a record's worth of characters, with more text following it
+    Unbroken,
+}
+
+/// the number of characters `bytes` decodes to, not counting a UTF-8
+/// sequence cut short at the end
+fn char_count(bytes: &[u8]) -> usize {
+    match std::str::from_utf8(bytes) {
+        Ok(text) => text.chars().count(),
+        Err(e) if e.error_len().is_none() => char_count(&bytes[..e.valid_up_to()]),
+        // read as Latin-1, a character per byte
+        Err(_) => bytes.len(),
+    }
+}
+
+impl<R: BufRead> NachaReader<R> {
+    pub fn new(reader: R) -> NachaReader<R> {
+        NachaReader {
+            reader,
+            buffer: Vec::new(),
+            line_number: 0,
+            in_batch: false,
+            has_entry: false,
+            entries: EntryLayout::Standard,
+            seen_header: false,
+            done: false,
+            lenient: None,
+        }
+    }
+
+    /// Like [`NachaFile::parse_bytes_lenient`](crate::NachaFile::parse_bytes_lenient),
+    /// a line at a time: each line is [`normalize`](crate::normalize)d, and
+    /// bad fields are kept as `Unknown` codes or left at their defaults, so
+    /// a record is only skipped when it can't be read at all. What was fixed
+    /// or left out is collected for [`NachaReader::take_diagnostics`].
+    pub fn lenient(reader: R) -> NachaReader<R> {
+        NachaReader {
+            lenient: Some(Lenient::default()),
+            ..NachaReader::new(reader)
+        }
+    }
+
+    /// The warnings and field errors collected so far by a lenient reader;
+    /// fixes made to many lines are only reported once reading is done.
+    pub fn take_diagnostics(&mut self) -> Vec<Diagnostic> {
+        self.lenient
+            .as_mut()
+            .map(|l| l.diagnostics.take())
+            .unwrap_or_default()
+    }
+
+    /// keeps track of the current batch and entry, so that records with
+    /// nothing to attach to are reported the same way `NachaFile` does
+    fn record(&mut self, line: &Line) -> Result<Record, ParseError> {
+        let record = Record::from_line(line, self.entries)?;
+        let orphan = || line.error(ParseErrorKind::OrphanRecord, RECORD_TYPE_CODE);
+        match &record {
+            Record::FileHeader(_) => self.seen_header = true,
+            Record::BatchHeader(header) => {
+                self.in_batch = true;
+                self.has_entry = false;
+                self.entries = header.entry_layout();
+            }
+            Record::Entry(_) | Record::BatchControl(_) if !self.in_batch => return Err(orphan()),
+            Record::Entry(_) => self.has_entry = true,
+            Record::Addendum(_) if !self.has_entry => return Err(orphan()),
+            Record::FileControl(_) => self.done = true,
+            _ => {}
+        }
+        Ok(record)
+    }
+
+    /// The next line, without its line ending. A lenient reader reads
+    /// anything that isn't UTF-8 as Latin-1, takes a lone CR as a line
+    /// break and splits a line with no line breaks into records.
+    fn next_line(&mut self) -> io::Result<Option<String>> {
+        let Some(lenient) = &mut self.lenient else {
+            self.buffer.clear();
+            if self.reader.read_until(b'\n', &mut self.buffer)? == 0 {
+                return Ok(None);
+            }
+            self.line_number += 1;
+            let mut bytes = self.buffer.as_slice();
+            while let [rest @ .., b'\r' | b'\n'] = bytes {
+                bytes = rest;
+            }
+            let text = std::str::from_utf8(bytes)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+            return Ok(Some(text.to_string()));
+        };
+        if let Some(line) = lenient.pending.pop_front() {
+            self.line_number += 1;
+            let diagnostics = lenient.diagnostics.get_mut();
+            return Ok(Some(lenient.fixes.fix(
+                &line,
+                self.line_number,
+                diagnostics,
+            )));
+        }
+        let end = read_piece(&mut self.reader, &mut self.buffer)?;
+        let diagnostics = lenient.diagnostics.get_mut();
+        if end == LineEnd::Eof && self.buffer.is_empty() {
+            return Ok(None);
+        }
+        self.line_number += 1;
+        let mut text = decode(&self.buffer, Some(self.line_number), diagnostics);
+        if self.line_number == 1 {
+            if let Some(rest) = text.strip_prefix('\u{feff}') {
+                text = rest.to_string();
+                diagnostics.push(Diagnostic::warning(Some(1), "removed byte order mark"));
+            }
+        }
+        if end == LineEnd::Cr && !lenient.carriage_returns {
+            lenient.carriage_returns = true;
+            diagnostics.push(Diagnostic::warning(
+                Some(self.line_number),
+                "converted CRLF (or CR) line endings to LF",
+            ));
+        }
+        if end == LineEnd::Unbroken {
+            lenient.unbroken.get_or_insert((self.line_number, 0)).1 += 1;
+        } else if let Some((line, records)) = lenient.unbroken.take() {
+            diagnostics.push(split_warning(line, records + 1));
+        }
+        if text.chars().skip(RECORD_SIZE).any(|c| !c.is_whitespace()) {
+            let chars: Vec<char> = text.chars().collect();
+            lenient.pending = chars
+                .chunks(RECORD_SIZE)
+                .map(|c| c.iter().collect())
+                .collect();
+            diagnostics.push(split_warning(self.line_number, lenient.pending.len()));
+            self.line_number -= 1;
+            return self.next_line();
+        }
+        Ok(Some(lenient.fixes.fix(
+            &text,
+            self.line_number,
+            diagnostics,
+        )))
+    }
+
+    /// reports what a lenient reader fixed or didn't find, once it's done
+    fn finish(&mut self) {
+        let Some(lenient) = self.lenient.as_mut() else {
+            return;
+        };
+        let diagnostics = lenient.diagnostics.get_mut();
+        if let Some((line, records)) = lenient.unbroken.take() {
+            diagnostics.push(split_warning(line, records));
+        }
+        std::mem::take(&mut lenient.fixes).report(diagnostics);
+        if !self.seen_header {
+            diagnostics.push(Diagnostic::warning(None, "no file header record found"));
+        }
+        if !self.done {
+            diagnostics.push(Diagnostic::warning(
+                Some(self.line_number),
+                "no file control record found",
+            ));
+        }
+    }
+}
+
+/// Reads the next piece of input into `buffer` for a lenient reader:
+/// up to a line break, which isn't kept, or a record's worth of
+/// characters when more text follows with no break, so that input with
+/// no line breaks isn't read whole.
+fn read_piece(reader: &mut impl BufRead, buffer: &mut Vec<u8>) -> io::Result<LineEnd> {
+    buffer.clear();
+    // past a record's worth, but only whitespace so far
+    let mut trailing = false;
+    loop {
+        let available = reader.fill_buf()?;
+        let Some(&next) = available.first() else {
+            return Ok(LineEnd::Eof);
+        };
+        let wanted = RECORD_SIZE.saturating_sub(char_count(buffer));
+        if wanted == 0 && !trailing {
+            match next {
+                b'\n' | b'\r' => {}
+                // trailing whitespace, read to the line break as usual
+                b' ' | b'\t' => trailing = true,
+                _ => return Ok(LineEnd::Unbroken),
+            }
+        }
+        let limit = match wanted {
+            0 => available.len(),
+            wanted => available.len().min(wanted),
+        };
+        match available[..limit]
+            .iter()
+            .position(|b| matches!(b, b'\n' | b'\r'))
+        {
+            Some(i) => {
+                buffer.extend_from_slice(&available[..i]);
+                let end = available[i];
+                reader.consume(i + 1);
+                if end == b'\n' {
+                    return Ok(LineEnd::Lf);
+                }
+                if reader.fill_buf()?.first() == Some(&b'\n') {
+                    reader.consume(1);
+                }
+                return Ok(LineEnd::Cr);
+            }
+            None => {
+                buffer.extend_from_slice(&available[..limit]);
+                reader.consume(limit);
+            }
+        }
+    }
+}
+
+fn split_warning(line: usize, records: usize) -> Diagnostic {
+    Diagnostic::warning(
+        Some(line),
+        format!(
+            "line has no line breaks, split it into {} records of {} characters",
+            records, RECORD_SIZE
+        ),
+    )
+}
+
+impl<R: BufRead> Iterator for NachaReader<R> {
+    type Item = Result<(usize, Record), ReadError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.done {
+            let text = match self.next_line() {
+                Ok(Some(text)) => text,
+                Ok(None) => {
+                    self.finish();
+                    self.done = true;
+                    return None;
+                }
+                Err(e) => {
+                    self.done = true;
+                    return Some(Err(e.into()));
+                }
+            };
+            if text.is_empty() {
+                continue;
+            }
+            // the diagnostics are taken out of `self` so the line can
+            // borrow them while the batch state is updated
+            let lenient = self.lenient.take();
+            let sink = lenient.as_ref().map(|l| &l.diagnostics);
+            let result = self
+                .record(&Line::lenient(&text, self.line_number, sink))
+                .map(|record| (self.line_number, record))
+                .map_err(ReadError::from);
+            self.lenient = lenient;
+            if self.done {
+                self.finish();
+            }
+            return Some(result);
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+    use std::io::{BufReader, Cursor, Read};
+    use std::rc::Rc;
+
+    const SMALL: &str = include_str!("../examples/small.ach");
+
+    /// input that counts how much of it has been read
+    struct Counted {
+        input: Cursor<Vec<u8>>,
+        read: Rc<Cell<usize>>,
+    }
+
+    impl Read for Counted {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let n = self.input.read(buf)?;
+            self.read.set(self.read.get() + n);
+            Ok(n)
+        }
+    }
+
+    fn kind(record: &Record) -> &'static str {
+        match record {
+            Record::FileHeader(_) => "file_header",
+            Record::BatchHeader(_) => "batch_header",
+            Record::Entry(_) => "entry",
+            Record::Addendum(_) => "addendum",
+            Record::BatchControl(_) => "batch_control",
+            Record::FileControl(_) => "file_control",
+        }
+    }
+
+    fn record_types(records: &[(usize, Record)]) -> Vec<(usize, &'static str)> {
+        records
+            .iter()
+            .map(|(line, record)| (*line, kind(record)))
+            .collect()
+    }
+
+    fn messages(diagnostics: Vec<Diagnostic>) -> Vec<String> {
+        diagnostics.iter().map(|d| d.to_string()).collect()
+    }
+
+    #[test]
+    fn reads_input_with_no_line_breaks_a_record_at_a_time() {
+        let read = Rc::new(Cell::new(0));
+        let input = Counted {
+            input: Cursor::new(SMALL.replace('\n', "").into_bytes()),
+            read: read.clone(),
+        };
+        let mut reader = NachaReader::lenient(BufReader::with_capacity(64, input));
+        let (line, header) = reader.next().unwrap().unwrap();
+        assert_eq!(line, 1);
+        assert!(matches!(header, Record::FileHeader(_)));
+        assert!(read.get() < 2 * RECORD_SIZE);
+
+        let records: Vec<_> = reader.by_ref().collect::<Result<_, _>>().unwrap();
+        assert_eq!(
+            record_types(&records),
+            vec![
+                (2, "batch_header"),
+                (3, "entry"),
+                (4, "batch_control"),
+                (5, "file_control")
+            ]
+        );
+        // the filler after the file control isn't read
+        assert!(read.get() < SMALL.len());
+        assert_eq!(
+            messages(reader.take_diagnostics()),
+            vec!["warning: line 1: line has no line breaks, split it into 5 records of 94 characters"]
+        );
+    }
+
+    #[test]
+    fn splits_long_lines_between_lone_carriage_returns() {
+        let mut lines: Vec<String> = SMALL.lines().map(str::to_string).collect();
+        let entry = lines.remove(2);
+        lines[1].push_str(&entry);
+        let input = lines.join("\r");
+        let mut reader = NachaReader::lenient(input.as_bytes());
+        let records: Vec<_> = reader.by_ref().collect::<Result<_, _>>().unwrap();
+        assert_eq!(
+            record_types(&records),
+            vec![
+                (1, "file_header"),
+                (2, "batch_header"),
+                (3, "entry"),
+                (4, "batch_control"),
+                (5, "file_control")
+            ]
+        );
+        assert_eq!(
+            messages(reader.take_diagnostics()),
+            vec![
+                "warning: line 1: converted CRLF (or CR) line endings to LF",
+                "warning: line 2: line has no line breaks, split it into 2 records of 94 characters",
+            ]
+        );
+    }
+
+    fn read(reader: NachaReader<impl BufRead>) -> Vec<(usize, Record)> {
+        reader.collect::<Result<_, _>>().unwrap()
+    }
+
+    #[test]
+    fn numbers_records_by_input_line() {
+        let content = SMALL.replacen('\n', "\n\n", 1);
+        let records = read(NachaReader::new(content.as_bytes()));
+        assert_eq!(
+            record_types(&records),
+            vec![
+                (1, "file_header"),
+                (3, "batch_header"),
+                (4, "entry"),
+                (5, "batch_control"),
+                (6, "file_control")
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_orphan_records_and_carries_on() {
+        let mut lines: Vec<&str> = SMALL.lines().collect();
+        lines.remove(1);
+        let content = lines.join("\n");
+        let results: Vec<_> = NachaReader::new(content.as_bytes())
+            .map(|result| match result {
+                Ok((line, record)) => Ok((line, kind(&record))),
+                Err(ReadError::Parse(e)) => Err((e.kind, e.line, e.record_type)),
+                Err(e) => panic!("{}", e),
+            })
+            .collect();
+        assert_eq!(
+            results,
+            vec![
+                Ok((1, "file_header")),
+                Err((ParseErrorKind::OrphanRecord, 2, "6".to_string())),
+                Err((ParseErrorKind::OrphanRecord, 3, "8".to_string())),
+                Ok((4, "file_control")),
+            ]
+        );
+    }
+
+    #[test]
+    fn stops_after_the_file_control() {
+        let content = format!("{}\nnot a record", SMALL);
+        let records = read(NachaReader::new(content.as_bytes()));
+        assert_eq!(records.len(), 5);
+        assert!(matches!(records[4], (5, Record::FileControl(_))));
+    }
+
+    #[test]
+    fn reads_iat_entries_in_their_own_layout() {
+        let content = include_str!("../examples/full.ach");
+        let file = crate::NachaFile::parse(content).unwrap();
+        let expected = file.batches.iter().flat_map(|b| &b.detail_entries);
+        let entries: Vec<DetailEntry> = read(NachaReader::new(content.as_bytes()))
+            .into_iter()
+            .filter_map(|(_, record)| match record {
+                Record::Entry(entry) => Some(entry),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(entries.len(), 2);
+        for (entry, expected) in entries.iter().zip(expected) {
+            assert!(entry.iat.is_some());
+            assert_eq!(entry.iat, expected.iat);
+            assert_eq!(entry.dfi_account_number, expected.dfi_account_number);
+            // addenda come as records of their own
+            assert_eq!(entry.addenda.len(), 0);
+        }
+    }
+
+    #[test]
+    fn lenient_reports_a_missing_header_and_control() {
+        let lines: Vec<&str> = SMALL.lines().collect();
+        let content = lines[1..4].join("\n");
+        let mut reader = NachaReader::lenient(content.as_bytes());
+        let records: Vec<_> = reader.by_ref().collect::<Result<_, _>>().unwrap();
+        assert_eq!(records.len(), 3);
+        assert_eq!(
+            messages(reader.take_diagnostics()),
+            vec![
+                "warning: no file header record found",
+                "warning: line 3: no file control record found",
+            ]
+        );
+    }
+
+    #[test]
+    fn lenient_reads_crlf_line_endings() {
+        let content = SMALL.replace('\n', "\r\n");
+        let mut reader = NachaReader::lenient(content.as_bytes());
+        let records: Vec<_> = reader.by_ref().collect::<Result<_, _>>().unwrap();
+        assert_eq!(
+            record_types(&records),
+            record_types(&read(NachaReader::new(SMALL.as_bytes())))
+        );
+        assert_eq!(
+            messages(reader.take_diagnostics()),
+            vec!["warning: line 1: converted CRLF (or CR) line endings to LF"]
+        );
+    }
+}
@@ -0,0 +1,186 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+const WEIGHTS: [u32; 8] = [3, 7, 1, 3, 7, 1, 3, 7];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RoutingNumberError {
+    /// not made up of the right number of digits
+    Malformed(String),
+    /// the ninth digit doesn't match the ABA mod-10 check of the first eight
+    CheckDigit { expected: u8, actual: u8 },
+}
+
+impl fmt::Display for RoutingNumberError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RoutingNumberError::Malformed(s) => write!(f, "malformed routing number {:?}", s),
+            RoutingNumberError::CheckDigit { expected, actual } => write!(
+                f,
+                "routing number check digit should be {} but is {}",
+                expected, actual
+            ),
+        }
+    }
+}
+
+impl std::error::Error for RoutingNumberError {}
+
+/// A 9 digit ABA routing number whose check digit has been verified.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct RoutingNumber(String);
+
+impl RoutingNumber {
+    /// Parses a 9 digit routing number, check digit included.
+    pub fn new(s: &str) -> Result<RoutingNumber, RoutingNumberError> {
+        if s.len() != 9 || !s.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(RoutingNumberError::Malformed(s.to_string()));
+        }
+        let expected = RoutingNumber::check_digit(&s[..8])?;
+        let actual = s.as_bytes()[8] - b'0';
+        if expected != actual {
+            return Err(RoutingNumberError::CheckDigit { expected, actual });
+        }
+        Ok(RoutingNumber(s.to_string()))
+    }
+
+    /// Builds a routing number from the 8 digit DFI identification and the
+    /// separate check digit, as they appear in an entry detail record.
+    pub fn from_parts(
+        dfi_id: &str,
+        check_digit: &str,
+    ) -> Result<RoutingNumber, RoutingNumberError> {
+        RoutingNumber::new(&format!("{}{}", dfi_id, check_digit))
+    }
+
+    /// Builds a routing number from an 8 digit DFI identification, working
+    /// out the check digit, as for the ODFI in a batch header.
+    pub fn from_dfi_id(dfi_id: &str) -> Result<RoutingNumber, RoutingNumberError> {
+        let check_digit = RoutingNumber::check_digit(dfi_id)?;
+        Ok(RoutingNumber(format!("{}{}", dfi_id, check_digit)))
+    }
+
+    /// Reads the 10 character immediate destination or origin of a file
+    /// header, which holds a routing number preceded by a space (or, by a
+    /// common convention, a zero).
+    pub fn from_immediate(field: &str) -> Result<RoutingNumber, RoutingNumberError> {
+        let trimmed = field.trim_start();
+        match trimmed.strip_prefix('0') {
+            Some(rest) if trimmed.len() == 10 => RoutingNumber::new(rest),
+            _ => RoutingNumber::new(trimmed),
+        }
+    }
+
+    /// The ABA mod-10 check digit for an 8 digit DFI identification, using
+    /// weights of 3, 7 and 1.
+    pub fn check_digit(dfi_id: &str) -> Result<u8, RoutingNumberError> {
+        if dfi_id.len() != 8 || !dfi_id.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(RoutingNumberError::Malformed(dfi_id.to_string()));
+        }
+        let sum: u32 = dfi_id
+            .bytes()
+            .zip(WEIGHTS)
+            .map(|(b, w)| (b - b'0') as u32 * w)
+            .sum();
+        Ok(((10 - sum % 10) % 10) as u8)
+    }
+
+    /// the first 8 digits
+    pub fn dfi_id(&self) -> &str {
+        &self.0[..8]
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for RoutingNumber {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl TryFrom<String> for RoutingNumber {
+    type Error = RoutingNumberError;
+
+    fn try_from(s: String) -> Result<RoutingNumber, RoutingNumberError> {
+        RoutingNumber::new(&s)
+    }
+}
+
+impl From<RoutingNumber> for String {
+    fn from(r: RoutingNumber) -> String {
+        r.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accepts_valid_routing_numbers() {
+        for s in ["121042882", "231380104", "021000021", "011000015"] {
+            assert_eq!(RoutingNumber::new(s).unwrap().as_str(), s);
+        }
+    }
+
+    #[test]
+    fn rejects_a_wrong_check_digit() {
+        assert_eq!(
+            RoutingNumber::new("121042883"),
+            Err(RoutingNumberError::CheckDigit {
+                expected: 2,
+                actual: 3
+            })
+        );
+        assert_eq!(
+            RoutingNumber::from_parts("23138010", "0"),
+            Err(RoutingNumberError::CheckDigit {
+                expected: 4,
+                actual: 0
+            })
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_routing_numbers() {
+        for s in [
+            "",
+            "12104288",
+            "1210428820",
+            "12104288a",
+            "12104288é",
+            " 21042882",
+        ] {
+            assert_eq!(
+                RoutingNumber::new(s),
+                Err(RoutingNumberError::Malformed(s.to_string()))
+            );
+        }
+    }
+
+    #[test]
+    fn check_digit_of_a_dfi_id() {
+        assert_eq!(RoutingNumber::check_digit("12104288"), Ok(2));
+        assert_eq!(RoutingNumber::check_digit("02100002"), Ok(1));
+        assert_eq!(
+            RoutingNumber::from_dfi_id("23138010").unwrap().as_str(),
+            "231380104"
+        );
+        assert!(RoutingNumber::check_digit("1210428").is_err());
+    }
+
+    #[test]
+    fn immediate_fields_may_start_with_a_space_or_zero() {
+        for field in [" 121042882", "0121042882"] {
+            assert_eq!(
+                RoutingNumber::from_immediate(field).unwrap().as_str(),
+                "121042882"
+            );
+        }
+        assert!(RoutingNumber::from_immediate(" 121042883").is_err());
+    }
+}
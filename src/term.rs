@@ -1,13 +1,10 @@
-use crate::ui;
-use crate::{
-    app::App,
-    lib::{DetailEntry, NachaFile},
-};
+use crate::{app::App, ui};
 use crossterm::{
     event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode},
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
+use nacha::{DetailEntry, NachaFile};
 use std::{
     error::Error,
     io,
@@ -18,27 +15,54 @@ use tui::{
     Terminal,
 };
 
+#[derive(Clone)]
 pub struct DetailEntryWithCounter {
     pub entry: DetailEntry,
     pub counter: u32,
+    /// index of the entry's batch in the file
+    pub batch: usize,
 }
 
 impl DetailEntryWithCounter {
-    pub fn new(entry: DetailEntry, counter: u32) -> DetailEntryWithCounter {
-        DetailEntryWithCounter { entry, counter }
+    pub fn new(entry: DetailEntry, counter: u32, batch: usize) -> DetailEntryWithCounter {
+        DetailEntryWithCounter {
+            entry,
+            counter,
+            batch,
+        }
     }
 }
 fn get_entries(nacha_file: &NachaFile) -> Vec<DetailEntryWithCounter> {
     let mut entries = Vec::new();
     let mut count: u32 = 1;
-    for batch in &nacha_file.batches {
+    for (i, batch) in nacha_file.batches.iter().enumerate() {
         for entry in &batch.detail_entries {
-            entries.push(DetailEntryWithCounter::new(entry.clone(), count));
+            entries.push(DetailEntryWithCounter::new(entry.clone(), count, i));
             count += 1;
         }
     }
-    return entries;
+    entries
+}
+/// The lines of the raw file holding each entry and its addenda, as 1-based
+/// line numbers and text, in the same order as [`get_entries`].
+fn get_raw_records(nacha_file: &NachaFile) -> Vec<Vec<(usize, String)>> {
+    let mut records: Vec<Vec<(usize, String)>> = Vec::new();
+    for (i, line) in nacha_file.raw().lines().enumerate() {
+        match line.get(..1) {
+            Some("6") => records.push(vec![(i + 1, line.to_string())]),
+            Some("7") => {
+                if let Some(entry) = records.last_mut() {
+                    entry.push((i + 1, line.to_string()))
+                }
+            }
+            // parsing stops at the file control, the rest is padding
+            Some("9") => break,
+            _ => {}
+        }
+    }
+    records
 }
+
 pub fn run(tick_rate: Duration, nacha_file: &mut NachaFile) -> Result<(), Box<dyn Error>> {
     // setup terminal
     enable_raw_mode()?;
@@ -48,7 +72,8 @@ pub fn run(tick_rate: Duration, nacha_file: &mut NachaFile) -> Result<(), Box<dy
     let mut terminal = Terminal::new(backend)?;
 
     // run the terminal
-    let app = App::new(nacha_file, get_entries(nacha_file));
+    let raw_records = get_raw_records(nacha_file);
+    let app = App::new(nacha_file, get_entries(nacha_file), raw_records);
     let res = run_app(&mut terminal, app, tick_rate);
 
     // restore terminal
@@ -83,6 +108,10 @@ fn run_app<B: Backend>(
             if let Event::Key(key) = event::read()? {
                 match key.code {
                     KeyCode::Char(c) => app.on_key(c),
+                    KeyCode::Tab => app.on_tab(),
+                    KeyCode::Enter => app.on_enter(),
+                    KeyCode::Backspace => app.on_backspace(),
+                    KeyCode::Esc => app.on_esc(),
                     KeyCode::Left => app.on_left(),
                     KeyCode::Up => app.on_up(),
                     KeyCode::Right => app.on_right(),
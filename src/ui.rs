@@ -1,13 +1,13 @@
-use crate::app::App;
-use crate::lib::{Addendum, Currency};
+use crate::app::{App, Focus, InputMode};
 use crate::term::DetailEntryWithCounter;
+use nacha::{layout, Addendum, Batch, BatchHeader, Currency, EntryLayout, WriteError};
 use std::cmp::Ordering;
 use tui::{
     backend::Backend,
     layout::{Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
     text::{Span, Spans},
-    widgets::{Block, Borders, Cell, Clear, Paragraph, Row, Table, Wrap},
+    widgets::{Block, Borders, Cell, Clear, List, ListItem, Paragraph, Row, Table, Wrap},
     Frame,
 };
 
@@ -17,14 +17,28 @@ pub fn draw<B: Backend>(f: &mut Frame<B>, app: &mut App) {
             [
                 Constraint::Length(7),
                 Constraint::Min(2),
-                Constraint::Length(3),
+                Constraint::Length(4),
             ]
             .as_ref(),
         )
         .split(f.size());
     draw_file_metadata(f, chunks[0], app);
-    draw_file_contents(f, chunks[1], app);
-    draw_shortcut_help(f, chunks[2]);
+    let contents = Layout::default()
+        .constraints([Constraint::Percentage(30), Constraint::Percentage(70)].as_ref())
+        .direction(Direction::Horizontal)
+        .split(chunks[1]);
+    draw_batch_tree(f, contents[0], app);
+    draw_file_contents(f, contents[1], app);
+    match (app.input_mode, &app.message) {
+        (InputMode::Normal, None) => draw_shortcut_help(f, chunks[2]),
+        _ => draw_input(f, chunks[2], app),
+    }
+    if app.show_detail {
+        draw_entry_detail(f, f.size(), app);
+    }
+    if app.show_raw {
+        draw_raw_records(f, f.size(), app);
+    }
 }
 
 fn draw_shortcut_help<B>(f: &mut Frame<B>, area: Rect)
@@ -44,8 +58,29 @@ where
         Span::styled("h/<left>", Style::default().fg(Color::Cyan)),
         Span::raw(": jump prev"),
         Span::raw("  "),
+        Span::styled("tab", Style::default().fg(Color::Cyan)),
+        Span::raw(": batches/entries"),
+        Span::raw("  "),
         Span::styled("o", Style::default().fg(Color::Cyan)),
-        Span::raw(": toggle addenda"),
+        Span::raw(": toggle addenda/batch summary"),
+        Span::raw("  "),
+        Span::styled("/", Style::default().fg(Color::Cyan)),
+        Span::raw(": search"),
+        Span::raw("  "),
+        Span::styled("n/N", Style::default().fg(Color::Cyan)),
+        Span::raw(": next/prev match"),
+        Span::raw("  "),
+        Span::styled("f", Style::default().fg(Color::Cyan)),
+        Span::raw(": filter"),
+        Span::raw("  "),
+        Span::styled("s/r", Style::default().fg(Color::Cyan)),
+        Span::raw(": sort/reverse"),
+        Span::raw("  "),
+        Span::styled("enter", Style::default().fg(Color::Cyan)),
+        Span::raw(": entry detail"),
+        Span::raw("  "),
+        Span::styled("x", Style::default().fg(Color::Cyan)),
+        Span::raw(": raw record"),
         Span::raw("  "),
         Span::styled("q", Style::default().fg(Color::Cyan)),
         Span::raw(": quit"),
@@ -60,6 +95,243 @@ where
     f.render_widget(paragraph, area);
 }
 
+/// Every field of the selected entry, its batch header and its addenda, with
+/// the columns each one takes up in the record.
+fn draw_entry_detail<B>(f: &mut Frame<B>, area: Rect, app: &App)
+where
+    B: Backend,
+{
+    let Some(e) = app.selected_entry() else {
+        return;
+    };
+    let header = &app.nacha_file.batches[e.batch].batch_header;
+    let entries = header.entry_layout();
+    let mut text = vec![Spans::from(vec![
+        Span::styled("enter/esc", Style::default().fg(Color::Cyan)),
+        Span::raw(": back  "),
+        Span::styled("j/k", Style::default().fg(Color::Cyan)),
+        Span::raw(": scroll  "),
+        Span::styled("x", Style::default().fg(Color::Cyan)),
+        Span::raw(": raw record"),
+    ])];
+    let mut records = vec![
+        ("Entry Detail".to_string(), e.entry.to_record()),
+        ("Batch Header".to_string(), header.to_record()),
+    ];
+    for (i, addendum) in e.entry.addenda.iter().enumerate() {
+        let title = format!("Addendum {} (type {})", i + 1, addendum.addenda_type_code);
+        records.push((title, addendum.to_record()));
+    }
+    // one width for the names of every record, so the values line up
+    let width = records
+        .iter()
+        .filter_map(|(_, record)| layout::for_record(record.as_ref().ok()?, entries))
+        .flatten()
+        .map(|field| field.name.len())
+        .max()
+        .unwrap_or(0);
+    for (title, record) in &records {
+        text.extend(record_lines(title, record, entries, width));
+    }
+
+    let block = Block::default().borders(Borders::ALL).title(Span::styled(
+        format!("Entry {}: {}", e.counter, e.entry.receiver_name()),
+        Style::default()
+            .fg(Color::Magenta)
+            .add_modifier(Modifier::BOLD),
+    ));
+    let paragraph = Paragraph::new(text)
+        .block(block)
+        .scroll((app.detail_scroll, 0));
+    f.render_widget(Clear, area);
+    f.render_widget(paragraph, area);
+}
+
+/// The selected entry and its addenda as they are in the file, under a
+/// column ruler, with the field under the cursor highlighted and described.
+fn draw_raw_records<B>(f: &mut Frame<B>, area: Rect, app: &App)
+where
+    B: Backend,
+{
+    let Some(e) = app.selected_entry() else {
+        return;
+    };
+    let records = app.selected_records();
+    let (line_number, record) = records.get(app.raw_record).cloned().unwrap_or_default();
+    let field = app.record_fields(&record).get(app.raw_field).copied();
+    let highlight = Style::default().fg(Color::Black).bg(Color::Yellow);
+    let dim = Style::default().fg(Color::DarkGray);
+
+    let mut text = vec![
+        Spans::from(vec![
+            Span::styled("h/l", Style::default().fg(Color::Cyan)),
+            Span::raw(": field  "),
+            Span::styled("j/k", Style::default().fg(Color::Cyan)),
+            Span::raw(": line  "),
+            Span::styled("x/esc", Style::default().fg(Color::Cyan)),
+            Span::raw(": back"),
+        ]),
+        Spans::from(""),
+    ];
+    if let Some(field) = field {
+        let value = record.chars().skip(field.start - 1).take(field.width());
+        text.push(Spans::from(vec![
+            Span::styled(
+                format!("{} ", field.name),
+                Style::default()
+                    .fg(Color::Cyan)
+                    .add_modifier(Modifier::BOLD),
+            ),
+            Span::raw(format!(
+                "columns {}-{}, {}, {}: ",
+                field.start,
+                field.end,
+                field.kind,
+                if field.required {
+                    "required"
+                } else {
+                    "optional"
+                }
+            )),
+            Span::styled(format!("\"{}\"", value.collect::<String>()), highlight),
+        ]));
+        text.push(Spans::from(Span::styled(field.description, dim)));
+    }
+    text.push(Spans::from(""));
+
+    // tens above units, the columns of the field picked out in both
+    let ruler = |digit: fn(usize) -> char| {
+        let mut spans = vec![Span::raw(" ".repeat(8))];
+        spans.extend((1..=nacha::RECORD_SIZE).map(|column| {
+            let style = match field {
+                Some(f) if (f.start..=f.end).contains(&column) => {
+                    Style::default().fg(Color::Yellow)
+                }
+                _ => dim,
+            };
+            Span::styled(digit(column).to_string(), style)
+        }));
+        Spans::from(spans)
+    };
+    text.push(ruler(|column| match column % 10 {
+        0 => char::from_digit((column / 10 % 10) as u32, 10).unwrap_or(' '),
+        _ => ' ',
+    }));
+    text.push(ruler(|column| {
+        char::from_digit((column % 10) as u32, 10).unwrap_or(' ')
+    }));
+
+    for (i, (number, line)) in records.iter().enumerate() {
+        let padded = format!("{:<width$}", line, width = nacha::RECORD_SIZE);
+        let number = number.map_or("-".to_string(), |n| n.to_string());
+        let mut spans = vec![Span::styled(format!("{:>6}  ", number), dim)];
+        match field {
+            Some(f) if i == app.raw_record => {
+                let padded: Vec<char> = padded.chars().collect();
+                let end = f.end.min(padded.len());
+                let start = (f.start - 1).min(end);
+                spans.push(Span::raw(padded[..start].iter().collect::<String>()));
+                spans.push(Span::styled(
+                    padded[start..end].iter().collect::<String>(),
+                    highlight,
+                ));
+                spans.push(Span::raw(padded[end..].iter().collect::<String>()));
+            }
+            _ => spans.push(Span::raw(line.clone())),
+        }
+        text.push(Spans::from(spans));
+    }
+
+    let title = match line_number {
+        Some(n) => format!("Entry {}: line {}", e.counter, n),
+        None => format!("Entry {}: not found in the file, as written", e.counter),
+    };
+    let block = Block::default().borders(Borders::ALL).title(Span::styled(
+        title,
+        Style::default()
+            .fg(Color::Magenta)
+            .add_modifier(Modifier::BOLD),
+    ));
+    f.render_widget(Clear, area);
+    f.render_widget(Paragraph::new(text).block(block), area);
+}
+
+/// a heading, then a line per field of the record giving its columns, name,
+/// value and description, or why the record can't be written
+fn record_lines(
+    title: &str,
+    record: &Result<String, WriteError>,
+    entries: EntryLayout,
+    width: usize,
+) -> Vec<Spans<'static>> {
+    let mut lines = vec![
+        Spans::from(""),
+        Spans::from(Span::styled(
+            title.to_string(),
+            Style::default()
+                .fg(Color::Magenta)
+                .add_modifier(Modifier::BOLD),
+        )),
+    ];
+    let record = match record {
+        Ok(record) => record,
+        Err(e) => {
+            lines.push(Spans::from(Span::styled(
+                e.to_string(),
+                Style::default().fg(Color::Red),
+            )));
+            return lines;
+        }
+    };
+    for field in layout::for_record(record, entries).unwrap_or_default() {
+        let value = field.text(record);
+        lines.push(Spans::from(vec![
+            Span::styled(
+                format!("{:>4}-{:<4}", field.start, field.end),
+                Style::default().fg(Color::DarkGray),
+            ),
+            Span::styled(
+                format!("{:<width$}  ", field.name, width = width),
+                Style::default().fg(Color::Cyan),
+            ),
+            Span::raw(format!("{:<36}  ", value.trim_end())),
+            Span::styled(
+                field.description.to_string(),
+                Style::default().fg(Color::DarkGray),
+            ),
+        ]));
+    }
+    lines
+}
+
+/// the search or filter being typed, or why the last filter was rejected
+fn draw_input<B>(f: &mut Frame<B>, area: Rect, app: &App)
+where
+    B: Backend,
+{
+    let (title, text) = match (&app.message, app.input_mode) {
+        (Some(message), _) => (
+            "Invalid Filter",
+            Span::styled(message.clone(), Style::default().fg(Color::Red)),
+        ),
+        (None, InputMode::Filter) => (
+            "Filter (e.g. amount>10000 txn=27 sec=WEB), enter to apply, esc to cancel",
+            Span::raw(format!("{}_", app.input)),
+        ),
+        (None, _) => (
+            "Search (name, account, trace, id or amount), enter to keep, esc to cancel",
+            Span::raw(format!("/{}_", app.input)),
+        ),
+    };
+    let block = Block::default().borders(Borders::ALL).title(Span::styled(
+        title,
+        Style::default()
+            .fg(Color::Magenta)
+            .add_modifier(Modifier::BOLD),
+    ));
+    f.render_widget(Paragraph::new(Spans::from(text)).block(block), area);
+}
+
 fn draw_file_metadata<B>(f: &mut Frame<B>, area: Rect, app: &mut App)
 where
     B: Backend,
@@ -80,13 +352,13 @@ where
                     .fg(Color::Cyan)
                     .add_modifier(Modifier::BOLD),
             ),
-            Span::from(format!(
-                "{}",
-                match app.nacha_file.file_header.file_creation_date {
+            Span::from(
+                (match app.nacha_file.file_header.file_creation_date {
                     Some(d) => d.to_string(),
                     None => "no date provided".to_string(),
-                }
-            )),
+                })
+                .to_string(),
+            ),
         ]),
         Spans::from(vec![
             Span::styled(
@@ -95,13 +367,13 @@ where
                     .fg(Color::Cyan)
                     .add_modifier(Modifier::BOLD),
             ),
-            Span::from(format!(
-                "{}",
-                match app.nacha_file.file_header.file_creation_time {
+            Span::from(
+                (match app.nacha_file.file_header.file_creation_time {
                     Some(t) => format!("{}", t.format("%H:%M")),
                     None => "no time provided".to_string(),
-                }
-            )),
+                })
+                .to_string(),
+            ),
         ]),
         Spans::from(vec![
             Span::styled(
@@ -111,10 +383,13 @@ where
                     .add_modifier(Modifier::BOLD),
             ),
             Span::from(format!(
-                "{} ({})",
-                app.nacha_file.file_header.immediate_origin_name,
-                app.nacha_file.file_header.immediate_origin,
+                "{} ",
+                app.nacha_file.file_header.immediate_origin_name
             )),
+            Span::styled(
+                format!("({})", app.nacha_file.file_header.immediate_origin),
+                routing_style(app.nacha_file.file_header.origin_routing_number().is_ok()),
+            ),
         ]),
         Spans::from(vec![
             Span::styled(
@@ -124,10 +399,18 @@ where
                     .add_modifier(Modifier::BOLD),
             ),
             Span::from(format!(
-                "{} ({})",
-                app.nacha_file.file_header.immediate_destination_name,
-                app.nacha_file.file_header.immediate_destination,
+                "{} ",
+                app.nacha_file.file_header.immediate_destination_name
             )),
+            Span::styled(
+                format!("({})", app.nacha_file.file_header.immediate_destination),
+                routing_style(
+                    app.nacha_file
+                        .file_header
+                        .destination_routing_number()
+                        .is_ok(),
+                ),
+            ),
         ]),
     ];
     let block = Block::default().borders(Borders::ALL).title(Span::styled(
@@ -220,6 +503,112 @@ where
     f.render_widget(paragraph, chunks[1]);
 }
 
+/// the border of the pane the movement keys go to
+fn focus_style(focused: bool) -> Style {
+    if focused {
+        Style::default().fg(Color::Cyan)
+    } else {
+        Style::default()
+    }
+}
+
+fn draw_batch_tree<B>(f: &mut Frame<B>, area: Rect, app: &mut App)
+where
+    B: Backend,
+{
+    let mut items = vec![ListItem::new(Spans::from(vec![
+        Span::styled("All batches", Style::default().add_modifier(Modifier::BOLD)),
+        Span::raw(format!(" ({} entries)", app.all_entries.len())),
+    ]))];
+    for (batch, expanded) in app.nacha_file.batches.iter().zip(&app.expanded) {
+        items.push(ListItem::new(batch_tree_item(batch, *expanded)));
+    }
+
+    let list = List::new(items)
+        .block(
+            Block::default()
+                .title(Span::styled(
+                    "Batches",
+                    Style::default()
+                        .fg(Color::Magenta)
+                        .add_modifier(Modifier::BOLD),
+                ))
+                .borders(Borders::ALL)
+                .border_style(focus_style(app.focus == Focus::Batches)),
+        )
+        .highlight_style(Style::default().add_modifier(Modifier::BOLD))
+        .highlight_symbol("> ");
+    f.render_stateful_widget(list, area, &mut app.batches);
+}
+
+/// A batch's number, SEC code and company, followed when expanded by a
+/// summary of its header and control.
+fn batch_tree_item(batch: &Batch, expanded: bool) -> Vec<Spans<'static>> {
+    let header = &batch.batch_header;
+    let control = batch.batch_control();
+    let date = |d: Option<chrono::NaiveDate>| d.map(|d| d.to_string()).unwrap_or_default();
+    let label = |text: &str| {
+        Span::styled(
+            format!("    {:<13}: ", text),
+            Style::default().fg(Color::Cyan),
+        )
+    };
+    let mut lines = vec![Spans::from(vec![
+        Span::raw(if expanded { "▾ " } else { "▸ " }),
+        Span::raw(format!(
+            "{} {} {} ({})",
+            header.batch_number,
+            header.standard_entry_class_code,
+            // IAT batches have no company name, only the originator's id
+            if header.company_name.is_empty() {
+                &header.company_id
+            } else {
+                &header.company_name
+            },
+            batch.detail_entries.len()
+        )),
+    ])];
+    if expanded {
+        lines.extend([
+            Spans::from(vec![
+                label("company id"),
+                Span::raw(header.company_id.clone()),
+            ]),
+            Spans::from(vec![
+                label("description"),
+                Span::raw(header.company_entry_description.clone()),
+            ]),
+            Spans::from(vec![
+                label("effective"),
+                Span::raw(date(header.effective_entry_date)),
+            ]),
+            Spans::from(vec![
+                label("settlement"),
+                Span::raw(date(header.settlement_date)),
+            ]),
+            Spans::from(vec![
+                label("entry/addenda"),
+                Span::raw(control.entry_addenda_count.clone()),
+            ]),
+            Spans::from(vec![
+                label("total debit"),
+                Span::styled(
+                    control.total_debit.pretty_dollars_cents(),
+                    Style::default().fg(Color::Red),
+                ),
+            ]),
+            Spans::from(vec![
+                label("total credit"),
+                Span::styled(
+                    control.total_credit.pretty_dollars_cents(),
+                    Style::default().fg(Color::Green),
+                ),
+            ]),
+        ]);
+    }
+    lines
+}
+
 fn draw_file_contents<B>(f: &mut Frame<B>, area: Rect, app: &mut App)
 where
     B: Backend,
@@ -232,21 +621,39 @@ where
         .items
         .iter()
         .map(|e| {
-            let cells = parse_entry_into_cells(e);
+            let batch_header = &app.nacha_file.batches[e.batch].batch_header;
+            let cells = parse_entry_into_cells(e, batch_header, app.is_match(e));
             Row::new(cells)
         })
         .collect();
 
+    let mut title = match app.selected_batch() {
+        Some(b) => format!(
+            "Batch {}",
+            app.nacha_file.batches[b].batch_header.batch_number
+        ),
+        None => "File Contents".to_string(),
+    };
+    let direction = if app.descending { "↓" } else { "↑" };
+    title += &format!(" [sorted by {} {}]", app.sort.name(), direction);
+    if let Some(filter) = &app.filter {
+        title += &format!(" [{}: {} entries]", filter, app.entries.items.len());
+    }
+    if !app.search.is_empty() {
+        let matches = app.entries.items.iter().filter(|e| app.is_match(e)).count();
+        title += &format!(" [/{}: {} matches]", app.search, matches);
+    }
     let table = Table::new(items)
         .block(
             Block::default()
                 .title(Span::styled(
-                    "File Contents",
+                    title,
                     Style::default()
                         .fg(Color::Magenta)
                         .add_modifier(Modifier::BOLD),
                 ))
-                .borders(Borders::ALL),
+                .borders(Borders::ALL)
+                .border_style(focus_style(app.focus == Focus::Entries)),
         )
         .highlight_style(Style::default().add_modifier(Modifier::BOLD))
         .highlight_symbol("> ")
@@ -254,53 +661,52 @@ where
         .widths(&[
             Constraint::Ratio(6, 100),
             Constraint::Ratio(6, 100),
-            Constraint::Ratio(20, 100),
+            Constraint::Ratio(14, 100),
+            Constraint::Ratio(10, 100),
+            Constraint::Ratio(11, 100),
+            Constraint::Ratio(14, 100),
             Constraint::Ratio(12, 100),
-            Constraint::Ratio(15, 100),
             Constraint::Ratio(10, 100),
-            Constraint::Ratio(6, 100),
+            Constraint::Ratio(8, 100),
         ]);
     f.render_stateful_widget(table, area, &mut app.entries.state);
 
     if app.show_popup {
         let area = centered_rect(95, 50, f.size());
         // todo: add counter to addenda items
-        match app.entries.state.selected() {
-            Some(_) => {
-                let addenda_items: Vec<Row> = app
-                    .addenda_popup
-                    .items
-                    .iter()
-                    .map(|e| {
-                        let cells = parse_addendum_into_cells(e);
-                        Row::new(cells)
-                    })
-                    .collect();
+        if app.entries.state.selected().is_some() {
+            let addenda_items: Vec<Row> = app
+                .addenda_popup
+                .items
+                .iter()
+                .map(|e| {
+                    let cells = parse_addendum_into_cells(e);
+                    Row::new(cells)
+                })
+                .collect();
 
-                let addenda_table = Table::new(addenda_items)
-                    .block(
-                        Block::default()
-                            .title(Span::styled(
-                                "Addenda Info",
-                                Style::default()
-                                    .fg(Color::Magenta)
-                                    .add_modifier(Modifier::BOLD),
-                            ))
-                            .borders(Borders::ALL),
-                    )
-                    .highlight_style(Style::default().add_modifier(Modifier::BOLD))
-                    .highlight_symbol("> ")
-                    .header(Row::new(make_addenda_header()))
-                    .widths(&[
-                        Constraint::Ratio(8, 100),
-                        Constraint::Ratio(65, 100),
-                        Constraint::Ratio(10, 100),
-                        Constraint::Ratio(10, 100),
-                    ]);
-                f.render_widget(Clear, area); // this clears out the background
-                f.render_stateful_widget(addenda_table, area, &mut app.addenda_popup.state);
-            }
-            None => {}
+            let addenda_table = Table::new(addenda_items)
+                .block(
+                    Block::default()
+                        .title(Span::styled(
+                            "Addenda Info",
+                            Style::default()
+                                .fg(Color::Magenta)
+                                .add_modifier(Modifier::BOLD),
+                        ))
+                        .borders(Borders::ALL),
+                )
+                .highlight_style(Style::default().add_modifier(Modifier::BOLD))
+                .highlight_symbol("> ")
+                .header(Row::new(make_addenda_header()))
+                .widths(&[
+                    Constraint::Ratio(8, 100),
+                    Constraint::Ratio(65, 100),
+                    Constraint::Ratio(10, 100),
+                    Constraint::Ratio(10, 100),
+                ]);
+            f.render_widget(Clear, area); // this clears out the background
+            f.render_stateful_widget(addenda_table, area, &mut app.addenda_popup.state);
         };
     }
 }
@@ -308,57 +714,79 @@ where
 fn parse_addendum_into_cells(a: &Addendum) -> Vec<Cell<'static>> {
     let cells = vec![
         Cell::from(Span::styled(
-            format!("{}", a.addenda_type_code),
-            Style::default().fg(Color::Reset),
-        )),
-        Cell::from(Span::styled(
-            format!("{}", a.payment_related_info),
+            a.addenda_type_code.to_string(),
             Style::default().fg(Color::Reset),
         )),
+        Cell::from(Span::styled(a.summary(), Style::default().fg(Color::Reset))),
         Cell::from(Span::styled(
-            format!("{}", a.addenda_sequence_number),
+            a.addenda_sequence_number.to_string(),
             Style::default().fg(Color::Reset),
         )),
         Cell::from(Span::styled(
-            format!("{}", a.entry_detail_sequence_number),
+            a.entry_detail_sequence_number.to_string(),
             Style::default().fg(Color::Reset),
         )),
     ];
-    return cells;
+    cells
 }
 
-fn parse_entry_into_cells(e: &DetailEntryWithCounter) -> Vec<Cell<'static>> {
-    let code = &e.entry.transaction_code[..];
-    let color = match code {
-        "22" | "32" | "42" | "52" => Color::Green,
-        "27" | "37" | "47" => Color::Red,
-        _ => Color::Reset,
+/// the cells of an entry's row, its number highlighted if it matches the
+/// search
+fn parse_entry_into_cells(
+    e: &DetailEntryWithCounter,
+    batch_header: &BatchHeader,
+    is_match: bool,
+) -> Vec<Cell<'static>> {
+    let code = &e.entry.transaction_code;
+    let color = if code.is_prenote() {
+        Color::Reset
+    } else if code.is_credit() {
+        Color::Green
+    } else if code.is_debit() {
+        Color::Red
+    } else {
+        Color::Yellow
     };
     let cells = vec![
         Cell::from(Span::styled(
             format!("{}", e.counter),
-            Style::default().fg(Color::Reset),
+            if is_match {
+                Style::default().fg(Color::Black).bg(Color::Yellow)
+            } else {
+                Style::default().fg(Color::Reset)
+            },
         )),
         Cell::from(Span::styled(
-            format!("{}", e.entry.transaction_code),
+            e.entry.transaction_code.to_string(),
             Style::default().fg(color),
         )),
         Cell::from(Span::styled(
-            format!("{}", e.entry.individual_name),
+            e.entry.receiver_name().to_string(),
             Style::default().fg(Color::Reset),
         )),
         Cell::from(Span::styled(
-            format!("{}", e.entry.dfi_account_number),
+            format!("{}{}", e.entry.receiving_dfi_id, e.entry.check_digit),
+            routing_style(e.entry.rdfi_routing_number().is_ok()),
+        )),
+        Cell::from(Span::styled(
+            e.entry.dfi_account_number.to_string(),
             Style::default().fg(Color::Reset),
         )),
         Cell::from(Span::styled(
-            format!("{}", e.entry.trace_number),
+            e.entry.trace_number.to_string(),
             Style::default().fg(Color::Reset),
         )),
         Cell::from(Span::styled(
             format!("{:>13}", e.entry.amount.pretty_dollars_cents()),
             Style::default().fg(color),
         )),
+        Cell::from(Span::styled(
+            batch_header
+                .settlement_date
+                .map(|d| d.to_string())
+                .unwrap_or_default(),
+            Style::default().fg(Color::Reset),
+        )),
         Cell::from(Span::styled(
             format!(
                 "{:^8}",
@@ -371,31 +799,42 @@ fn parse_entry_into_cells(e: &DetailEntryWithCounter) -> Vec<Cell<'static>> {
             Style::default().fg(Color::Reset),
         )),
     ];
-    return cells;
+    cells
+}
+
+/// routing numbers that fail the check digit test stand out in red
+fn routing_style(valid: bool) -> Style {
+    if valid {
+        Style::default().fg(Color::Reset)
+    } else {
+        Style::default()
+            .fg(Color::Red)
+            .add_modifier(Modifier::BOLD | Modifier::UNDERLINED)
+    }
 }
 
 fn make_addenda_header() -> Vec<Cell<'static>> {
     vec![
         Cell::from(Span::styled(
-            format!("{}", "Type Code"),
+            "Type Code".to_string(),
             Style::default()
                 .add_modifier(Modifier::BOLD)
                 .fg(Color::Cyan),
         )),
         Cell::from(Span::styled(
-            format!("{}", "Payment Related Information"),
+            "Information".to_string(),
             Style::default()
                 .add_modifier(Modifier::BOLD)
                 .fg(Color::Cyan),
         )),
         Cell::from(Span::styled(
-            format!("{}", "Seq Num"),
+            "Seq Num".to_string(),
             Style::default()
                 .add_modifier(Modifier::BOLD)
                 .fg(Color::Cyan),
         )),
         Cell::from(Span::styled(
-            format!("{}", "Entry Seq Num"),
+            "Entry Seq Num".to_string(),
             Style::default()
                 .add_modifier(Modifier::BOLD)
                 .fg(Color::Cyan),
@@ -406,31 +845,37 @@ fn make_addenda_header() -> Vec<Cell<'static>> {
 fn make_header() -> Vec<Cell<'static>> {
     vec![
         Cell::from(Span::styled(
-            format!("{}", "Entry #"),
+            "Entry #".to_string(),
             Style::default()
                 .add_modifier(Modifier::BOLD)
                 .fg(Color::Cyan),
         )),
         Cell::from(Span::styled(
-            format!("{}", "TXN Code"),
+            "TXN Code".to_string(),
             Style::default()
                 .add_modifier(Modifier::BOLD)
                 .fg(Color::Cyan),
         )),
         Cell::from(Span::styled(
-            format!("{}", "Individual Name"),
+            "Individual Name".to_string(),
             Style::default()
                 .add_modifier(Modifier::BOLD)
                 .fg(Color::Cyan),
         )),
         Cell::from(Span::styled(
-            format!("{}", "DFI Acct #"),
+            "RDFI".to_string(),
             Style::default()
                 .add_modifier(Modifier::BOLD)
                 .fg(Color::Cyan),
         )),
         Cell::from(Span::styled(
-            format!("{}", "Trace #"),
+            "DFI Acct #".to_string(),
+            Style::default()
+                .add_modifier(Modifier::BOLD)
+                .fg(Color::Cyan),
+        )),
+        Cell::from(Span::styled(
+            "Trace #".to_string(),
             Style::default()
                 .add_modifier(Modifier::BOLD)
                 .fg(Color::Cyan),
@@ -442,7 +887,13 @@ fn make_header() -> Vec<Cell<'static>> {
                 .fg(Color::Cyan),
         )),
         Cell::from(Span::styled(
-            format!("{}", "Addenda?"),
+            "Settlement".to_string(),
+            Style::default()
+                .add_modifier(Modifier::BOLD)
+                .fg(Color::Cyan),
+        )),
+        Cell::from(Span::styled(
+            "Addenda?".to_string(),
             Style::default()
                 .add_modifier(Modifier::BOLD)
                 .fg(Color::Cyan),
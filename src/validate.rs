@@ -0,0 +1,570 @@
+use crate::control::FileTotals;
+use crate::layout::{self, EntryLayout, FieldType};
+use crate::{
+    Amount, Batch, BatchControl, Diagnostic, FileControl, FileHeader, NachaFile, Record,
+    RoutingNumberError, ServiceClassCode, Severity, WriteError,
+};
+use serde::Serialize;
+use std::fmt;
+
+/// A value in the file that doesn't match what it should be.
+#[derive(Debug, Serialize, Clone, PartialEq, Eq)]
+pub struct ValidationError {
+    /// the record holding the bad value, e.g. `batch_control`
+    pub record: &'static str,
+    /// batch the record belongs to, `None` for file level records
+    pub batch_number: Option<String>,
+    /// trace number of the entry the record belongs to, if any
+    pub trace_number: Option<String>,
+    pub field: &'static str,
+    pub expected: String,
+    pub actual: String,
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(batch_number) = &self.batch_number {
+            write!(f, "batch {}: ", batch_number)?;
+        }
+        if let Some(trace_number) = &self.trace_number {
+            write!(f, "entry {}: ", trace_number)?;
+        }
+        let actual = if self.actual.trim().is_empty() {
+            "(blank)"
+        } else {
+            &self.actual
+        };
+        write!(
+            f,
+            "{}.{} expected {} but found {}",
+            self.record, self.field, self.expected, actual
+        )
+    }
+}
+
+impl From<ValidationError> for Diagnostic {
+    fn from(e: ValidationError) -> Diagnostic {
+        Diagnostic {
+            severity: Severity::Error,
+            line: None,
+            field: Some(e.field),
+            range: None,
+            message: e.to_string(),
+        }
+    }
+}
+
+/// Collects mismatches for a single record, comparing numeric fields by
+/// value so that `000001` and `1` are equal.
+struct Checker<'a> {
+    errors: &'a mut Vec<ValidationError>,
+    record: &'static str,
+    batch_number: Option<String>,
+    trace_number: Option<String>,
+}
+
+impl<'a> Checker<'a> {
+    /// a field is only reported once per record, e.g. an unknown code that
+    /// isn't digits either
+    fn push(&mut self, field: &'static str, expected: String, actual: String) {
+        let reported = self
+            .errors
+            .iter()
+            .rev()
+            .take_while(|e| {
+                e.record == self.record
+                    && e.batch_number == self.batch_number
+                    && e.trace_number == self.trace_number
+            })
+            .any(|e| e.field == field);
+        if reported {
+            return;
+        }
+        self.errors.push(ValidationError {
+            record: self.record,
+            batch_number: self.batch_number.clone(),
+            trace_number: self.trace_number.clone(),
+            field,
+            expected,
+            actual,
+        });
+    }
+
+    /// reports a routing number that doesn't pass the check digit test
+    fn routing<T>(
+        &mut self,
+        field: &'static str,
+        value: &str,
+        result: Result<T, RoutingNumberError>,
+    ) {
+        match result {
+            Ok(_) => {}
+            Err(RoutingNumberError::CheckDigit { expected, .. }) => self.push(
+                field,
+                format!("check digit {}", expected),
+                value.to_string(),
+            ),
+            Err(RoutingNumberError::Malformed(_)) => {
+                self.push(field, "a routing number".to_string(), value.to_string())
+            }
+        }
+    }
+
+    /// Checks a written record against its layout: required fields have a
+    /// value and numeric ones hold only digits. A record that can't be
+    /// written because a value is too wide is reported as such.
+    fn layout(&mut self, record: Result<String, WriteError>, entries: EntryLayout) {
+        let record = match record {
+            Ok(record) => record,
+            Err(e) => {
+                let expected = format!("at most {} characters", e.width);
+                return self.push(e.field, expected, e.value);
+            }
+        };
+        for field in layout::for_record(&record, entries).unwrap_or_default() {
+            let value = field.text(&record);
+            let blank = value.trim().is_empty();
+            if field.required && blank {
+                self.push(field.name, "a value".to_string(), value);
+            } else if field.kind != FieldType::Alphanumeric
+                && !blank
+                && !value.chars().all(|c| c.is_ascii_digit())
+            {
+                self.push(field.name, "digits".to_string(), value);
+            }
+        }
+    }
+
+    /// reports a code that a lenient parse didn't know
+    fn code(&mut self, field: &'static str, expected: &str, code: &str, unknown: bool) {
+        if unknown {
+            self.push(field, expected.to_string(), code.to_string());
+        }
+    }
+
+    fn check(&mut self, field: &'static str, expected: impl ToString, actual: impl ToString) {
+        let (expected, actual) = (expected.to_string(), actual.to_string());
+        let equal = match (expected.trim().parse::<u64>(), actual.trim().parse::<u64>()) {
+            (Ok(e), Ok(a)) => e == a,
+            _ => expected.trim() == actual.trim(),
+        };
+        if !equal {
+            self.push(field, expected, actual);
+        }
+    }
+}
+
+impl Batch {
+    /// Checks each record against its layout, the ODFI and RDFI routing numbers and that each entry's
+    /// transaction code fits the batch, then recomputes the batch control
+    /// from the entries and reports every field that differs from the one in
+    /// the file.
+    pub fn validate(&self) -> Vec<ValidationError> {
+        let mut errors = Vec::new();
+        let batch_number = Some(self.batch_header.batch_number.clone());
+        let header = &self.batch_header;
+        let entries = header.entry_layout();
+        let mut checker = Checker {
+            errors: &mut errors,
+            record: "batch_header",
+            batch_number: batch_number.clone(),
+            trace_number: None,
+        };
+        checker.layout(header.to_record(), entries);
+        let (service_class, sec) = (
+            &header.service_class_code,
+            &header.standard_entry_class_code,
+        );
+        checker.code(
+            "service_class_code",
+            "a service class code",
+            service_class.as_str(),
+            service_class.is_unknown(),
+        );
+        checker.code(
+            "standard_entry_class_code",
+            "a standard entry class code",
+            sec.as_str(),
+            sec.is_unknown(),
+        );
+        checker.routing(
+            "originating_dfi_id",
+            &header.originating_dfi_id,
+            header.odfi_routing_number(),
+        );
+        for entry in &self.detail_entries {
+            let mut checker = Checker {
+                errors: &mut errors,
+                record: "entry_detail",
+                batch_number: batch_number.clone(),
+                trace_number: Some(entry.trace_number.clone()),
+            };
+            checker.layout(entry.to_record(), entries);
+            match entry.rdfi_routing_number() {
+                Err(RoutingNumberError::CheckDigit { expected, actual }) => {
+                    checker.push("check_digit", expected.to_string(), actual.to_string())
+                }
+                result => checker.routing(
+                    "receiving_dfi_id",
+                    &format!("{}{}", entry.receiving_dfi_id, entry.check_digit),
+                    result,
+                ),
+            }
+            let code = &entry.transaction_code;
+            checker.code(
+                "transaction_code",
+                "a transaction code",
+                code.as_str(),
+                code.is_unknown(),
+            );
+            if !header.service_class_code.allows(code) {
+                let kind = match header.service_class_code {
+                    ServiceClassCode::AutomatedAccountingAdvices => "an advice",
+                    _ if code.is_advice() => "a code other than an advice",
+                    _ if code.is_debit() => "a credit",
+                    _ => "a debit",
+                };
+                checker.push(
+                    "transaction_code",
+                    format!("{} for service class {}", kind, header.service_class_code),
+                    code.to_string(),
+                );
+            }
+            if code.is_prenote() || code.is_zero_dollar() {
+                checker.check("amount", Amount::ZERO, entry.amount);
+            }
+            checker.record = "addendum";
+            for addendum in &entry.addenda {
+                checker.layout(addendum.to_record(), entries);
+            }
+        }
+
+        let expected = self.compute_control();
+        let actual = &self.batch_control;
+        let mut checker = Checker {
+            errors: &mut errors,
+            record: "batch_control",
+            batch_number,
+            trace_number: None,
+        };
+        checker.layout(actual.to_record(), entries);
+        checker.check(
+            "service_class_code",
+            &expected.service_class_code,
+            &actual.service_class_code,
+        );
+        checker.check(
+            "entry_addenda_count",
+            &expected.entry_addenda_count,
+            &actual.entry_addenda_count,
+        );
+        checker.check("entry_hash", &expected.entry_hash, &actual.entry_hash);
+        checker.check("total_debit", expected.total_debit, actual.total_debit);
+        checker.check("total_credit", expected.total_credit, actual.total_credit);
+        errors
+    }
+}
+
+impl NachaFile {
+    /// Checks the file header and control against their layouts, and the
+    /// routing numbers in the file header and every batch, then
+    /// recomputes every batch control and the file control from the entries
+    /// and reports every field that differs from the one in the file.
+    pub fn validate(&self) -> Vec<ValidationError> {
+        let mut errors = Vec::new();
+        check_file_header(&self.file_header, &mut errors);
+        errors.extend(self.batches.iter().flat_map(|b| b.validate()));
+        check_file_control(&self.compute_control(), &self.file_control, &mut errors);
+        errors
+    }
+}
+
+fn check_file_header(header: &FileHeader, errors: &mut Vec<ValidationError>) {
+    let mut checker = Checker {
+        errors,
+        record: "file_header",
+        batch_number: None,
+        trace_number: None,
+    };
+    checker.layout(header.to_record(), EntryLayout::Standard);
+    checker.routing(
+        "immediate_destination",
+        &header.immediate_destination,
+        header.destination_routing_number(),
+    );
+    checker.routing(
+        "immediate_origin",
+        &header.immediate_origin,
+        header.origin_routing_number(),
+    );
+}
+
+fn check_file_control(
+    expected: &FileControl,
+    actual: &FileControl,
+    errors: &mut Vec<ValidationError>,
+) {
+    let mut checker = Checker {
+        errors,
+        record: "file_control",
+        batch_number: None,
+        trace_number: None,
+    };
+    checker.layout(actual.to_record(), EntryLayout::Standard);
+    checker.check("batch_count", expected.batch_count, actual.batch_count);
+    checker.check("block_count", expected.block_count, actual.block_count);
+    checker.check(
+        "entry_and_addenda_count",
+        expected.entry_and_addenda_count,
+        actual.entry_and_addenda_count,
+    );
+    checker.check("entry_hash", &expected.entry_hash, &actual.entry_hash);
+    checker.check("total_debit", expected.total_debit, actual.total_debit);
+    checker.check("total_credit", expected.total_credit, actual.total_credit);
+}
+
+/// Checks a file a record at a time, as read by a
+/// [`NachaReader`](crate::NachaReader), holding no more than one batch, and
+/// gives the same errors as [`NachaFile::validate`] would for the whole file.
+#[derive(Debug, Default)]
+pub struct Validator {
+    errors: Vec<ValidationError>,
+    file_header: Option<FileHeader>,
+    batch: Option<Batch>,
+    totals: FileTotals,
+    file_control: FileControl,
+}
+
+impl Validator {
+    pub fn new() -> Validator {
+        Validator::default()
+    }
+
+    /// Adds the next record. Each batch is checked once the next batch
+    /// header or the file control shows it's over. An entry, addendum or
+    /// batch control with no batch or entry to belong to is an error.
+    pub fn push(&mut self, record: Record) {
+        match record {
+            Record::FileHeader(header) => {
+                check_file_header(&header, &mut self.errors);
+                self.file_header = Some(header);
+            }
+            Record::BatchHeader(batch_header) => {
+                self.end_batch();
+                self.batch = Some(Batch {
+                    batch_header,
+                    detail_entries: Vec::new(),
+                    batch_control: BatchControl::default(),
+                });
+            }
+            Record::Entry(entry) => match &mut self.batch {
+                Some(batch) => batch.detail_entries.push(entry),
+                None => self.orphan("entry_detail", "a batch header", &entry.record_type_code),
+            },
+            Record::Addendum(addendum) => {
+                match self
+                    .batch
+                    .as_mut()
+                    .and_then(|b| b.detail_entries.last_mut())
+                {
+                    Some(entry) => entry.addenda.push(addendum),
+                    None => self.orphan("addendum", "an entry", &addendum.record_type_code),
+                }
+            }
+            Record::BatchControl(control) => match &mut self.batch {
+                Some(batch) => batch.batch_control = control,
+                None => self.orphan("batch_control", "a batch header", &control.record_type_code),
+            },
+            Record::FileControl(control) => {
+                self.end_batch();
+                self.file_control = control;
+            }
+        }
+    }
+
+    /// a record with nothing to belong to, which a parsed file can't hold
+    fn orphan(&mut self, record: &'static str, parent: &str, record_type_code: &str) {
+        self.errors.push(ValidationError {
+            record,
+            batch_number: self
+                .batch
+                .as_ref()
+                .map(|b| b.batch_header.batch_number.clone()),
+            trace_number: None,
+            field: "record_type_code",
+            expected: format!("{} before it", parent),
+            actual: record_type_code.to_string(),
+        });
+    }
+
+    fn end_batch(&mut self) {
+        if let Some(batch) = self.batch.take() {
+            self.errors.extend(batch.validate());
+            self.totals.add(&batch);
+        }
+    }
+
+    /// Checks the file control, and the file header if there wasn't one,
+    /// and gives every error found.
+    pub fn finish(mut self) -> Vec<ValidationError> {
+        self.end_batch();
+        let header = self.file_header.take().unwrap_or_else(|| {
+            let header = FileHeader::default();
+            check_file_header(&header, &mut self.errors);
+            header
+        });
+        let expected = self.totals.control(header.blocking_factor_or_default());
+        check_file_control(&expected, &self.file_control, &mut self.errors);
+        self.errors
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Addendum, NachaReader};
+
+    const SMALL: &str = include_str!("../examples/small.ach");
+
+    fn small() -> NachaFile {
+        NachaFile::parse(SMALL).unwrap()
+    }
+
+    /// (record, field, expected, actual) of each error
+    fn mismatches(errors: &[ValidationError]) -> Vec<(&str, &str, &str, &str)> {
+        errors
+            .iter()
+            .map(|e| (e.record, e.field, e.expected.as_str(), e.actual.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn valid_file_has_no_errors() {
+        assert_eq!(small().validate(), vec![]);
+    }
+
+    #[test]
+    fn reports_totals_that_dont_match_the_entries() {
+        let mut file = small();
+        file.batches[0].detail_entries[0].amount = Amount::from_cents(250);
+        let errors = file.validate();
+        assert_eq!(
+            mismatches(&errors),
+            vec![
+                ("batch_control", "total_debit", "2.50", "1000000.00"),
+                ("file_control", "total_debit", "2.50", "1000000.00"),
+            ]
+        );
+        assert_eq!(errors[0].batch_number.as_deref(), Some("0000001"));
+        assert_eq!(errors[1].batch_number, None);
+    }
+
+    #[test]
+    fn reports_counts_and_hash_that_dont_match() {
+        let mut file = small();
+        file.batches[0].batch_control.entry_addenda_count = "000002".to_string();
+        file.batches[0].batch_control.entry_hash = "0000000001".to_string();
+        file.file_control.batch_count = 2;
+        file.file_control.block_count = 2;
+        assert_eq!(
+            mismatches(&file.validate()),
+            vec![
+                ("batch_control", "entry_addenda_count", "000001", "000002"),
+                ("batch_control", "entry_hash", "0023138010", "0000000001"),
+                ("file_control", "batch_count", "1", "2"),
+                ("file_control", "block_count", "1", "2"),
+            ]
+        );
+    }
+
+    #[test]
+    fn numbers_are_compared_by_value() {
+        let mut file = small();
+        file.batches[0].batch_control.entry_hash = "23138010".to_string();
+        assert_eq!(file.validate(), vec![]);
+    }
+
+    #[test]
+    fn validator_gives_the_same_errors_as_the_whole_file() {
+        let content = SMALL.replace("         0100000000", "         0000000250");
+        let mut validator = Validator::new();
+        for record in NachaReader::new(content.as_bytes()) {
+            validator.push(record.unwrap().1);
+        }
+        let errors = validator.finish();
+        assert_eq!(errors, NachaFile::parse(&content).unwrap().validate());
+        assert_eq!(errors.len(), 2);
+    }
+
+    fn orphan_addendum() -> String {
+        format!("705{:<80}00010000001", "orphan")
+    }
+
+    /// what a `Validator` fed by a lenient reader and a leniently parsed
+    /// file each give
+    fn both_ways(content: &str) -> (Vec<ValidationError>, Vec<ValidationError>) {
+        let mut validator = Validator::new();
+        for (_, record) in NachaReader::lenient(content.as_bytes()).flatten() {
+            validator.push(record);
+        }
+        let (file, _) = NachaFile::parse_lenient(content);
+        (validator.finish(), file.validate())
+    }
+
+    #[test]
+    fn validator_agrees_with_the_whole_file() {
+        let (streamed, whole) = both_ways(include_str!("../examples/full.ach"));
+        assert_eq!(streamed, whole);
+        assert_eq!(streamed, vec![]);
+
+        // an addendum before any entry is left out both ways
+        let mut lines: Vec<&str> = SMALL.lines().collect();
+        let addendum = orphan_addendum();
+        lines.insert(2, &addendum);
+        let (streamed, whole) = both_ways(&lines.join("\n"));
+        assert_eq!(streamed, whole);
+        assert_eq!(streamed, vec![]);
+    }
+
+    #[test]
+    fn validator_reports_orphan_records() {
+        let file = small();
+        let batch = &file.batches[0];
+        let mut validator = Validator::new();
+        validator.push(Record::FileHeader(file.file_header.clone()));
+        validator.push(Record::Entry(batch.detail_entries[0].clone()));
+        validator.push(Record::BatchControl(batch.batch_control.clone()));
+        validator.push(Record::BatchHeader(batch.batch_header.clone()));
+        let addendum = Addendum::parse(&orphan_addendum(), 5).unwrap();
+        validator.push(Record::Addendum(addendum));
+        let errors = validator.finish();
+        let orphans: Vec<_> = errors
+            .iter()
+            .filter(|e| e.field == "record_type_code")
+            .map(|e| (e.record, e.batch_number.as_deref(), e.to_string()))
+            .collect();
+        assert_eq!(
+            orphans,
+            vec![
+                (
+                    "entry_detail",
+                    None,
+                    "entry_detail.record_type_code expected a batch header before it but found 6"
+                        .to_string()
+                ),
+                (
+                    "batch_control",
+                    None,
+                    "batch_control.record_type_code expected a batch header before it but found 8"
+                        .to_string()
+                ),
+                (
+                    "addendum",
+                    Some("0000001"),
+                    "batch 0000001: addendum.record_type_code expected an entry before it but found 7"
+                        .to_string()
+                ),
+            ]
+        );
+    }
+}
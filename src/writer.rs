@@ -0,0 +1,729 @@
+use crate::layout::{
+    addendum, adv_entry_detail, batch_control, batch_header, entry_detail, file_control,
+    file_header, iat_batch_header, iat_entry_detail, EntryLayout, Field, FieldType,
+};
+use crate::{
+    Addendum, AdvEntryDetail, BatchControl, BatchControlRef, BatchHeader, DetailEntry, FileControl,
+    FileControlRef, FileHeader, FileHeaderRef, IatBatchHeader, IatEntryDetail, Line, NachaFile,
+    Record, WriteError,
+};
+use chrono::{NaiveDate, NaiveTime};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::ops::Range;
+
+pub const RECORD_SIZE: usize = 94;
+const DEFAULT_BLOCKING_FACTOR: usize = 10;
+
+/// Left-justified and space-padded.
+fn alpha(value: &str, width: usize) -> String {
+    format!("{:<width$}", value, width = width)
+}
+
+/// Right-justified and zero-padded. Blank fields stay blank.
+fn numeric(value: &str, width: usize) -> String {
+    if value.is_empty() {
+        return " ".repeat(width);
+    }
+    format!("{:0>width$}", value, width = width)
+}
+
+/// Right-justified and space-padded, as used by the immediate destination
+/// and origin (` 123456789`).
+fn right(value: &str, width: usize) -> String {
+    format!("{:>width$}", value, width = width)
+}
+
+/// A record being written, field by field, into a line of spaces. Fields
+/// left unset stay blank.
+pub(crate) struct RecordWriter(Vec<char>);
+
+impl RecordWriter {
+    pub(crate) fn new() -> RecordWriter {
+        RecordWriter(vec![' '; RECORD_SIZE])
+    }
+
+    /// Pads `value` to the field's width according to its type. A value
+    /// wider than the field is an error rather than being cut short.
+    pub(crate) fn put(self, field: Field, value: &str) -> Result<RecordWriter, WriteError> {
+        self.check(field, value)?;
+        let text = match field.kind {
+            FieldType::Numeric => numeric(value, field.width()),
+            FieldType::Alphanumeric | FieldType::Date => alpha(value, field.width()),
+        };
+        Ok(self.splice(field, &text))
+    }
+
+    /// Right-justifies `value`, for the immediate destination and origin.
+    pub(crate) fn put_right(self, field: Field, value: &str) -> Result<RecordWriter, WriteError> {
+        self.check(field, value)?;
+        Ok(self.splice(field, &right(value, field.width())))
+    }
+
+    /// widths count characters, not bytes
+    fn check(&self, field: Field, value: &str) -> Result<(), WriteError> {
+        if value.chars().count() <= field.width() {
+            return Ok(());
+        }
+        Err(WriteError {
+            record_type: self.0[0].to_string(),
+            field: field.name,
+            width: field.width(),
+            value: value.to_string(),
+        })
+    }
+
+    fn splice(mut self, field: Field, text: &str) -> RecordWriter {
+        for (c, t) in self.0[field.range()].iter_mut().zip(text.chars()) {
+            *c = t;
+        }
+        self
+    }
+
+    pub(crate) fn finish(self) -> String {
+        self.0.into_iter().collect()
+    }
+}
+
+pub(crate) fn date(value: &Option<NaiveDate>) -> String {
+    match value {
+        Some(d) => d.format("%y%m%d").to_string(),
+        None => " ".repeat(6),
+    }
+}
+
+/// day of the year, as the settlement date is written
+pub(crate) fn julian(value: &Option<NaiveDate>) -> String {
+    match value {
+        Some(d) => d.format("%j").to_string(),
+        None => " ".repeat(3),
+    }
+}
+
+pub(crate) fn time(value: &Option<NaiveTime>) -> String {
+    match value {
+        Some(t) => t.format("%H%M").to_string(),
+        None => " ".repeat(4),
+    }
+}
+
+/// Where a record sits in a file, to look up the text it was parsed from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) enum Position {
+    FileHeader,
+    BatchHeader(usize),
+    Entry(usize, usize),
+    Addendum(usize, usize, usize),
+    BatchControl(usize),
+    FileControl,
+}
+
+/// The lines of a parsed file that each record was read from, so that
+/// records which haven't changed are written back exactly as they were.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Sources {
+    ranges: HashMap<Position, Range<usize>>,
+    /// whether every line read became a record still in the file, so that
+    /// an unchanged file can be written back whole, filler and all
+    complete: bool,
+}
+
+impl Sources {
+    pub(crate) fn new() -> Sources {
+        Sources {
+            ranges: HashMap::new(),
+            complete: true,
+        }
+    }
+
+    pub(crate) fn insert(&mut self, position: Position, range: Range<usize>) {
+        if self.ranges.insert(position, range).is_some() {
+            self.complete = false;
+        }
+    }
+
+    /// notes a line that didn't become a record
+    pub(crate) fn skip(&mut self) {
+        self.complete = false;
+    }
+}
+
+/// The records of a file being written, taking each from its source text
+/// when it's unchanged.
+struct Records<'a> {
+    raw: &'a str,
+    sources: &'a Sources,
+    records: Vec<String>,
+    /// whether every record so far is as it was parsed
+    unchanged: bool,
+}
+
+impl Records<'_> {
+    fn push(&mut self, position: Position, fresh: String, entries: EntryLayout) {
+        let source = self
+            .sources
+            .ranges
+            .get(&position)
+            .map(|r| &self.raw[r.clone()]);
+        match source {
+            Some(source)
+                if fresh == source || rewritten(source, entries) == Some(fresh.clone()) =>
+            {
+                self.records.push(source.to_string());
+                return;
+            }
+            // a record missing from the input, such as the controls of a
+            // partial file, is unchanged as long as it's still blank
+            None if fresh == blank(position) => {}
+            _ => self.unchanged = false,
+        }
+        self.records.push(fresh);
+    }
+}
+
+/// `source` read and written back, which is how a record parsed from it
+/// is written as long as it's unchanged
+fn rewritten(source: &str, entries: EntryLayout) -> Option<String> {
+    let sink = RefCell::new(Vec::new());
+    let line = Line::lenient(source, 0, Some(&sink));
+    Record::from_line(&line, entries).ok()?.to_record().ok()
+}
+
+/// how a record the parser filled in for a missing one is written
+fn blank(position: Position) -> String {
+    let record = match position {
+        Position::FileHeader => FileHeaderRef::default().into_owned().to_record(),
+        Position::BatchControl(_) => BatchControlRef::default().into_owned().to_record(),
+        Position::FileControl => FileControlRef::default().into_owned().to_record(),
+        _ => return String::new(),
+    };
+    record.unwrap_or_default()
+}
+
+impl NachaFile {
+    /// Writes the file out as fixed-width 94 character records, each ending
+    /// in a newline and padded with lines of `9`s up to a multiple of the
+    /// blocking factor. Records that haven't changed since the file was
+    /// parsed are written as they were found, and a file that hasn't
+    /// changed at all is written back byte for byte. Fails if any value is
+    /// too wide for its field.
+    pub fn to_ach_string(&self) -> Result<String, WriteError> {
+        let mut records = Records {
+            raw: &self.raw,
+            sources: &self.sources,
+            records: Vec::new(),
+            unchanged: self.sources.complete,
+        };
+        let standard = EntryLayout::Standard;
+        records.push(
+            Position::FileHeader,
+            self.file_header.to_record()?,
+            standard,
+        );
+        for (b, batch) in self.batches.iter().enumerate() {
+            let header = &batch.batch_header;
+            records.push(Position::BatchHeader(b), header.to_record()?, standard);
+            for (e, entry) in batch.detail_entries.iter().enumerate() {
+                let entries = header.entry_layout();
+                records.push(Position::Entry(b, e), entry.to_record()?, entries);
+                for (a, addendum) in entry.addenda.iter().enumerate() {
+                    let position = Position::Addendum(b, e, a);
+                    records.push(position, addendum.to_record()?, standard);
+                }
+            }
+            let control = batch.batch_control.to_record()?;
+            records.push(Position::BatchControl(b), control, standard);
+        }
+        let control = self.file_control.to_record()?;
+        records.push(Position::FileControl, control, standard);
+        if records.unchanged {
+            return Ok(self.raw.clone());
+        }
+
+        let mut records = records.records;
+        let blocking_factor = self.file_header.blocking_factor_or_default();
+        while records.len() % blocking_factor != 0 {
+            records.push("9".repeat(RECORD_SIZE));
+        }
+        let mut out = records.join("\n");
+        out.push('\n');
+        Ok(out)
+    }
+}
+
+impl Record {
+    pub fn to_record(&self) -> Result<String, WriteError> {
+        match self {
+            Record::FileHeader(r) => r.to_record(),
+            Record::BatchHeader(r) => r.to_record(),
+            Record::Entry(r) => r.to_record(),
+            Record::Addendum(r) => r.to_record(),
+            Record::BatchControl(r) => r.to_record(),
+            Record::FileControl(r) => r.to_record(),
+        }
+    }
+}
+
+impl FileHeader {
+    pub(crate) fn blocking_factor_or_default(&self) -> usize {
+        match self.blocking_factor.parse() {
+            Ok(0) | Err(_) => DEFAULT_BLOCKING_FACTOR,
+            Ok(n) => n,
+        }
+    }
+
+    pub fn to_record(&self) -> Result<String, WriteError> {
+        Ok(RecordWriter::new()
+            .put(file_header::RECORD_TYPE_CODE, &self.record_type_code)?
+            .put(file_header::PRIORITY_CODE, &self.priority_code)?
+            .put_right(
+                file_header::IMMEDIATE_DESTINATION,
+                &self.immediate_destination,
+            )?
+            .put_right(file_header::IMMEDIATE_ORIGIN, &self.immediate_origin)?
+            .put(
+                file_header::FILE_CREATION_DATE,
+                &date(&self.file_creation_date),
+            )?
+            .put(
+                file_header::FILE_CREATION_TIME,
+                &time(&self.file_creation_time),
+            )?
+            .put(file_header::FILE_ID_MODIFIER, &self.file_id_modifier)?
+            .put(file_header::RECORD_SIZE, &self.record_size)?
+            .put(file_header::BLOCKING_FACTOR, &self.blocking_factor)?
+            .put(file_header::FORMAT_CODE, &self.format_code)?
+            .put(
+                file_header::IMMEDIATE_DESTINATION_NAME,
+                &self.immediate_destination_name,
+            )?
+            .put(
+                file_header::IMMEDIATE_ORIGIN_NAME,
+                &self.immediate_origin_name,
+            )?
+            .put(file_header::REFERENCE_CODE, &self.reference_code)?
+            .finish())
+    }
+}
+
+impl BatchHeader {
+    pub fn to_record(&self) -> Result<String, WriteError> {
+        if let Some(iat) = &self.iat {
+            return self.to_iat_record(iat);
+        }
+        Ok(RecordWriter::new()
+            .put(batch_header::RECORD_TYPE_CODE, &self.record_type_code)?
+            .put(
+                batch_header::SERVICE_CLASS_CODE,
+                self.service_class_code.as_str(),
+            )?
+            .put(batch_header::COMPANY_NAME, &self.company_name)?
+            .put(
+                batch_header::COMPANY_DISCRETIONARY_DATA,
+                &self.company_discretionary_data,
+            )?
+            .put(batch_header::COMPANY_ID, &self.company_id)?
+            .put(
+                batch_header::STANDARD_ENTRY_CLASS_CODE,
+                self.standard_entry_class_code.as_str(),
+            )?
+            .put(
+                batch_header::COMPANY_ENTRY_DESCRIPTION,
+                &self.company_entry_description,
+            )?
+            .put(
+                batch_header::COMPANY_DESCRIPTIVE_DATE,
+                &self.company_descriptive_date,
+            )?
+            .put(
+                batch_header::EFFECTIVE_ENTRY_DATE,
+                &date(&self.effective_entry_date),
+            )?
+            .put(
+                batch_header::SETTLEMENT_DATE,
+                &julian(&self.settlement_date),
+            )?
+            .put(
+                batch_header::ORIGINATOR_STATUS_CODE,
+                &self.originator_status_code,
+            )?
+            .put(batch_header::ORIGINATING_DFI_ID, &self.originating_dfi_id)?
+            .put(batch_header::BATCH_NUMBER, &self.batch_number)?
+            .finish())
+    }
+
+    fn to_iat_record(&self, iat: &IatBatchHeader) -> Result<String, WriteError> {
+        Ok(RecordWriter::new()
+            .put(iat_batch_header::RECORD_TYPE_CODE, &self.record_type_code)?
+            .put(
+                iat_batch_header::SERVICE_CLASS_CODE,
+                self.service_class_code.as_str(),
+            )?
+            .put(iat_batch_header::IAT_INDICATOR, &iat.iat_indicator)?
+            .put(
+                iat_batch_header::FOREIGN_EXCHANGE_INDICATOR,
+                &iat.foreign_exchange_indicator,
+            )?
+            .put(
+                iat_batch_header::FOREIGN_EXCHANGE_REFERENCE_INDICATOR,
+                &iat.foreign_exchange_reference_indicator,
+            )?
+            .put(
+                iat_batch_header::FOREIGN_EXCHANGE_REFERENCE,
+                &iat.foreign_exchange_reference,
+            )?
+            .put(
+                iat_batch_header::ISO_DESTINATION_COUNTRY_CODE,
+                &iat.iso_destination_country_code,
+            )?
+            .put(
+                iat_batch_header::ORIGINATOR_IDENTIFICATION,
+                &self.company_id,
+            )?
+            .put(
+                iat_batch_header::STANDARD_ENTRY_CLASS_CODE,
+                self.standard_entry_class_code.as_str(),
+            )?
+            .put(
+                iat_batch_header::COMPANY_ENTRY_DESCRIPTION,
+                &self.company_entry_description,
+            )?
+            .put(
+                iat_batch_header::ISO_ORIGINATING_CURRENCY_CODE,
+                &iat.iso_originating_currency_code,
+            )?
+            .put(
+                iat_batch_header::ISO_DESTINATION_CURRENCY_CODE,
+                &iat.iso_destination_currency_code,
+            )?
+            .put(
+                iat_batch_header::EFFECTIVE_ENTRY_DATE,
+                &date(&self.effective_entry_date),
+            )?
+            .put(
+                iat_batch_header::SETTLEMENT_DATE,
+                &julian(&self.settlement_date),
+            )?
+            .put(
+                iat_batch_header::ORIGINATOR_STATUS_CODE,
+                &self.originator_status_code,
+            )?
+            .put(
+                iat_batch_header::ORIGINATING_DFI_ID,
+                &self.originating_dfi_id,
+            )?
+            .put(iat_batch_header::BATCH_NUMBER, &self.batch_number)?
+            .finish())
+    }
+}
+
+impl DetailEntry {
+    pub fn to_record(&self) -> Result<String, WriteError> {
+        if let Some(iat) = &self.iat {
+            return self.to_iat_record(iat);
+        }
+        if let Some(adv) = &self.adv {
+            return self.to_adv_record(adv);
+        }
+        Ok(RecordWriter::new()
+            .put(entry_detail::RECORD_TYPE_CODE, &self.record_type_code)?
+            .put(
+                entry_detail::TRANSACTION_CODE,
+                self.transaction_code.as_str(),
+            )?
+            .put(entry_detail::RECEIVING_DFI_ID, &self.receiving_dfi_id)?
+            .put(entry_detail::CHECK_DIGIT, &self.check_digit)?
+            .put(entry_detail::DFI_ACCOUNT_NUMBER, &self.dfi_account_number)?
+            .put(entry_detail::AMOUNT, &self.amount.cents().to_string())?
+            .put(
+                entry_detail::INDIVIDUAL_ID_NUMBER,
+                &self.individual_id_number,
+            )?
+            .put(entry_detail::INDIVIDUAL_NAME, &self.individual_name)?
+            .put(entry_detail::DISCRETIONARY_DATA, &self.discretionary_data)?
+            .put(
+                entry_detail::ADDENDA_RECORD_INDICATOR,
+                &self.addenda_record_indicator,
+            )?
+            .put(entry_detail::TRACE_NUMBER, &self.trace_number)?
+            .finish())
+    }
+
+    fn to_iat_record(&self, iat: &IatEntryDetail) -> Result<String, WriteError> {
+        Ok(RecordWriter::new()
+            .put(iat_entry_detail::RECORD_TYPE_CODE, &self.record_type_code)?
+            .put(
+                iat_entry_detail::TRANSACTION_CODE,
+                self.transaction_code.as_str(),
+            )?
+            .put(iat_entry_detail::RECEIVING_DFI_ID, &self.receiving_dfi_id)?
+            .put(iat_entry_detail::CHECK_DIGIT, &self.check_digit)?
+            .put(iat_entry_detail::NUMBER_OF_ADDENDA, &iat.number_of_addenda)?
+            .put(iat_entry_detail::AMOUNT, &self.amount.cents().to_string())?
+            .put(
+                iat_entry_detail::FOREIGN_RECEIVER_ACCOUNT_NUMBER,
+                &self.dfi_account_number,
+            )?
+            .put(
+                iat_entry_detail::GATEWAY_OFAC_SCREENING_INDICATOR,
+                &iat.gateway_ofac_screening_indicator,
+            )?
+            .put(
+                iat_entry_detail::SECONDARY_OFAC_SCREENING_INDICATOR,
+                &iat.secondary_ofac_screening_indicator,
+            )?
+            .put(
+                iat_entry_detail::ADDENDA_RECORD_INDICATOR,
+                &self.addenda_record_indicator,
+            )?
+            .put(iat_entry_detail::TRACE_NUMBER, &self.trace_number)?
+            .finish())
+    }
+
+    fn to_adv_record(&self, adv: &AdvEntryDetail) -> Result<String, WriteError> {
+        Ok(RecordWriter::new()
+            .put(adv_entry_detail::RECORD_TYPE_CODE, &self.record_type_code)?
+            .put(
+                adv_entry_detail::TRANSACTION_CODE,
+                self.transaction_code.as_str(),
+            )?
+            .put(adv_entry_detail::RECEIVING_DFI_ID, &self.receiving_dfi_id)?
+            .put(adv_entry_detail::CHECK_DIGIT, &self.check_digit)?
+            .put(
+                adv_entry_detail::DFI_ACCOUNT_NUMBER,
+                &self.dfi_account_number,
+            )?
+            .put(adv_entry_detail::AMOUNT, &self.amount.cents().to_string())?
+            .put(
+                adv_entry_detail::ADVICE_ROUTING_NUMBER,
+                &adv.advice_routing_number,
+            )?
+            .put(
+                adv_entry_detail::FILE_IDENTIFICATION,
+                &adv.file_identification,
+            )?
+            .put(adv_entry_detail::ACH_OPERATOR_DATA, &adv.ach_operator_data)?
+            .put(adv_entry_detail::INDIVIDUAL_NAME, &self.individual_name)?
+            .put(
+                adv_entry_detail::DISCRETIONARY_DATA,
+                &self.discretionary_data,
+            )?
+            .put(
+                adv_entry_detail::ADDENDA_RECORD_INDICATOR,
+                &self.addenda_record_indicator,
+            )?
+            .put(
+                adv_entry_detail::ACH_OPERATOR_ROUTING_NUMBER,
+                &adv.ach_operator_routing_number,
+            )?
+            .put(
+                adv_entry_detail::JULIAN_DATE_CREATED,
+                &adv.julian_date_created,
+            )?
+            .put(adv_entry_detail::SEQUENCE_NUMBER, &adv.sequence_number)?
+            .finish())
+    }
+}
+
+impl Addendum {
+    pub fn to_record(&self) -> Result<String, WriteError> {
+        let record = RecordWriter::new()
+            .put(addendum::RECORD_TYPE_CODE, &self.record_type_code)?
+            .put(addendum::ADDENDA_TYPE_CODE, &self.addenda_type_code)?;
+        let record = match &self.detail {
+            // types 02, 98 and 99 end in a trace number rather than sequence
+            // numbers, which the detail writes itself
+            Some(detail) if detail.trace_number().is_some() => {
+                return Ok(detail.write(record)?.finish())
+            }
+            Some(detail) if !detail.is_free_text() => detail.write(record)?,
+            // free text is only ever written from `payment_related_info`
+            _ => record.put(addendum::PAYMENT_RELATED_INFO, &self.payment_related_info)?,
+        };
+        Ok(record
+            .put(
+                addendum::ADDENDA_SEQUENCE_NUMBER,
+                &self.addenda_sequence_number,
+            )?
+            .put(
+                addendum::ENTRY_DETAIL_SEQUENCE_NUMBER,
+                &self.entry_detail_sequence_number,
+            )?
+            .finish())
+    }
+}
+
+impl BatchControl {
+    pub fn to_record(&self) -> Result<String, WriteError> {
+        Ok(RecordWriter::new()
+            .put(batch_control::RECORD_TYPE_CODE, &self.record_type_code)?
+            .put(
+                batch_control::SERVICE_CLASS_CODE,
+                self.service_class_code.as_str(),
+            )?
+            .put(
+                batch_control::ENTRY_ADDENDA_COUNT,
+                &self.entry_addenda_count,
+            )?
+            .put(batch_control::ENTRY_HASH, &self.entry_hash)?
+            .put(
+                batch_control::TOTAL_DEBIT,
+                &self.total_debit.cents().to_string(),
+            )?
+            .put(
+                batch_control::TOTAL_CREDIT,
+                &self.total_credit.cents().to_string(),
+            )?
+            .put(batch_control::COMPANY_ID, &self.company_id)?
+            .put(
+                batch_control::MESSAGE_AUTHENTICATION_CODE,
+                &self.message_authentication_code,
+            )?
+            .put(batch_control::RESERVED, &self.reserved)?
+            .put(batch_control::ORIGINATING_DFI_ID, &self.originating_dfi_id)?
+            .put(batch_control::BATCH_NUMBER, &self.batch_number)?
+            .finish())
+    }
+}
+
+impl FileControl {
+    pub fn to_record(&self) -> Result<String, WriteError> {
+        Ok(RecordWriter::new()
+            .put(file_control::RECORD_TYPE_CODE, &self.record_type_code)?
+            .put(file_control::BATCH_COUNT, &self.batch_count.to_string())?
+            .put(file_control::BLOCK_COUNT, &self.block_count.to_string())?
+            .put(
+                file_control::ENTRY_AND_ADDENDA_COUNT,
+                &self.entry_and_addenda_count.to_string(),
+            )?
+            .put(file_control::ENTRY_HASH, &self.entry_hash)?
+            .put(
+                file_control::TOTAL_DEBIT,
+                &self.total_debit.cents().to_string(),
+            )?
+            .put(
+                file_control::TOTAL_CREDIT,
+                &self.total_credit.cents().to_string(),
+            )?
+            .put(file_control::RESERVED, &self.reserved)?
+            .finish())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::EXAMPLES;
+    use crate::{Amount, StandardEntryClass};
+
+    fn round_trip(content: &str) -> String {
+        NachaFile::parse(content).unwrap().to_ach_string().unwrap()
+    }
+
+    #[test]
+    fn writes_examples_back_exactly() {
+        for content in EXAMPLES {
+            assert_eq!(round_trip(content), content);
+            let (file, _) = NachaFile::parse_lenient(content);
+            assert_eq!(file.to_ach_string().unwrap(), content);
+        }
+    }
+
+    #[test]
+    fn keeps_the_text_of_unchanged_records() {
+        let small = include_str!("../examples/small.ach");
+        let content = small
+            // a name with a leading space
+            .replace(
+                "0100000000               Receiver",
+                "0100000000                Receiver",
+            )
+            .replace("Account Name   0121", "Account Name  0121")
+            // a date that doesn't parse
+            .replace("190625   1", "1906XX   1")
+            // a batch number that isn't zero-padded
+            .replace("1121042880000001\n", "112104288      1\n");
+        assert_eq!(round_trip(&content), content);
+
+        let mut file = NachaFile::parse(&content).unwrap();
+        file.batches[0].detail_entries[0].amount = Amount::from_cents(200000);
+        let written = file.to_ach_string().unwrap();
+        let (lines, expected): (Vec<_>, Vec<_>) =
+            (written.lines().collect(), content.lines().collect());
+        assert_eq!(lines.len(), expected.len());
+        for (i, (line, expected)) in lines.iter().zip(&expected).enumerate() {
+            if i == 2 {
+                assert_eq!(&line[29..39], "0000200000");
+            } else {
+                assert_eq!(line, expected);
+            }
+        }
+        assert!(written.ends_with('\n'));
+    }
+
+    #[test]
+    fn reads_and_writes_adv_entries_in_their_own_layout() {
+        let content = include_str!("../examples/adv.ach");
+        let file = NachaFile::parse(content).unwrap();
+        let batch = &file.batches[0];
+        assert_eq!(
+            batch.batch_header.standard_entry_class_code,
+            StandardEntryClass::AutomatedAccounting
+        );
+        let entry = &batch.detail_entries[0];
+        assert_eq!(entry.dfi_account_number, "12345678");
+        assert_eq!(entry.amount, Amount::from_cents(100000));
+        assert_eq!(entry.individual_name, "Receiver Bank Name");
+        let adv = entry.adv.as_ref().unwrap();
+        assert_eq!(adv.advice_routing_number, "121042882");
+        assert_eq!(adv.file_identification, "A0001");
+        assert_eq!(adv.ach_operator_routing_number, "01100001");
+        assert_eq!(adv.julian_date_created, "175");
+        assert_eq!(adv.sequence_number, "0001");
+        assert!(file.validate().is_empty());
+        assert_eq!(round_trip(content), content);
+    }
+
+    #[test]
+    fn pads_edited_files_with_filler() {
+        for content in [
+            include_str!("../examples/large.ach"),
+            include_str!("../examples/large_no_addenda.ach"),
+        ] {
+            let mut file = NachaFile::parse(content).unwrap();
+            file.batches[0].detail_entries[0].individual_name = "Someone Else".to_string();
+            let written = file.to_ach_string().unwrap();
+            let records = content.lines().count();
+            assert_eq!(written.lines().count() % 10, 0);
+            assert!(written
+                .lines()
+                .skip(records)
+                .all(|l| l == "9".repeat(RECORD_SIZE)));
+            assert!(written.ends_with('\n'));
+        }
+    }
+
+    #[test]
+    fn rejects_values_wider_than_their_field() {
+        let mut file = NachaFile::parse(include_str!("../examples/small.ach")).unwrap();
+        file.batches[0].batch_header.company_name = "Much Too Long Company".to_string();
+        let e = file.to_ach_string().unwrap_err();
+        assert_eq!((e.field, e.width), ("company_name", 16));
+        assert_eq!(e.record_type, "5");
+
+        // width is counted in characters, so non-ASCII text fits as usual
+        file.batches[0].batch_header.company_name = "Señor Café".to_string();
+        let written = file.to_ach_string().unwrap();
+        let header = written.lines().nth(1).unwrap();
+        assert_eq!(header.chars().count(), RECORD_SIZE);
+        assert_eq!(
+            NachaFile::parse(&written).unwrap().batches[0]
+                .batch_header
+                .company_name,
+            "Señor Café"
+        );
+        file.batches[0].detail_entries[0].trace_number = "é".repeat(16);
+        let e = file.to_ach_string().unwrap_err();
+        assert_eq!((e.field, e.width), ("trace_number", 15));
+    }
+}